@@ -0,0 +1,116 @@
+//! Encodes rendered pixmaps as the frames of an animated GIF or APNG, for
+//! codes that cycle on a screen: Structured Append parts shown one after
+//! another, or a reveal that ends on the final symbol. Every frame replaces
+//! the previous one, so frames with transparent pixels don't pile up.
+
+use tiny_skia::Pixmap;
+
+use crate::error::QrError;
+use crate::raster;
+
+/// A container for an animation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn parse(format: &str) -> Result<Self, QrError> {
+        match format.to_lowercase().as_str() {
+            "gif" => Ok(AnimationFormat::Gif),
+            "apng" | "png" => Ok(AnimationFormat::Apng),
+            other => Err(QrError::InvalidOption(format!(
+                "Unsupported animation format '{}': expected gif or apng",
+                other
+            ))),
+        }
+    }
+}
+
+/// One rendered frame and how long it stays on screen, in milliseconds.
+pub struct AnimatedFrame {
+    pub pixmap: Pixmap,
+    pub delay: u32,
+}
+
+/// Encode `frames`, all the size of the first, as `format`. `plays` is how
+/// many times the animation runs; 0 loops forever.
+pub fn encode(frames: &[AnimatedFrame], format: AnimationFormat, plays: u32) -> Result<Vec<u8>, QrError> {
+    if frames.is_empty() {
+        return Err(QrError::InvalidOption("An animation needs at least one frame".to_string()));
+    }
+    match format {
+        AnimationFormat::Gif => encode_gif(frames, plays),
+        AnimationFormat::Apng => encode_apng(frames, plays),
+    }
+}
+
+fn encode_gif(frames: &[AnimatedFrame], plays: u32) -> Result<Vec<u8>, QrError> {
+    let error = |e: gif::EncodingError| QrError::Encode(format!("Failed to encode GIF: {}", e));
+    let (width, height) = gif_dimensions(&frames[0].pixmap)?;
+    let mut gif_buffer = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut gif_buffer, width, height, &[]).map_err(error)?;
+        // The GIF loop count is the number of repeats after the first play,
+        // and a single play is a GIF without one
+        match plays {
+            0 => encoder.set_repeat(gif::Repeat::Infinite).map_err(error)?,
+            1 => {}
+            plays => {
+                let repeats = u16::try_from(plays - 1).unwrap_or(u16::MAX);
+                encoder.set_repeat(gif::Repeat::Finite(repeats)).map_err(error)?
+            }
+        }
+        for frame in frames {
+            let (width, height) = gif_dimensions(&frame.pixmap)?;
+            let mut rgba = raster::demultiplied(&frame.pixmap);
+            // Speed 1 picks the best palette for photographic logos; codes
+            // with 256 colors or fewer get an exact palette either way
+            let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 1);
+            // Clear the frame to transparent before the next one is drawn,
+            // so its transparent pixels show the page, not this frame
+            gif_frame.dispose = gif::DisposalMethod::Background;
+            // GIF delays count hundredths of a second
+            gif_frame.delay = u16::try_from(frame.delay / 10).unwrap_or(u16::MAX);
+            encoder.write_frame(&gif_frame).map_err(error)?;
+        }
+    }
+    Ok(gif_buffer)
+}
+
+fn gif_dimensions(pixmap: &Pixmap) -> Result<(u16, u16), QrError> {
+    match (u16::try_from(pixmap.width()), u16::try_from(pixmap.height())) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(QrError::InvalidOption(
+            "GIF frames are at most 65535 pixels on a side".to_string(),
+        )),
+    }
+}
+
+fn encode_apng(frames: &[AnimatedFrame], plays: u32) -> Result<Vec<u8>, QrError> {
+    let error = |e: png::EncodingError| QrError::Encode(format!("Failed to encode APNG: {}", e));
+    let first = &frames[0];
+    let mut png_buffer = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_buffer, first.pixmap.width(), first.pixmap.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, plays).map_err(error)?;
+        let mut writer = encoder.write_header().map_err(error)?;
+        for frame in frames {
+            // Milliseconds while they fit the 16-bit numerator, whole
+            // seconds beyond
+            let (numerator, denominator) = match u16::try_from(frame.delay) {
+                Ok(ms) => (ms, 1000),
+                Err(_) => (u16::try_from(frame.delay / 1000).unwrap_or(u16::MAX), 1),
+            };
+            writer.set_frame_delay(numerator, denominator).map_err(error)?;
+            writer
+                .write_image_data(&raster::demultiplied(&frame.pixmap))
+                .map_err(error)?;
+        }
+        writer.finish().map_err(error)?;
+    }
+    Ok(png_buffer)
+}
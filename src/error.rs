@@ -0,0 +1,134 @@
+//! Errors reported to JavaScript. Every failure maps to one `QrError`
+//! variant, and each variant to a stable `code` on the thrown `Error`, so
+//! callers can branch on `error.code` instead of matching message text.
+
+use std::fmt;
+
+use napi::bindgen_prelude::{JsObjectValue, JsValue};
+use napi::Env;
+
+/// Everything that can go wrong while encoding, rendering or decoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QrError {
+    /// The data does not fit: it needs `needed` data bits where the largest
+    /// allowed symbol has `available`.
+    DataTooLong {
+        message: String,
+        needed: usize,
+        available: usize,
+    },
+    /// A color is not a valid hex color.
+    InvalidColor(String),
+    /// An option is missing, out of range or not supported by the symbology.
+    InvalidOption(String),
+    /// The logo is not a recognised image.
+    LogoDecode(String),
+    /// The SVG to rasterize could not be parsed.
+    SvgParse(String),
+    /// The data cannot be encoded as given (a segment mode or GS1 element
+    /// string that does not match it), or an image encoder failed.
+    Encode(String),
+    /// The image to decode could not be read.
+    Decode(String),
+}
+
+impl QrError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            QrError::DataTooLong {
+                needed, available, ..
+            } => ErrorCode::DataTooLong {
+                needed: *needed,
+                available: *available,
+            },
+            QrError::InvalidColor(_) => ErrorCode::InvalidColor,
+            QrError::InvalidOption(_) => ErrorCode::InvalidOption,
+            QrError::LogoDecode(_) => ErrorCode::LogoDecode,
+            QrError::SvgParse(_) => ErrorCode::SvgParse,
+            QrError::Encode(_) => ErrorCode::Encode,
+            QrError::Decode(_) => ErrorCode::Decode,
+        }
+    }
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrError::DataTooLong {
+                message,
+                needed,
+                available,
+            } => write!(f, "{} ({} bits needed, {} available)", message, needed, available),
+            QrError::InvalidColor(message)
+            | QrError::InvalidOption(message)
+            | QrError::LogoDecode(message)
+            | QrError::SvgParse(message)
+            | QrError::Encode(message)
+            | QrError::Decode(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
+
+/// The `code` property of a thrown error. napi-rs sets `code` from the
+/// error status, so this stands in for `napi::Status`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Also carries the `needed` and `available` bits, which `settle` sets
+    /// as numeric properties of the error.
+    DataTooLong { needed: usize, available: usize },
+    InvalidColor,
+    InvalidOption,
+    LogoDecode,
+    SvgParse,
+    Encode,
+    Decode,
+}
+
+impl AsRef<str> for ErrorCode {
+    fn as_ref(&self) -> &str {
+        match self {
+            ErrorCode::DataTooLong { .. } => "ERR_DATA_TOO_LONG",
+            ErrorCode::InvalidColor => "ERR_INVALID_COLOR",
+            ErrorCode::InvalidOption => "ERR_INVALID_OPTION",
+            ErrorCode::LogoDecode => "ERR_LOGO_DECODE",
+            ErrorCode::SvgParse => "ERR_SVG_PARSE",
+            ErrorCode::Encode => "ERR_ENCODE",
+            ErrorCode::Decode => "ERR_DECODE",
+        }
+    }
+}
+
+impl From<QrError> for napi::Error<ErrorCode> {
+    fn from(error: QrError) -> Self {
+        napi::Error::new(error.code(), error)
+    }
+}
+
+/// Result of the exported functions: errors carry an `ErrorCode`.
+pub type Result<T> = napi::Result<T, ErrorCode>;
+
+/// Return the value, or a JavaScript error carrying the `code`, and the
+/// `needed` and `available` bits of `ERR_DATA_TOO_LONG`, passed through by
+/// reference. napi-rs only sets `code` on the errors it creates itself, so
+/// async tasks settle with this and the encoding exports throw through it.
+pub fn settle<T>(env: &Env, output: Result<T>) -> napi::Result<T> {
+    output.map_err(|error| {
+        let code = error.status;
+        let created = env
+            .create_error(napi::Error::from_reason(error.reason))
+            .and_then(|mut object| {
+                object.set_named_property("code", code.as_ref())?;
+                if let ErrorCode::DataTooLong { needed, available } = code {
+                    object.set_named_property("needed", needed as u32)?;
+                    object.set_named_property("available", available as u32)?;
+                }
+                Ok(object)
+            });
+        match created {
+            Ok(object) => napi::Error::from(object.to_unknown()),
+            Err(error) => error,
+        }
+    })
+}
@@ -0,0 +1,169 @@
+//! GS1 element strings for GS1 QR Codes.
+//!
+//! GS1 data is given in the bracketed human readable form, e.g.
+//! `(01)09506000134352(17)251231(10)ABC123`. Every Application Identifier is
+//! checked against its format (length, character set, check digit and date)
+//! and the elements are joined into the element string that follows the FNC1
+//! in first position mode indicator. A variable-length element that is not
+//! the last one is terminated with a GS (`0x1D`) separator.
+
+/// Separator ending a variable-length element, FNC1 in the encoded data.
+pub const GROUP_SEPARATOR: char = '\u{1D}';
+
+/// Shape of an Application Identifier's data field.
+#[derive(Clone, Copy)]
+enum Format {
+    /// Exactly `n` digits, the last one a GS1 check digit.
+    CheckDigit(usize),
+    /// A `YYMMDD` date; `DD` may be `00` for "end of month".
+    Date,
+    /// Up to `n` digits.
+    Numeric(usize),
+    /// Up to `n` characters of GS1 character set 82.
+    Alphanumeric(usize),
+}
+
+impl Format {
+    fn is_fixed_length(self) -> bool {
+        matches!(self, Format::CheckDigit(_) | Format::Date)
+    }
+}
+
+/// Supported Application Identifiers and their data titles.
+const APPLICATION_IDENTIFIERS: &[(&str, &str, Format)] = &[
+    ("00", "SSCC", Format::CheckDigit(18)),
+    ("01", "GTIN", Format::CheckDigit(14)),
+    ("02", "CONTENT", Format::CheckDigit(14)),
+    ("10", "BATCH/LOT", Format::Alphanumeric(20)),
+    ("11", "PROD DATE", Format::Date),
+    ("13", "PACK DATE", Format::Date),
+    ("15", "BEST BEFORE", Format::Date),
+    ("17", "USE BY OR EXPIRY", Format::Date),
+    ("21", "SERIAL", Format::Alphanumeric(20)),
+    ("30", "VAR. COUNT", Format::Numeric(8)),
+    ("37", "COUNT", Format::Numeric(8)),
+];
+
+/// GS1 character set 82, the characters allowed in alphanumeric fields.
+fn is_cset82_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!\"%&'()*+,-./:;<=>?_".contains(c)
+}
+
+/// GS1 mod-10 check digit of `digits` (without the check digit itself).
+fn check_digit(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| (d - b'0') as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+fn validate_date(value: &str) -> Result<(), String> {
+    let field = |i: usize| value[i..i + 2].parse::<u32>().unwrap_or(0);
+    let (year, month, day) = (field(0), field(2), field(4));
+    if !(1..=12).contains(&month) {
+        return Err(format!("month {:02} is out of range", month));
+    }
+    let days = match month {
+        2 if year % 4 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if day > days {
+        return Err(format!("day {:02} is out of range for month {:02}", day, month));
+    }
+    Ok(())
+}
+
+fn validate_element(ai: &str, title: &str, format: Format, value: &str) -> Result<(), String> {
+    let digits_only = value.bytes().all(|b| b.is_ascii_digit());
+    match format {
+        Format::CheckDigit(length) => {
+            if value.len() != length || !digits_only {
+                return Err(format!("Invalid GS1 ({}) {}: expected {} digits", ai, title, length));
+            }
+            let expected = check_digit(&value[..length - 1]);
+            if value.as_bytes()[length - 1] - b'0' != expected as u8 {
+                return Err(format!(
+                    "Invalid GS1 ({}) {} check digit: expected {}",
+                    ai, title, expected
+                ));
+            }
+        }
+        Format::Date => {
+            if value.len() != 6 || !digits_only {
+                return Err(format!("Invalid GS1 ({}) {}: expected a YYMMDD date", ai, title));
+            }
+            validate_date(value).map_err(|e| format!("Invalid GS1 ({}) {}: {}", ai, title, e))?;
+        }
+        Format::Numeric(max) => {
+            if value.is_empty() || value.len() > max || !digits_only {
+                return Err(format!("Invalid GS1 ({}) {}: expected 1 to {} digits", ai, title, max));
+            }
+        }
+        Format::Alphanumeric(max) => {
+            let length = value.chars().count();
+            if length == 0 || length > max {
+                return Err(format!(
+                    "Invalid GS1 ({}) {}: expected 1 to {} characters",
+                    ai, title, max
+                ));
+            }
+            if let Some(c) = value.chars().find(|&c| !is_cset82_char(c)) {
+                return Err(format!("Invalid GS1 ({}) {}: character {:?} is not allowed", ai, title, c));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Split the bracketed form into `(ai, value)` pairs. Values run up to the
+/// next `(`, so they cannot contain one.
+fn parse_elements(input: &str) -> Result<Vec<(&str, &str)>, String> {
+    let mut elements = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let close = match rest.strip_prefix('(').and_then(|r| r.find(')')) {
+            Some(close) => close + 1,
+            None => {
+                return Err(format!(
+                    "Invalid GS1 element string: expected an Application Identifier in parentheses at \"{}\"",
+                    rest
+                ))
+            }
+        };
+        let ai = &rest[1..close];
+        let end = rest[close..].find('(').map_or(rest.len(), |i| close + i);
+        elements.push((ai, &rest[close + 1..end]));
+        rest = &rest[end..];
+    }
+    if elements.is_empty() {
+        return Err("Invalid GS1 element string: no Application Identifiers".to_string());
+    }
+    Ok(elements)
+}
+
+/// Validate a bracketed GS1 element string and return the element string to
+/// encode, without the leading FNC1.
+pub fn element_string(input: &str) -> Result<String, String> {
+    let elements = parse_elements(input)?;
+
+    let mut encoded = String::new();
+    for (i, &(ai, value)) in elements.iter().enumerate() {
+        let &(_, title, format) = APPLICATION_IDENTIFIERS
+            .iter()
+            .find(|(known, _, _)| *known == ai)
+            .ok_or_else(|| format!("Unsupported GS1 Application Identifier: ({})", ai))?;
+        validate_element(ai, title, format, value)?;
+
+        encoded.push_str(ai);
+        encoded.push_str(value);
+        if !format.is_fixed_length() && i + 1 < elements.len() {
+            encoded.push(GROUP_SEPARATOR);
+        }
+    }
+    Ok(encoded)
+}
@@ -2,12 +2,42 @@ use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use quircs::Quirc;
 
+mod animation;
+mod error;
+mod gs1;
+mod microqr;
 mod nodeqr;
-use nodeqr::{BitMatrix, EcLevel};
+mod pdf;
+mod raster;
+mod rmqr;
+mod sjis;
+pub mod tasks;
+use error::{settle, ErrorCode, QrError, Result};
+use nodeqr::{BitMatrix, CreateOptions, EcLevel, QrData, SegmentInput, Symbology};
+use raster::RasterFormat;
+
+/// A caller-defined segment: `data` encoded in `mode` (`"numeric"`,
+/// `"alphanumeric"`, `"byte"` or `"kanji"`).
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct QrSegment {
+    pub mode: String,
+    pub data: String,
+}
 
 #[napi(object)]
 pub struct QrOptions {
-    pub text: String,
+    /// Text to encode. Ignored when `data`, `gs1` or `segments` is set.
+    pub text: Option<String>,
+    /// Raw bytes to encode as a single Byte-mode segment instead of `text`.
+    pub data: Option<Buffer>,
+    /// GS1 element string in bracketed form, e.g.
+    /// `(01)09506000134352(17)251231(10)ABC123`, encoded as a GS1 QR Code
+    /// instead of `text` or `data`.
+    pub gs1: Option<String>,
+    /// Ordered segments with caller-chosen modes, encoded instead of `text`
+    /// or `data` without automatic segmentation.
+    pub segments: Option<Vec<QrSegment>>,
     pub size: Option<u32>,
     pub margin: Option<u32>,
     pub logo_path: Option<String>,
@@ -17,11 +47,40 @@ pub struct QrOptions {
     pub background_color: Option<String>,
     pub foreground_color: Option<String>,
     pub error_correction: Option<String>,
+    /// Pin the QR version (1-40) instead of picking the smallest that fits.
+    pub version: Option<u32>,
+    /// Smallest QR version (1-40) the encoder may pick.
+    pub min_version: Option<u32>,
+    /// Largest QR version (1-40) the encoder may pick.
+    pub max_version: Option<u32>,
+    /// Force a mask pattern (0-7) instead of the lowest-penalty one.
+    pub mask_pattern: Option<u32>,
+    /// Encode Japanese text in Kanji mode (Shift JIS) instead of UTF-8 bytes.
+    pub kanji: Option<bool>,
+    /// ECI assignment number to declare the character set, e.g. 26 for UTF-8.
+    pub eci: Option<u32>,
+    /// Raise the error correction level (L to M to Q to H) as far as the data
+    /// still fits in the same version.
+    pub boost_ecl: Option<bool>,
+    /// Symbol family: `"qr"` (default), `"microqr"` for Micro QR M1-M4,
+    /// whose versions count as 1-4, or `"rmqr"` for rMQR R7x43-R17x139,
+    /// counted as 1-32.
+    pub symbology: Option<String>,
 }
 
 #[napi(object)]
 pub struct QrOptionsWithBuffer {
-    pub text: String,
+    /// Text to encode. Ignored when `data`, `gs1` or `segments` is set.
+    pub text: Option<String>,
+    /// Raw bytes to encode as a single Byte-mode segment instead of `text`.
+    pub data: Option<Buffer>,
+    /// GS1 element string in bracketed form, e.g.
+    /// `(01)09506000134352(17)251231(10)ABC123`, encoded as a GS1 QR Code
+    /// instead of `text` or `data`.
+    pub gs1: Option<String>,
+    /// Ordered segments with caller-chosen modes, encoded instead of `text`
+    /// or `data` without automatic segmentation.
+    pub segments: Option<Vec<QrSegment>>,
     pub size: Option<u32>,
     pub margin: Option<u32>,
     pub logo_buffer: Option<Buffer>,
@@ -31,6 +90,25 @@ pub struct QrOptionsWithBuffer {
     pub background_color: Option<String>,
     pub foreground_color: Option<String>,
     pub error_correction: Option<String>,
+    /// Pin the QR version (1-40) instead of picking the smallest that fits.
+    pub version: Option<u32>,
+    /// Smallest QR version (1-40) the encoder may pick.
+    pub min_version: Option<u32>,
+    /// Largest QR version (1-40) the encoder may pick.
+    pub max_version: Option<u32>,
+    /// Force a mask pattern (0-7) instead of the lowest-penalty one.
+    pub mask_pattern: Option<u32>,
+    /// Encode Japanese text in Kanji mode (Shift JIS) instead of UTF-8 bytes.
+    pub kanji: Option<bool>,
+    /// ECI assignment number to declare the character set, e.g. 26 for UTF-8.
+    pub eci: Option<u32>,
+    /// Raise the error correction level (L to M to Q to H) as far as the data
+    /// still fits in the same version.
+    pub boost_ecl: Option<bool>,
+    /// Symbol family: `"qr"` (default), `"microqr"` for Micro QR M1-M4,
+    /// whose versions count as 1-4, or `"rmqr"` for rMQR R7x43-R17x139,
+    /// counted as 1-32.
+    pub symbology: Option<String>,
 }
 
 #[napi(object)]
@@ -50,9 +128,14 @@ pub struct QrGenerator {
 }
 
 impl QrGenerator {
-    pub fn new(text: &str, size: u32, margin: u32, ec_level: EcLevel) -> napi::Result<Self> {
-        let matrix = nodeqr::create(text, ec_level)
-            .map_err(|e| Error::from_reason(format!("QR code generation failed: {}", e)))?;
+    pub fn new(
+        data: QrData,
+        size: u32,
+        margin: u32,
+        ec_level: EcLevel,
+        create_options: &CreateOptions,
+    ) -> Result<Self> {
+        let matrix = nodeqr::create(data, ec_level, create_options)?;
 
         Ok(Self {
             matrix,
@@ -68,43 +151,48 @@ impl QrGenerator {
         self.foreground_color = fg;
     }
 
-    pub fn generate_svg(
-        &self,
-        logo_path: Option<&str>,
-        logo_size_ratio: f64,
-        logo_background_color: Option<[u8; 4]>,
-        logo_padding_ratio: f64,
-    ) -> napi::Result<String> {
-        // Resolve the logo (if any) to a base64 PNG data URL, falling back to
-        // the raw path as the href if the file cannot be read.
-        let logo_data_url = logo_path.map(|path| match std::fs::read(path) {
-            Ok(bytes) => encode_png_data_url(&bytes),
-            Err(_) => path.to_string(),
-        });
+    pub fn generate_svg(&self, logo_path: Option<&str>, logo: &LogoStyle) -> Result<String> {
+        let logo_data_url = logo_path.map(resolve_logo_path).transpose()?;
 
-        Ok(self.build_svg(
-            logo_data_url,
-            logo_size_ratio,
-            logo_background_color,
-            logo_padding_ratio,
-        ))
+        Ok(self.build_svg(logo_data_url, logo))
     }
 
     pub fn generate_svg_with_buffer(
         &self,
         logo_buffer: Option<&[u8]>,
-        logo_size_ratio: f64,
-        logo_background_color: Option<[u8; 4]>,
-        logo_padding_ratio: f64,
-    ) -> napi::Result<String> {
-        let logo_data_url = logo_buffer.map(encode_png_data_url);
+        logo: &LogoStyle,
+    ) -> Result<String> {
+        let logo_data_url = logo_buffer.map(encode_logo_data_url).transpose()?;
 
-        Ok(self.build_svg(
-            logo_data_url,
-            logo_size_ratio,
-            logo_background_color,
-            logo_padding_ratio,
-        ))
+        Ok(self.build_svg(logo_data_url, logo))
+    }
+
+    /// Where `build_svg` puts the symbol and the logo, in SVG user units.
+    fn layout(&self, logo: &LogoStyle) -> SvgLayout {
+        // `size` is the width of the symbol; rMQR symbols are shorter than
+        // they are wide and keep their module aspect ratio.
+        let size = self.size as f64;
+        let margin = self.margin as f64;
+        let symbol_height = size * self.matrix.height as f64 / self.matrix.width as f64;
+        let width = size + 2.0 * margin;
+        let height = symbol_height + 2.0 * margin;
+        let centered = |square_size: f64| Square {
+            x: (width - square_size) / 2.0,
+            y: (height - square_size) / 2.0,
+            size: square_size,
+        };
+        let logo_size = symbol_height.min(size) * logo.size_ratio;
+
+        SvgLayout {
+            width,
+            height,
+            view_width: width.trunc(),
+            view_height: height.trunc(),
+            margin,
+            module_size: size / self.matrix.width as f64,
+            logo: centered(logo_size),
+            patch: centered(logo_size * (1.0 + 2.0 * logo.padding_ratio)),
+        }
     }
 
     /// Shared SVG builder for the logo-capable rendering path. `logo_data_url`
@@ -113,25 +201,20 @@ impl QrGenerator {
     /// (one subpath per horizontal run of dark modules) instead of one `<rect>`
     /// per module — visually identical, but far fewer nodes and much smaller
     /// output.
-    fn build_svg(
-        &self,
-        logo_data_url: Option<String>,
-        logo_size_ratio: f64,
-        logo_background_color: Option<[u8; 4]>,
-        logo_padding_ratio: f64,
-    ) -> String {
+    fn build_svg(&self, logo_data_url: Option<String>, logo: &LogoStyle) -> String {
         use svg::node::element::{Image as SvgImage, Path, Rectangle};
         use svg::Document;
         use std::fmt::Write as _;
 
-        let qr_width = self.matrix.size;
-        let module_size = self.size as f64 / qr_width as f64;
-        let total_size = self.size as f64 + 2.0 * self.margin as f64;
+        let qr_width = self.matrix.width;
+        let qr_height = self.matrix.height;
+        let layout = self.layout(logo);
+        let module_size = layout.module_size;
 
         let mut document = Document::new()
-            .set("width", total_size)
-            .set("height", total_size)
-            .set("viewBox", (0, 0, total_size as i32, total_size as i32));
+            .set("width", layout.width)
+            .set("height", layout.height)
+            .set("viewBox", (0, 0, layout.view_width as i32, layout.view_height as i32));
 
         // Background
         let bg_color = format!("rgb({},{},{})",
@@ -154,8 +237,8 @@ impl QrGenerator {
             self.foreground_color[2]
         );
 
-        let mut d = String::with_capacity(qr_width * qr_width);
-        for row in 0..qr_width {
+        let mut d = String::with_capacity(qr_width * qr_height);
+        for row in 0..qr_height {
             let mut col = 0;
             while col < qr_width {
                 if self.matrix.get(row, col) != 0 {
@@ -164,8 +247,8 @@ impl QrGenerator {
                         col += 1;
                     }
                     let run_len = (col - run_start) as f64;
-                    let x = self.margin as f64 + run_start as f64 * module_size;
-                    let y = self.margin as f64 + row as f64 * module_size;
+                    let x = layout.margin + run_start as f64 * module_size;
+                    let y = layout.margin + row as f64 * module_size;
                     let w = run_len * module_size;
                     // Format directly into the buffer to avoid a temporary
                     // String allocation per run (write to String is infallible).
@@ -183,33 +266,26 @@ impl QrGenerator {
 
         // Add logo if provided
         if let Some(data_url) = logo_data_url {
-            let logo_size = (self.size as f64) * logo_size_ratio;
-            let center_x = (total_size - logo_size) / 2.0;
-            let center_y = (total_size - logo_size) / 2.0;
-
             // Knockout patch behind the logo so transparent areas don't reveal QR modules
-            if let Some(patch_color) = logo_background_color {
-                let patch_size = logo_size * (1.0 + 2.0 * logo_padding_ratio);
-                let patch_x = (total_size - patch_size) / 2.0;
-                let patch_y = (total_size - patch_size) / 2.0;
+            if let Some(patch_color) = logo.background_color {
                 let patch_fill = format!(
                     "rgb({},{},{})",
                     patch_color[0], patch_color[1], patch_color[2]
                 );
                 let patch = Rectangle::new()
-                    .set("x", patch_x)
-                    .set("y", patch_y)
-                    .set("width", patch_size)
-                    .set("height", patch_size)
+                    .set("x", layout.patch.x)
+                    .set("y", layout.patch.y)
+                    .set("width", layout.patch.size)
+                    .set("height", layout.patch.size)
                     .set("fill", patch_fill);
                 document = document.add(patch);
             }
 
             let logo_image = SvgImage::new()
-                .set("x", center_x)
-                .set("y", center_y)
-                .set("width", logo_size)
-                .set("height", logo_size)
+                .set("x", layout.logo.x)
+                .set("y", layout.logo.y)
+                .set("width", layout.logo.size)
+                .set("height", layout.logo.size)
                 .set("href", data_url);
 
             document = document.add(logo_image);
@@ -218,13 +294,178 @@ impl QrGenerator {
         document.to_string()
     }
 
+    /// Raster counterpart of `generate_svg_with_buffer`. A logo `image` can't
+    /// decode is left to resvg through the SVG.
+    pub fn generate_image(
+        &self,
+        logo_buffer: Option<&[u8]>,
+        logo: &LogoStyle,
+        format: RasterFormat,
+        encoding: raster::Encoding,
+    ) -> Result<Vec<u8>> {
+        match logo_buffer.map(|bytes| (bytes, raster::LogoImage::decode(bytes))) {
+            Some((_, Some(image))) => {
+                let scene = self.raster_scene(Some(&image), logo);
+                Ok(raster::render(&scene, format, encoding)?)
+            }
+            Some((bytes, None)) => {
+                let svg = self.generate_svg_with_buffer(Some(bytes), logo)?;
+                rasterize_svg(&svg, None, None, format, encoding)
+            }
+            None => Ok(raster::render(&self.raster_scene(None, logo), format, encoding)?),
+        }
+    }
+
+    /// Vector counterpart of `generate_svg_with_buffer`: the layout of
+    /// `build_svg` as a PDF page `width` `unit`s wide (see `pdf::page_width`).
+    /// Unlike the raster path there is no resvg fallback: a logo `image`
+    /// can't decode is an error.
+    pub fn generate_pdf(
+        &self,
+        logo_buffer: Option<&[u8]>,
+        logo: &LogoStyle,
+        width: Option<f64>,
+        unit: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        let layout = self.layout(logo);
+        // The page is the SVG's own size, in its CSS pixels.
+        let fit = ViewBoxFit::new(
+            layout.width,
+            layout.height,
+            layout.view_width,
+            layout.view_height,
+            layout.width,
+            layout.height,
+        );
+
+        let image = logo_buffer
+            .map(|bytes| {
+                image::load_from_memory(bytes)
+                    .map(|image| image.to_rgba8())
+                    .map_err(|e| QrError::LogoDecode(format!("Failed to decode logo: {}", e)))
+            })
+            .transpose()?;
+        let logo = image.as_ref().map(|image| pdf::LogoPlacement {
+            image,
+            x: fit.x(layout.logo.x),
+            y: fit.y(layout.logo.y),
+            size: layout.logo.size * fit.scale_x,
+            patch: logo.background_color.map(|color| raster::Patch {
+                color,
+                x: fit.x(layout.patch.x) as f32,
+                y: fit.y(layout.patch.y) as f32,
+                size: (layout.patch.size * fit.scale_x) as f32,
+            }),
+        });
+
+        let scene = pdf::Scene {
+            matrix: &self.matrix,
+            width: fit.width,
+            height: fit.height,
+            x: fit.x(layout.margin),
+            y: fit.y(layout.margin),
+            module_width: layout.module_size * fit.scale_x,
+            module_height: layout.module_size * fit.scale_y,
+            dark: self.foreground_color,
+            light: self.background_color,
+            logo,
+        };
+        let page_width = pdf::page_width(&scene, width, unit)?;
+        Ok(pdf::render(&scene, page_width)?)
+    }
+
+    /// The layout of `build_svg` in the pixels `render_svg_to_pixmap` renders
+    /// it at by default.
+    fn raster_scene<'a>(
+        &'a self,
+        image: Option<&'a raster::LogoImage>,
+        logo: &LogoStyle,
+    ) -> raster::Scene<'a> {
+        let layout = self.layout(logo);
+        let fit = ViewBoxFit::pixmap(
+            layout.width,
+            layout.height,
+            layout.view_width,
+            layout.view_height,
+        );
+
+        let logo = image.map(|image| raster::LogoPlacement {
+            image,
+            x: fit.x(layout.logo.x) as f32,
+            y: fit.y(layout.logo.y) as f32,
+            size: (layout.logo.size * fit.scale_x) as f32,
+            patch: logo.background_color.map(|color| raster::Patch {
+                color,
+                x: fit.x(layout.patch.x) as f32,
+                y: fit.y(layout.patch.y) as f32,
+                size: (layout.patch.size * fit.scale_x) as f32,
+            }),
+        });
+
+        raster::Scene {
+            matrix: &self.matrix,
+            width: fit.width as u32,
+            height: fit.height as u32,
+            x: fit.x(layout.margin),
+            y: fit.y(layout.margin),
+            module_width: layout.module_size * fit.scale_x,
+            module_height: layout.module_size * fit.scale_y,
+            dark: self.foreground_color,
+            light: self.background_color,
+            logo,
+        }
+    }
+}
+
+/// Where `build_svg` draws the symbol and the logo, in SVG user units.
+/// `raster_scene` and `generate_pdf` map it through a `ViewBoxFit`, so all
+/// three renderers place them alike.
+struct SvgLayout {
+    /// Size of the SVG document.
+    width: f64,
+    height: f64,
+    /// `build_svg` truncates its viewBox to whole units.
+    view_width: f64,
+    view_height: f64,
+    margin: f64,
+    module_size: f64,
+    logo: Square,
+    /// The knockout patch behind the logo, drawn when the logo has a
+    /// background color.
+    patch: Square,
+}
+
+/// A square of `size` at `x`, `y`.
+struct Square {
+    x: f64,
+    y: f64,
+    size: f64,
 }
 
-/// Encode raw image bytes as a base64 `data:image/png;base64,...` URL for
-/// embedding directly in an SVG `<image>` href.
-fn encode_png_data_url(bytes: &[u8]) -> String {
+/// How the logo-capable renderers size and back the logo.
+pub struct LogoStyle {
+    size_ratio: f64,
+    background_color: Option<[u8; 4]>,
+    padding_ratio: f64,
+}
+
+/// Encode raw image bytes as a base64 `data:<mime>;base64,...` URL for
+/// embedding directly in an SVG `<image>` href. The MIME type comes from the
+/// image's signature; bytes that are not a known image format are rejected.
+fn encode_logo_data_url(bytes: &[u8]) -> std::result::Result<String, QrError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
-    format!("data:image/png;base64,{}", BASE64.encode(bytes))
+    let format = image::guess_format(bytes)
+        .map_err(|_| QrError::LogoDecode("Logo is not a recognised image format".to_string()))?;
+    Ok(format!("data:{};base64,{}", format.to_mime_type(), BASE64.encode(bytes)))
+}
+
+/// Resolve a logo file to a base64 data URL, falling back to the raw path as
+/// the href if the file cannot be read.
+fn resolve_logo_path(path: &str) -> std::result::Result<String, QrError> {
+    match std::fs::read(path) {
+        Ok(bytes) => encode_logo_data_url(&bytes),
+        Err(_) => Ok(path.to_string()),
+    }
 }
 
 fn parse_ec_level(level_str: Option<&str>) -> EcLevel {
@@ -237,17 +478,73 @@ fn parse_ec_level(level_str: Option<&str>) -> EcLevel {
     }
 }
 
-fn parse_color(color_str: &str) -> napi::Result<[u8; 4]> {
+/// The payload to encode: `gs1` when set, then `segments`, then `data`,
+/// otherwise `text`.
+fn select_payload<'a>(
+    gs1: &'a Option<String>,
+    segments: &'a Option<Vec<QrSegment>>,
+    data: &'a Option<Buffer>,
+    text: &'a Option<String>,
+) -> Result<QrData<'a>> {
+    match (gs1, segments, data, text) {
+        (Some(gs1), _, _, _) => Ok(QrData::Text(gs1)),
+        (None, Some(segments), _, _) => Ok(QrData::Segments(
+            segments
+                .iter()
+                .map(|segment| SegmentInput {
+                    mode: &segment.mode,
+                    data: &segment.data,
+                })
+                .collect(),
+        )),
+        (None, None, Some(data), _) => Ok(QrData::Bytes(data.as_ref())),
+        (None, None, None, Some(text)) => Ok(QrData::Text(text)),
+        (None, None, None, None) => Err(QrError::InvalidOption(
+            "Either text, data, gs1 or segments must be provided".to_string(),
+        )
+        .into()),
+    }
+}
+
+/// Implements `payload()` and `create_options()` for napi option structs that
+/// share the encoder input and option fields.
+macro_rules! impl_encode_options {
+    ($($options:ty),*) => {$(
+        impl $options {
+            fn payload(&self) -> Result<QrData<'_>> {
+                select_payload(&self.gs1, &self.segments, &self.data, &self.text)
+            }
+
+            /// Collect the encoder options into the encoder's `CreateOptions`.
+            fn create_options(&self) -> Result<CreateOptions> {
+                Ok(CreateOptions {
+                    version: self.version.map(|v| v as usize),
+                    min_version: self.min_version.map(|v| v as usize),
+                    max_version: self.max_version.map(|v| v as usize),
+                    mask_pattern: self.mask_pattern,
+                    kanji: self.kanji.unwrap_or(false),
+                    eci: self.eci,
+                    structured_append: None,
+                    gs1: self.gs1.is_some(),
+                    boost_ecl: self.boost_ecl.unwrap_or(false),
+                    symbology: Symbology::parse(self.symbology.as_deref())?,
+                })
+            }
+        }
+    )*};
+}
+
+impl_encode_options!(QrOptions, QrOptionsWithBuffer, QrCodeSvgOptions, QrEncodeOptions);
+
+fn parse_color(color_str: &str) -> Result<[u8; 4]> {
+    let invalid = || QrError::InvalidColor("Invalid color format".to_string());
     if color_str.starts_with('#') && color_str.len() == 7 {
-        let r = u8::from_str_radix(&color_str[1..3], 16)
-            .map_err(|_| Error::from_reason("Invalid color format"))?;
-        let g = u8::from_str_radix(&color_str[3..5], 16)
-            .map_err(|_| Error::from_reason("Invalid color format"))?;
-        let b = u8::from_str_radix(&color_str[5..7], 16)
-            .map_err(|_| Error::from_reason("Invalid color format"))?;
+        let r = u8::from_str_radix(&color_str[1..3], 16).map_err(|_| invalid())?;
+        let g = u8::from_str_radix(&color_str[3..5], 16).map_err(|_| invalid())?;
+        let b = u8::from_str_radix(&color_str[5..7], 16).map_err(|_| invalid())?;
         Ok([r, g, b, 255])
     } else {
-        Err(Error::from_reason("Color must be in #RRGGBB format"))
+        Err(QrError::InvalidColor("Color must be in #RRGGBB format".to_string()).into())
     }
 }
 
@@ -282,62 +579,157 @@ fn apply_colors(
 }
 
 
+/// Implements `generator()` and `logo_style()` for the option structs of the
+/// logo-capable renderers.
+macro_rules! impl_logo_options {
+    ($($options:ty),*) => {$(
+        impl $options {
+            /// Build the symbol with the size, margin and colors to render it.
+            fn generator(&self) -> Result<QrGenerator> {
+                let size = self.size.unwrap_or(200);
+                let margin = self.margin.unwrap_or(20);
+                let ec_level = parse_ec_level(self.error_correction.as_deref());
+
+                let create_options = self.create_options()?;
+
+                let mut generator =
+                    QrGenerator::new(self.payload()?, size, margin, ec_level, &create_options)?;
+                apply_colors(
+                    &mut generator,
+                    self.background_color.as_deref(),
+                    self.foreground_color.as_deref(),
+                )?;
+                Ok(generator)
+            }
+
+            fn logo_style(&self) -> Result<LogoStyle> {
+                Ok(LogoStyle {
+                    size_ratio: self.logo_size_ratio.unwrap_or(0.2),
+                    background_color: parse_optional_color(self.logo_background_color.as_deref())?,
+                    padding_ratio: self.logo_padding_ratio.unwrap_or(0.1),
+                })
+            }
+        }
+    )*};
+}
+
+impl_logo_options!(QrOptions, QrOptionsWithBuffer);
+
 #[napi]
-pub fn generate_qr_svg(options: QrOptions) -> Result<String> {
-    let size = options.size.unwrap_or(200);
-    let margin = options.margin.unwrap_or(20);
-    let logo_size_ratio = options.logo_size_ratio.unwrap_or(0.2);
-    let logo_padding_ratio = options.logo_padding_ratio.unwrap_or(0.1);
-    let ec_level = parse_ec_level(options.error_correction.as_deref());
-
-    let mut generator = QrGenerator::new(&options.text, size, margin, ec_level)?;
-    apply_colors(
-        &mut generator,
-        options.background_color.as_deref(),
-        options.foreground_color.as_deref(),
-    )?;
+pub fn generate_qr_svg(env: Env, options: QrOptions) -> napi::Result<String> {
+    settle(&env, qr_svg(options))
+}
 
-    let logo_background_color = parse_optional_color(options.logo_background_color.as_deref())?;
+pub(crate) fn qr_svg(options: QrOptions) -> Result<String> {
+    let generator = options.generator()?;
+    let logo = options.logo_style()?;
 
-    generator.generate_svg(
-        options.logo_path.as_deref(),
-        logo_size_ratio,
-        logo_background_color,
-        logo_padding_ratio,
-    )
+    generator.generate_svg(options.logo_path.as_deref(), &logo)
 }
 
 
 #[napi]
-pub fn generate_qr_svg_with_buffer(options: QrOptionsWithBuffer) -> Result<String> {
-    let size = options.size.unwrap_or(200);
-    let margin = options.margin.unwrap_or(20);
-    let logo_size_ratio = options.logo_size_ratio.unwrap_or(0.2);
-    let logo_padding_ratio = options.logo_padding_ratio.unwrap_or(0.1);
-    let ec_level = parse_ec_level(options.error_correction.as_deref());
-
-    let mut generator = QrGenerator::new(&options.text, size, margin, ec_level)?;
-    apply_colors(
-        &mut generator,
-        options.background_color.as_deref(),
-        options.foreground_color.as_deref(),
-    )?;
+pub fn generate_qr_svg_with_buffer(env: Env, options: QrOptionsWithBuffer) -> napi::Result<String> {
+    settle(&env, qr_svg_with_buffer(options))
+}
+
+pub(crate) fn qr_svg_with_buffer(options: QrOptionsWithBuffer) -> Result<String> {
+    let generator = options.generator()?;
+    let logo = options.logo_style()?;
+
+    let logo_buffer = options.logo_buffer.as_ref().map(|b| b.as_ref());
+
+    generator.generate_svg_with_buffer(logo_buffer, &logo)
+}
+
+/// Raster counterpart of `generate_qr_svg`: paints the symbol and logo
+/// straight into a PNG, JPEG, WebP or AVIF the size `convert_svg_to_*`
+/// renders the SVG at. A logo that can't be read or decoded goes through the
+/// SVG and resvg instead. `quality` is the JPEG, lossy WebP or AVIF quality;
+/// `lossless` and `effort` only apply to WebP and `speed` to AVIF.
+#[napi]
+pub fn generate_qr_image(
+    env: Env,
+    options: QrOptions,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> napi::Result<Buffer> {
+    settle(&env, qr_image(options, format, quality, lossless, effort, speed))
+}
+
+pub(crate) fn qr_image(
+    options: QrOptions,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> Result<Buffer> {
+    let format = RasterFormat::parse(&format)?;
+    let encoding = raster::Encoding { quality, lossless, effort, speed };
+    let generator = options.generator()?;
+    let logo = options.logo_style()?;
+
+    let image = match options.logo_path.as_deref().map(|path| (path, std::fs::read(path))) {
+        Some((_, Ok(bytes))) => generator.generate_image(Some(&bytes), &logo, format, encoding)?,
+        Some((path, Err(_))) => {
+            let svg = generator.generate_svg(Some(path), &logo)?;
+            rasterize_svg(&svg, None, None, format, encoding)?
+        }
+        None => generator.generate_image(None, &logo, format, encoding)?,
+    };
+    Ok(image.into())
+}
+
+/// Raster counterpart of `generate_qr_svg_with_buffer`, like
+/// `generate_qr_image`.
+#[napi]
+pub fn generate_qr_image_with_buffer(
+    env: Env,
+    options: QrOptionsWithBuffer,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> napi::Result<Buffer> {
+    settle(&env, qr_image_with_buffer(options, format, quality, lossless, effort, speed))
+}
 
-    let logo_background_color = parse_optional_color(options.logo_background_color.as_deref())?;
+pub(crate) fn qr_image_with_buffer(
+    options: QrOptionsWithBuffer,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> Result<Buffer> {
+    let format = RasterFormat::parse(&format)?;
+    let encoding = raster::Encoding { quality, lossless, effort, speed };
+    let generator = options.generator()?;
+    let logo = options.logo_style()?;
 
     let logo_buffer = options.logo_buffer.as_ref().map(|b| b.as_ref());
 
-    generator.generate_svg_with_buffer(
-        logo_buffer,
-        logo_size_ratio,
-        logo_background_color,
-        logo_padding_ratio,
-    )
+    Ok(generator.generate_image(logo_buffer, &logo, format, encoding)?.into())
 }
 
 #[napi(object)]
 pub struct QrCodeSvgOptions {
-    pub text: String,
+    /// Text to encode. Ignored when `data`, `gs1` or `segments` is set.
+    pub text: Option<String>,
+    /// Raw bytes to encode as a single Byte-mode segment instead of `text`.
+    pub data: Option<Buffer>,
+    /// GS1 element string in bracketed form, e.g.
+    /// `(01)09506000134352(17)251231(10)ABC123`, encoded as a GS1 QR Code
+    /// instead of `text` or `data`.
+    pub gs1: Option<String>,
+    /// Ordered segments with caller-chosen modes, encoded instead of `text`
+    /// or `data` without automatic segmentation.
+    pub segments: Option<Vec<QrSegment>>,
     pub error_correction: Option<String>,
     /// Pixel width/height of the rendered SVG (maps to node-qrcode `width`).
     pub width: Option<u32>,
@@ -347,25 +739,642 @@ pub struct QrCodeSvgOptions {
     pub dark_color: Option<String>,
     /// Background (light module) color, hex string.
     pub light_color: Option<String>,
+    /// Pin the QR version, 1-40 (maps to node-qrcode `version`).
+    pub version: Option<u32>,
+    /// Smallest QR version (1-40) the encoder may pick.
+    pub min_version: Option<u32>,
+    /// Largest QR version (1-40) the encoder may pick.
+    pub max_version: Option<u32>,
+    /// Force a mask pattern (0-7) instead of the lowest-penalty one.
+    pub mask_pattern: Option<u32>,
+    /// Encode Japanese text in Kanji mode (Shift JIS) instead of UTF-8 bytes.
+    pub kanji: Option<bool>,
+    /// ECI assignment number to declare the character set, e.g. 26 for UTF-8.
+    pub eci: Option<u32>,
+    /// Raise the error correction level (L to M to Q to H) as far as the data
+    /// still fits in the same version.
+    pub boost_ecl: Option<bool>,
+    /// Symbol family: `"qr"` (default), `"microqr"` for Micro QR M1-M4,
+    /// whose versions count as 1-4, or `"rmqr"` for rMQR R7x43-R17x139,
+    /// counted as 1-32.
+    pub symbology: Option<String>,
 }
 
 /// Generate a QR code SVG string that is byte-for-byte identical to
 /// `qrcode`'s `QRCode.toString(text, { type: 'svg', ... })`. This replaces the
 /// JavaScript `qrcode` dependency for the no-logo rendering path.
 #[napi]
-pub fn generate_qr_code_svg(options: QrCodeSvgOptions) -> Result<String> {
+pub fn generate_qr_code_svg(env: Env, options: QrCodeSvgOptions) -> napi::Result<String> {
+    settle(&env, qr_code_svg(options))
+}
+
+pub(crate) fn qr_code_svg(options: QrCodeSvgOptions) -> Result<String> {
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    let create_options = options.create_options()?;
+    Ok(nodeqr::render_svg(
+        options.payload()?,
+        ecl,
+        &create_options,
+        options.width,
+        options.margin.map(|m| m as i64),
+        options.dark_color.as_deref(),
+        options.light_color.as_deref(),
+    )?)
+}
+
+/// Raster counterpart of `generate_qr_code_svg`: paints the same symbol
+/// straight into a PNG, JPEG, WebP or AVIF the size `convert_svg_to_*`
+/// renders the SVG at, without building and parsing the SVG.
+#[napi]
+pub fn generate_qr_code_image(
+    env: Env,
+    options: QrCodeSvgOptions,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> napi::Result<Buffer> {
+    settle(&env, qr_code_image(options, format, quality, lossless, effort, speed))
+}
+
+pub(crate) fn qr_code_image(
+    options: QrCodeSvgOptions,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> Result<Buffer> {
+    let format = RasterFormat::parse(&format)?;
+    let encoding = raster::Encoding { quality, lossless, effort, speed };
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    let create_options = options.create_options()?;
+    let plan = nodeqr::plan_render(
+        options.payload()?,
+        ecl,
+        &create_options,
+        options.width,
+        options.margin.map(|m| m as i64),
+        options.dark_color.as_deref(),
+        options.light_color.as_deref(),
+    )?;
+    Ok(raster::render(&plan_scene(&plan), format, encoding)?.into())
+}
+
+/// Vector counterpart of `generate_qr_svg`: the same symbol, colors, margin
+/// and logo patch as a one-page PDF for print, `width` `unit`s wide (`"mm"`
+/// by default; without a width, one SVG pixel is 0.75pt). A PDF can't link
+/// the logo file the way the SVG's `href` does, so a `logo_path` that can't
+/// be read is an invalid option rather than a missing logo.
+#[napi]
+pub fn generate_qr_pdf(
+    env: Env,
+    options: QrOptions,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> napi::Result<Buffer> {
+    settle(&env, qr_pdf(options, width, unit))
+}
+
+pub(crate) fn qr_pdf(options: QrOptions, width: Option<f64>, unit: Option<String>) -> Result<Buffer> {
+    let generator = options.generator()?;
+    let logo = options.logo_style()?;
+
+    let logo_buffer = options
+        .logo_path
+        .as_deref()
+        .map(|path| {
+            std::fs::read(path).map_err(|e| {
+                QrError::InvalidOption(format!("Failed to read logo file '{}': {}", path, e))
+            })
+        })
+        .transpose()?;
+
+    Ok(generator
+        .generate_pdf(logo_buffer.as_deref(), &logo, width, unit.as_deref())?
+        .into())
+}
+
+/// Vector counterpart of `generate_qr_svg_with_buffer`, like
+/// `generate_qr_pdf`.
+#[napi]
+pub fn generate_qr_pdf_with_buffer(
+    env: Env,
+    options: QrOptionsWithBuffer,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> napi::Result<Buffer> {
+    settle(&env, qr_pdf_with_buffer(options, width, unit))
+}
+
+pub(crate) fn qr_pdf_with_buffer(
+    options: QrOptionsWithBuffer,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> Result<Buffer> {
+    let generator = options.generator()?;
+    let logo = options.logo_style()?;
+
+    let logo_buffer = options.logo_buffer.as_ref().map(|b| b.as_ref());
+
+    Ok(generator
+        .generate_pdf(logo_buffer, &logo, width, unit.as_deref())?
+        .into())
+}
+
+/// Vector counterpart of `generate_qr_code_svg`, like `generate_qr_pdf`.
+#[napi]
+pub fn generate_qr_code_pdf(
+    env: Env,
+    options: QrCodeSvgOptions,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> napi::Result<Buffer> {
+    settle(&env, qr_code_pdf(options, width, unit))
+}
+
+pub(crate) fn qr_code_pdf(
+    options: QrCodeSvgOptions,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> Result<Buffer> {
     let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
-    nodeqr::render_svg(
-        &options.text,
+    let create_options = options.create_options()?;
+    let plan = nodeqr::plan_render(
+        options.payload()?,
         ecl,
+        &create_options,
         options.width,
         options.margin.map(|m| m as i64),
         options.dark_color.as_deref(),
         options.light_color.as_deref(),
-    )
-    .map_err(Error::from_reason)
+    )?;
+
+    // The viewBox of `render_svg`, fitted into its width and height
+    let view_width = (plan.matrix.width + plan.margin * 2) as f64;
+    let view_height = (plan.matrix.height + plan.margin * 2) as f64;
+    let module = (plan.width / view_width).min(plan.height / view_height);
+    let margin = plan.margin as f64 * module;
+    let scene = pdf::Scene {
+        matrix: &plan.matrix,
+        width: plan.width,
+        height: plan.height,
+        x: (plan.width - view_width * module) / 2.0 + margin,
+        y: (plan.height - view_height * module) / 2.0 + margin,
+        module_width: module,
+        module_height: module,
+        dark: plan.dark,
+        light: plan.light,
+        logo: None,
+    };
+    let page_width = pdf::page_width(&scene, width, unit.as_deref())?;
+    Ok(pdf::render(&scene, page_width)?.into())
+}
+
+/// Split the payload across up to 16 Structured Append symbols and render
+/// each one like `generate_qr_code_svg`. Data that fits in one symbol yields
+/// a single plain QR code.
+#[napi]
+pub fn generate_qr_code_svg_parts(env: Env, options: QrCodeSvgOptions) -> napi::Result<Vec<String>> {
+    settle(&env, qr_code_svg_parts(options))
+}
+
+fn qr_code_svg_parts(options: QrCodeSvgOptions) -> Result<Vec<String>> {
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    let symbols =
+        nodeqr::create_structured_append(options.payload()?, ecl, &options.create_options()?)?;
+    Ok(nodeqr::render_svg_parts(
+        &symbols,
+        options.width,
+        options.margin.map(|m| m as i64),
+        options.dark_color.as_deref(),
+        options.light_color.as_deref(),
+    )?)
+}
+
+/// Like `generate_qr_code_svg_parts`, but lays the symbols out on one SVG
+/// sheet with `columns` symbols per row.
+#[napi]
+pub fn generate_qr_code_svg_sheet(env: Env, options: QrCodeSvgOptions, columns: Option<u32>) -> napi::Result<String> {
+    settle(&env, qr_code_svg_sheet(options, columns))
+}
+
+fn qr_code_svg_sheet(options: QrCodeSvgOptions, columns: Option<u32>) -> Result<String> {
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    let symbols =
+        nodeqr::create_structured_append(options.payload()?, ecl, &options.create_options()?)?;
+    Ok(nodeqr::render_svg_sheet(
+        &symbols,
+        columns,
+        options.width,
+        options.margin.map(|m| m as i64),
+        options.dark_color.as_deref(),
+        options.light_color.as_deref(),
+    )?)
+}
+
+
+#[napi(object)]
+#[derive(Default)]
+pub struct QrEncodeOptions {
+    /// Text to encode. Ignored when `data`, `gs1` or `segments` is set.
+    pub text: Option<String>,
+    /// Raw bytes to encode as a single Byte-mode segment instead of `text`.
+    pub data: Option<Buffer>,
+    /// GS1 element string in bracketed form, e.g.
+    /// `(01)09506000134352(17)251231(10)ABC123`, encoded as a GS1 QR Code
+    /// instead of `text` or `data`.
+    pub gs1: Option<String>,
+    /// Ordered segments with caller-chosen modes, encoded instead of `text`
+    /// or `data` without automatic segmentation.
+    pub segments: Option<Vec<QrSegment>>,
+    pub error_correction: Option<String>,
+    /// Pin the QR version (1-40) instead of picking the smallest that fits.
+    pub version: Option<u32>,
+    /// Smallest QR version (1-40) the encoder may pick.
+    pub min_version: Option<u32>,
+    /// Largest QR version (1-40) the encoder may pick.
+    pub max_version: Option<u32>,
+    /// Force a mask pattern (0-7) instead of the lowest-penalty one.
+    pub mask_pattern: Option<u32>,
+    /// Encode Japanese text in Kanji mode (Shift JIS) instead of UTF-8 bytes.
+    pub kanji: Option<bool>,
+    /// ECI assignment number to declare the character set, e.g. 26 for UTF-8.
+    pub eci: Option<u32>,
+    /// Raise the error correction level (L to M to Q to H) as far as the data
+    /// still fits in the same version.
+    pub boost_ecl: Option<bool>,
+    /// Symbol family: `"qr"` (default), `"microqr"` for Micro QR M1-M4,
+    /// whose versions count as 1-4, or `"rmqr"` for rMQR R7x43-R17x139,
+    /// counted as 1-32.
+    pub symbology: Option<String>,
+}
+
+#[napi(object)]
+pub struct MaskPenaltyResult {
+    pub version: u32,
+    /// Penalty score of each mask pattern, indexed by pattern (0-7).
+    pub penalties: Vec<u32>,
+    /// The pattern picked automatically (lowest penalty, first on ties).
+    pub best_mask_pattern: u32,
+}
+
+/// Score all eight mask patterns for the symbol the encoder would build, so a
+/// caller can choose one to force through `maskPattern`. Any `maskPattern` in
+/// the options is ignored here.
+#[napi]
+pub fn get_mask_penalties(env: Env, options: QrEncodeOptions) -> napi::Result<MaskPenaltyResult> {
+    settle(&env, mask_penalties(options))
+}
+
+fn mask_penalties(options: QrEncodeOptions) -> Result<MaskPenaltyResult> {
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    let report = nodeqr::mask_penalties(options.payload()?, ecl, &options.create_options()?)?;
+
+    Ok(MaskPenaltyResult {
+        version: report.version as u32,
+        penalties: report.penalties.iter().map(|&p| p as u32).collect(),
+        best_mask_pattern: report.best_mask_pattern,
+    })
+}
+
+/// The error correction level the encoder uses for these options: the
+/// requested one, or the raised level when `boostEcl` is set.
+#[napi]
+pub fn get_error_correction_level(env: Env, options: QrEncodeOptions) -> napi::Result<String> {
+    settle(&env, error_correction_level(options))
+}
+
+fn error_correction_level(options: QrEncodeOptions) -> Result<String> {
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    let level = nodeqr::resolve_ec_level(options.payload()?, ecl, &options.create_options()?)?;
+
+    Ok(level.as_str().to_string())
+}
+
+#[napi(object)]
+pub struct InspectSegment {
+    /// `"Numeric"`, `"Alphanumeric"`, `"Byte"`, `"Kanji"` or `"FNC1"`.
+    pub mode: String,
+    pub data: String,
+    /// Characters, or bytes in Byte mode.
+    pub length: u32,
+    /// Mode indicator, character count and data bits.
+    pub bits: u32,
+}
+
+#[napi(object)]
+pub struct Capacity {
+    /// Data bits.
+    pub bits: u32,
+    /// Digits in Numeric mode.
+    pub numeric: u32,
+    /// Characters in Alphanumeric mode.
+    pub alphanumeric: u32,
+    /// Bytes in Byte mode.
+    pub byte: u32,
+    /// Characters in Kanji mode.
+    pub kanji: u32,
+}
+
+#[napi(object)]
+pub struct LevelCapacity {
+    pub error_correction: String,
+    /// Capacity of the symbol's version at this level.
+    pub capacity: Capacity,
+}
+
+#[napi(object)]
+pub struct InspectResult {
+    pub version: u32,
+    /// The level encoded with, raised when `boostEcl` is set.
+    pub error_correction: String,
+    pub mask_pattern: u32,
+    pub segments: Vec<InspectSegment>,
+    /// Structured Append and ECI header bits.
+    pub header_bits: u32,
+    /// Header and segment bits, without terminator and padding.
+    pub total_bits: u32,
+    /// Free bits, and how much of each mode still fits in one more segment.
+    pub remaining: Capacity,
+    /// Capacity of the version at every level, from L to H.
+    pub capacities: Vec<LevelCapacity>,
+}
+
+impl From<nodeqr::Capacity> for Capacity {
+    fn from(capacity: nodeqr::Capacity) -> Self {
+        Capacity {
+            bits: capacity.bits as u32,
+            numeric: capacity.numeric as u32,
+            alphanumeric: capacity.alphanumeric as u32,
+            byte: capacity.byte as u32,
+            kanji: capacity.kanji as u32,
+        }
+    }
 }
 
+/// Plan the symbol the encoder would build for `text` without rendering it:
+/// version, mask, segments with their bit cost, bits used and left, and the
+/// capacity of the version at every error correction level. `data`, `gs1`
+/// or `segments` in the options are inspected instead of `text` when set.
+#[napi]
+pub fn inspect(env: Env, text: String, options: Option<QrEncodeOptions>) -> napi::Result<InspectResult> {
+    settle(&env, inspect_symbol(text, options))
+}
+
+fn inspect_symbol(text: String, options: Option<QrEncodeOptions>) -> Result<InspectResult> {
+    let options = options_with_text(text, options);
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    let report = nodeqr::inspect(options.payload()?, ecl, &options.create_options()?)?;
+
+    Ok(InspectResult {
+        version: report.version as u32,
+        error_correction: report.ecl.as_str().to_string(),
+        mask_pattern: report.mask_pattern,
+        segments: report
+            .segments
+            .into_iter()
+            .map(|segment| InspectSegment {
+                mode: segment.mode.to_string(),
+                data: segment.data,
+                length: segment.length as u32,
+                bits: segment.bits as u32,
+            })
+            .collect(),
+        header_bits: report.header_bits as u32,
+        total_bits: report.total_bits as u32,
+        remaining: report.remaining.into(),
+        capacities: report
+            .capacities
+            .into_iter()
+            .map(|(level, capacity)| LevelCapacity {
+                error_correction: level.as_str().to_string(),
+                capacity: capacity.into(),
+            })
+            .collect(),
+    })
+}
+
+/// `options` with `text` as the text to encode, for the functions that take
+/// the text separately.
+fn options_with_text(text: String, options: Option<QrEncodeOptions>) -> QrEncodeOptions {
+    QrEncodeOptions {
+        text: Some(text),
+        ..options.unwrap_or_default()
+    }
+}
+
+#[napi(object)]
+pub struct QrMatrix {
+    /// Modules per row; the width for rMQR symbols.
+    pub size: u32,
+    /// Number of rows, equal to `size` except for rMQR symbols.
+    pub height: u32,
+    pub version: u32,
+    /// The level encoded with, raised when `boostEcl` is set.
+    pub error_correction: String,
+    /// The applied mask pattern; absent for rMQR's fixed mask.
+    pub mask_pattern: Option<u32>,
+    /// Rows packed 8 modules to a byte, leftmost module in the most
+    /// significant bit, each row padded to a whole byte.
+    pub packed: Buffer,
+    /// One array of 0 (light) and 1 (dark) per row.
+    pub rows: Vec<Vec<u8>>,
+    /// Role of every module, row by row: 0 finder (with separators and rMQR
+    /// sub-finder and corner patterns), 1 timing, 2 alignment, 3 format
+    /// (with the dark module), 4 version, 5 data, 6 error correction and
+    /// 7 remainder.
+    pub roles: Buffer,
+    /// Error correction block of every data and error correction module, row
+    /// by row; 0 for the other modules.
+    pub blocks: Buffer,
+}
+
+fn create_symbol(text: String, options: Option<QrEncodeOptions>) -> Result<nodeqr::Symbol> {
+    let options = options_with_text(text, options);
+    let ecl = nodeqr::EcLevel::from_str_or_m(options.error_correction.as_deref());
+    Ok(nodeqr::create_symbol(options.payload()?, ecl, &options.create_options()?)?)
+}
+
+/// Encode `text` and return the module grid without rendering it, for
+/// callers that draw the modules themselves. No quiet zone is included.
+#[napi]
+pub fn generate_qr_matrix(
+    env: Env,
+    text: String,
+    options: Option<QrEncodeOptions>,
+) -> napi::Result<QrMatrix> {
+    let symbol = settle(&env, create_symbol(text, options))?;
+    let modules = &symbol.modules;
+
+    Ok(QrMatrix {
+        size: modules.width as u32,
+        height: modules.height as u32,
+        version: symbol.version as u32,
+        error_correction: symbol.ecl.as_str().to_string(),
+        mask_pattern: symbol.mask_pattern,
+        packed: modules.to_packed().into(),
+        rows: (0..modules.height)
+            .map(|row| (0..modules.width).map(|col| modules.get(row, col)).collect())
+            .collect(),
+        roles: module_roles(modules).map(|role| role.code()).collect::<Vec<_>>().into(),
+        blocks: module_roles(modules).map(|role| role.block()).collect::<Vec<_>>().into(),
+    })
+}
+
+fn module_roles(modules: &nodeqr::BitMatrix) -> impl Iterator<Item = nodeqr::ModuleRole> + '_ {
+    (0..modules.height).flat_map(move |row| (0..modules.width).map(move |col| modules.role(row, col)))
+}
+
+/// `generateQrMatrix` as JSON: the same fields, with `rows` as arrays of 0
+/// and 1 and without the packed, roles and blocks buffers.
+#[napi]
+pub fn generate_qr_matrix_json(
+    env: Env,
+    text: String,
+    options: Option<QrEncodeOptions>,
+) -> napi::Result<String> {
+    let symbol = settle(&env, create_symbol(text, options))?;
+    let modules = &symbol.modules;
+
+    let rows: Vec<String> = (0..modules.height)
+        .map(|row| {
+            let cells: Vec<&str> = (0..modules.width)
+                .map(|col| if modules.get(row, col) != 0 { "1" } else { "0" })
+                .collect();
+            format!("[{}]", cells.join(","))
+        })
+        .collect();
+    let mask_pattern = symbol
+        .mask_pattern
+        .map_or("null".to_string(), |p| p.to_string());
+
+    Ok(format!(
+        "{{\"size\":{},\"height\":{},\"version\":{},\"errorCorrection\":\"{}\",\"maskPattern\":{},\"rows\":[{}]}}",
+        modules.width,
+        modules.height,
+        symbol.version,
+        symbol.ecl.as_str(),
+        mask_pattern,
+        rows.join(",")
+    ))
+}
+
+/// `generateQrMatrix` as text: one line of `0` (light) and `1` (dark) per
+/// row.
+#[napi]
+pub fn generate_qr_matrix_text(
+    env: Env,
+    text: String,
+    options: Option<QrEncodeOptions>,
+) -> napi::Result<String> {
+    Ok(settle(&env, create_symbol(text, options))?.modules.to_text())
+}
+
+/// Scale of the rasters rendered without an explicit size, relative to the
+/// SVG's own size.
+const SUPERSAMPLING: f64 = 2.0;
+
+/// The layout of `nodeqr::render_svg`'s SVG in the pixels
+/// `render_svg_to_pixmap` renders it at by default.
+fn plan_scene(plan: &nodeqr::RenderPlan) -> raster::Scene<'_> {
+    let view_width = (plan.matrix.width + plan.margin * 2) as f64;
+    let view_height = (plan.matrix.height + plan.margin * 2) as f64;
+    let fit = ViewBoxFit::pixmap(plan.width, plan.height, view_width, view_height);
+    let margin = plan.margin as f64;
+
+    raster::Scene {
+        matrix: &plan.matrix,
+        width: fit.width as u32,
+        height: fit.height as u32,
+        x: fit.x(margin),
+        y: fit.y(margin),
+        module_width: fit.scale_x,
+        module_height: fit.scale_y,
+        dark: plan.dark,
+        light: plan.light,
+        logo: None,
+    }
+}
+
+/// Maps SVG user units to an output `width` x `height` an SVG of
+/// `svg_width` x `svg_height` is drawn into, with the viewBox fitted like
+/// `preserveAspectRatio="xMidYMid meet"`.
+struct ViewBoxFit {
+    width: f64,
+    height: f64,
+    /// Output units per user unit.
+    scale_x: f64,
+    scale_y: f64,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl ViewBoxFit {
+    fn new(
+        svg_width: f64,
+        svg_height: f64,
+        view_width: f64,
+        view_height: f64,
+        width: f64,
+        height: f64,
+    ) -> Self {
+        let pixels_x = width / svg_width;
+        let pixels_y = height / svg_height;
+        let scale = (svg_width / view_width).min(svg_height / view_height);
+
+        Self {
+            width,
+            height,
+            scale_x: pixels_x * scale,
+            scale_y: pixels_y * scale,
+            offset_x: pixels_x * (svg_width - view_width * scale) / 2.0,
+            offset_y: pixels_y * (svg_height - view_height * scale) / 2.0,
+        }
+    }
+
+    /// The fit into the whole pixels `render_svg_to_pixmap` renders the SVG
+    /// at by default.
+    fn pixmap(svg_width: f64, svg_height: f64, view_width: f64, view_height: f64) -> Self {
+        Self::new(
+            svg_width,
+            svg_height,
+            view_width,
+            view_height,
+            (svg_width * SUPERSAMPLING).trunc(),
+            (svg_height * SUPERSAMPLING).trunc(),
+        )
+    }
+
+    fn x(&self, x: f64) -> f64 {
+        self.offset_x + x * self.scale_x
+    }
+
+    fn y(&self, y: f64) -> f64 {
+        self.offset_y + y * self.scale_y
+    }
+}
+
+/// Render an SVG with resvg and encode it as `format`, on a transparent
+/// background where the format keeps alpha and white for JPEG.
+fn rasterize_svg(
+    svg_content: &str,
+    width: Option<u32>,
+    height: Option<u32>,
+    format: RasterFormat,
+    encoding: raster::Encoding,
+) -> Result<Vec<u8>> {
+    use resvg::tiny_skia;
+
+    let background = match format {
+        RasterFormat::Png | RasterFormat::Webp | RasterFormat::Avif => tiny_skia::Color::TRANSPARENT,
+        RasterFormat::Jpeg => tiny_skia::Color::WHITE,
+    };
+    let pixmap = render_svg_to_pixmap(svg_content, width, height, background)?;
+
+    Ok(raster::encode_pixmap(&pixmap, format, encoding)?)
+}
 
 /// Parse an SVG string and render it to a tiny-skia pixmap at 2x supersampling
 /// (or the explicit `width`/`height` when provided), filled with `background`
@@ -379,14 +1388,16 @@ fn render_svg_to_pixmap(
     use resvg::tiny_skia;
     use resvg::usvg;
 
-    let mut options = usvg::Options::default();
-    options.shape_rendering = usvg::ShapeRendering::CrispEdges;
-    options.text_rendering = usvg::TextRendering::OptimizeSpeed;
-    options.image_rendering = usvg::ImageRendering::OptimizeQuality;
-    options.default_size = usvg::Size::from_wh(200.0, 200.0).unwrap();
+    let options = usvg::Options {
+        shape_rendering: usvg::ShapeRendering::CrispEdges,
+        text_rendering: usvg::TextRendering::OptimizeSpeed,
+        image_rendering: usvg::ImageRendering::OptimizeQuality,
+        default_size: usvg::Size::from_wh(200.0, 200.0).unwrap(),
+        ..Default::default()
+    };
 
     let tree = usvg::Tree::from_str(svg_content, &options)
-        .map_err(|e| Error::from_reason(format!("Failed to parse SVG: {}", e)))?;
+        .map_err(|e| QrError::SvgParse(format!("Failed to parse SVG: {}", e)))?;
 
     // Default to 2x supersampling when no explicit dimensions are given. When
     // only one dimension is specified, scale the other proportionally to
@@ -395,11 +1406,11 @@ fn render_svg_to_pixmap(
     let (pixmap_width, pixmap_height) = match (width, height) {
         (Some(w), Some(h)) => (w, h),
         (Some(w), None) => {
-            let h = (w as f32 * tree_size.height() / tree_size.width()) as u32;
+            let h = (w as f32 * tree_size.height() / tree_size.width()).round() as u32;
             (w, h)
         }
         (None, Some(h)) => {
-            let w = (h as f32 * tree_size.width() / tree_size.height()) as u32;
+            let w = (h as f32 * tree_size.width() / tree_size.height()).round() as u32;
             (w, h)
         }
         (None, None) => (
@@ -409,7 +1420,7 @@ fn render_svg_to_pixmap(
     };
 
     let mut pixmap = tiny_skia::Pixmap::new(pixmap_width, pixmap_height)
-        .ok_or_else(|| Error::from_reason("Failed to create pixmap"))?;
+        .ok_or_else(|| QrError::InvalidOption("Failed to create pixmap".to_string()))?;
     pixmap.fill(background);
 
     let scale_x = pixmap_width as f32 / tree_size.width();
@@ -423,84 +1434,359 @@ fn render_svg_to_pixmap(
 
 #[napi]
 pub fn convert_svg_to_png(svg_content: String, width: Option<u32>, height: Option<u32>) -> Result<Buffer> {
-    use resvg::tiny_skia;
-
-    // PNG supports alpha, so render onto a transparent background.
-    let pixmap = render_svg_to_pixmap(&svg_content, width, height, tiny_skia::Color::TRANSPARENT)?;
+    Ok(rasterize_svg(&svg_content, width, height, RasterFormat::Png, raster::Encoding::default())?.into())
+}
 
-    let png_data = pixmap
-        .encode_png()
-        .map_err(|e| Error::from_reason(format!("Failed to encode PNG: {}", e)))?;
+#[napi]
+pub fn convert_svg_to_jpeg(svg_content: String, width: Option<u32>, height: Option<u32>, quality: Option<u8>) -> Result<Buffer> {
+    let encoding = raster::Encoding { quality, ..Default::default() };
+    Ok(rasterize_svg(&svg_content, width, height, RasterFormat::Jpeg, encoding)?.into())
+}
 
-    Ok(png_data.into())
+/// Render an SVG to WebP. With a `quality` (0-100) the colors are lossy and
+/// the alpha channel stays lossless; without one, or with `lossless` set, the
+/// whole image is lossless. `effort` (0-6, default: 4) trades encoding speed
+/// for size.
+#[napi]
+pub fn convert_svg_to_webp(
+    svg_content: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+) -> Result<Buffer> {
+    let encoding = raster::Encoding { quality, lossless, effort, ..Default::default() };
+    Ok(rasterize_svg(&svg_content, width, height, RasterFormat::Webp, encoding)?.into())
 }
 
+/// Render an SVG to AVIF, keeping the alpha channel of the transparent
+/// pixmap. `quality` (1-100, default: 80) applies to color and alpha alike;
+/// `speed` runs from 1 (slowest, smallest) to 10 (fastest, default: 4).
 #[napi]
-pub fn convert_svg_to_jpeg(svg_content: String, width: Option<u32>, height: Option<u32>, quality: Option<u8>) -> Result<Buffer> {
-    use resvg::tiny_skia;
-    use std::io::Cursor;
-
-    // JPEG has no alpha channel, so render onto an opaque white background.
-    let pixmap = render_svg_to_pixmap(&svg_content, width, height, tiny_skia::Color::WHITE)?;
-    let pixmap_width = pixmap.width();
-    let pixmap_height = pixmap.height();
-
-    // Convert pixmap to RGB image (drop the alpha channel)
-    let rgb_image = image::RgbImage::from_raw(
-        pixmap_width,
-        pixmap_height,
-        pixmap.data()
-            .chunks_exact(4)
-            .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
-            .collect::<Vec<u8>>()
-    ).ok_or_else(|| Error::from_reason("Failed to create RGB image"))?;
-
-    // Encode as JPEG with specified quality (default: 90)
-    let jpeg_quality = quality.unwrap_or(90).clamp(1, 100);
-    let mut jpeg_buffer = Vec::new();
-    let mut cursor = Cursor::new(&mut jpeg_buffer);
-
-    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, jpeg_quality);
-    encoder.encode(
-        rgb_image.as_raw(),
-        pixmap_width,
-        pixmap_height,
-        image::ExtendedColorType::Rgb8
-    ).map_err(|e| Error::from_reason(format!("Failed to encode JPEG: {}", e)))?;
-
-    Ok(jpeg_buffer.into())
+pub fn convert_svg_to_avif(
+    svg_content: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    quality: Option<u8>,
+    speed: Option<u8>,
+) -> Result<Buffer> {
+    let encoding = raster::Encoding { quality, speed, ..Default::default() };
+    Ok(rasterize_svg(&svg_content, width, height, RasterFormat::Avif, encoding)?.into())
+}
+
+/// One frame of `encode_animation`.
+#[napi(object)]
+pub struct AnimationFrame {
+    pub svg: String,
+    /// How long the frame stays on screen, in milliseconds (default: 1000).
+    /// GIF rounds it down to hundredths of a second.
+    pub delay: Option<u32>,
 }
 
+/// Render SVG frames through the same pixmap pipeline as the converters and
+/// encode them as an animated `"gif"` or `"apng"`. Every frame is rendered
+/// at the size of the first, which is `width`/`height` or the 2x default.
+/// `loop_count` is how many times the animation plays; 0 (default) loops
+/// forever.
 #[napi]
-pub fn convert_svg_to_webp(svg_content: String, width: Option<u32>, height: Option<u32>, _quality: Option<u8>) -> Result<Buffer> {
-    use resvg::tiny_skia;
-    use std::io::Cursor;
+pub fn encode_animation(
+    frames: Vec<AnimationFrame>,
+    format: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    loop_count: Option<u32>,
+) -> Result<Buffer> {
+    use rayon::prelude::*;
+
+    let format = animation::AnimationFormat::parse(&format)?;
+    let Some((first, rest)) = frames.split_first() else {
+        return Err(QrError::InvalidOption("An animation needs at least one frame".to_string()).into());
+    };
+
+    let background = resvg::tiny_skia::Color::TRANSPARENT;
+    let render = |frame: &AnimationFrame, width, height| -> Result<animation::AnimatedFrame> {
+        Ok(animation::AnimatedFrame {
+            pixmap: render_svg_to_pixmap(&frame.svg, width, height, background)?,
+            delay: frame.delay.unwrap_or(1000),
+        })
+    };
+    let first = render(first, width, height)?;
+    let (width, height) = (Some(first.pixmap.width()), Some(first.pixmap.height()));
+    let rest = rest
+        .par_iter()
+        .map(|frame| render(frame, width, height))
+        .collect::<Result<Vec<_>>>()?;
+    let frames: Vec<_> = std::iter::once(first).chain(rest).collect();
+
+    Ok(animation::encode(&frames, format, loop_count.unwrap_or(0))?.into())
+}
+
+/// One code in a `generate_batch` run: only the payload, which is encoded
+/// with the batch's shared style.
+#[napi(object)]
+pub struct BatchItem {
+    /// Text to encode. Ignored when `data`, `gs1` or `segments` is set.
+    pub text: Option<String>,
+    /// Raw bytes to encode as a single Byte-mode segment instead of `text`.
+    pub data: Option<Buffer>,
+    /// GS1 element string in bracketed form, encoded as a GS1 QR Code
+    /// instead of `text` or `data`.
+    pub gs1: Option<String>,
+    /// Ordered segments with caller-chosen modes, encoded instead of `text`
+    /// or `data` without automatic segmentation.
+    pub segments: Option<Vec<QrSegment>>,
+}
+
+impl BatchItem {
+    fn payload(&self) -> Result<QrData<'_>> {
+        select_payload(&self.gs1, &self.segments, &self.data, &self.text)
+    }
+}
+
+/// Style shared by every code in a `generate_batch` run, with the same
+/// fields as `QrOptions`. Codes without a logo are rendered by the
+/// node-qrcode port, like `generate_qr_code_svg`, and codes with one like
+/// `generate_qr_svg_with_buffer`, where `margin` applies.
+#[napi(object)]
+#[derive(Default)]
+pub struct BatchStyle {
+    pub size: Option<u32>,
+    pub margin: Option<u32>,
+    /// Logo file, read once for the whole batch.
+    pub logo_path: Option<String>,
+    /// Logo image bytes, used instead of `logo_path`.
+    pub logo_buffer: Option<Buffer>,
+    pub logo_size_ratio: Option<f64>,
+    pub logo_background_color: Option<String>,
+    pub logo_padding_ratio: Option<f64>,
+    pub background_color: Option<String>,
+    pub foreground_color: Option<String>,
+    pub error_correction: Option<String>,
+    /// Pin the QR version (1-40) instead of picking the smallest that fits.
+    pub version: Option<u32>,
+    /// Smallest QR version (1-40) the encoder may pick.
+    pub min_version: Option<u32>,
+    /// Largest QR version (1-40) the encoder may pick.
+    pub max_version: Option<u32>,
+    /// Force a mask pattern (0-7) instead of the lowest-penalty one.
+    pub mask_pattern: Option<u32>,
+    /// Encode Japanese text in Kanji mode (Shift JIS) instead of UTF-8 bytes.
+    pub kanji: Option<bool>,
+    /// ECI assignment number to declare the character set, e.g. 26 for UTF-8.
+    pub eci: Option<u32>,
+    /// Raise the error correction level (L to M to Q to H) as far as the data
+    /// still fits in the same version.
+    pub boost_ecl: Option<bool>,
+    /// Symbol family: `"qr"` (default), `"microqr"` or `"rmqr"`.
+    pub symbology: Option<String>,
+    /// JPEG quality 1-100 (default: 90) for the `"jpeg"` format, lossy
+    /// WebP quality 0-100 for `"webp"`, or AVIF quality 1-100 (default: 80)
+    /// for `"avif"`.
+    pub quality: Option<u8>,
+    /// Encode `"webp"` losslessly even with a `quality`.
+    pub lossless: Option<bool>,
+    /// WebP compression effort, 0 (fastest) to 6 (smallest, default: 4).
+    pub effort: Option<u8>,
+    /// AVIF encoder speed, 1 (slowest, smallest) to 10 (default: 4).
+    pub speed: Option<u8>,
+}
+
+impl BatchStyle {
+    fn encoding(&self) -> raster::Encoding {
+        raster::Encoding {
+            quality: self.quality,
+            lossless: self.lossless,
+            effort: self.effort,
+            speed: self.speed,
+        }
+    }
+
+    /// The encoder options for one item; only the GS1 flag depends on it.
+    fn create_options(&self, gs1: bool) -> Result<CreateOptions> {
+        Ok(CreateOptions {
+            version: self.version.map(|v| v as usize),
+            min_version: self.min_version.map(|v| v as usize),
+            max_version: self.max_version.map(|v| v as usize),
+            mask_pattern: self.mask_pattern,
+            kanji: self.kanji.unwrap_or(false),
+            eci: self.eci,
+            structured_append: None,
+            gs1,
+            boost_ecl: self.boost_ecl.unwrap_or(false),
+            symbology: Symbology::parse(self.symbology.as_deref())?,
+        })
+    }
+}
 
-    // WebP supports alpha, so render onto a transparent background.
-    let pixmap = render_svg_to_pixmap(&svg_content, width, height, tiny_skia::Color::TRANSPARENT)?;
-    let pixmap_width = pixmap.width();
-    let pixmap_height = pixmap.height();
+/// The outcome of one `generate_batch` item: the SVG string or image buffer,
+/// or the error that item failed with.
+#[napi(object)]
+pub struct BatchResult {
+    pub output: Option<Either<String, Buffer>>,
+    pub error: Option<String>,
+    /// Stable error code, as on thrown errors.
+    pub code: Option<String>,
+    /// Bits the data needs and the symbol has, for `ERR_DATA_TOO_LONG`.
+    pub needed: Option<u32>,
+    pub available: Option<u32>,
+}
 
-    // Keep RGBA data (WebP supports alpha channel)
-    let rgba_image = image::RgbaImage::from_raw(
-        pixmap_width,
-        pixmap_height,
-        pixmap.data().to_vec()
-    ).ok_or_else(|| Error::from_reason("Failed to create RGBA image"))?;
+/// A batch with everything shared by its items resolved up front, so a bad
+/// style or logo fails the batch once instead of every item.
+struct Batch {
+    style: BatchStyle,
+    /// The raster format, or `None` for SVG.
+    format: Option<RasterFormat>,
+    logo_data_url: Option<String>,
+    /// The decoded logo for raster formats, unless `image` can't read it.
+    logo_image: Option<raster::LogoImage>,
+    logo: LogoStyle,
+}
 
-    // Encode as WebP lossless
-    let mut webp_buffer = Vec::new();
-    let cursor = Cursor::new(&mut webp_buffer);
+impl Batch {
+    fn new(style: BatchStyle, format: Option<&str>) -> Result<Self> {
+        let format = match format.unwrap_or("svg") {
+            svg if svg.eq_ignore_ascii_case("svg") => None,
+            format => Some(RasterFormat::parse(format).map_err(|_| {
+                QrError::InvalidOption(format!(
+                    "Unsupported batch format '{}': expected svg, png, jpeg, webp or avif",
+                    format
+                ))
+            })?),
+        };
+        style.create_options(false)?;
+        let decode_logo = |bytes: &[u8]| format.and_then(|_| raster::LogoImage::decode(bytes));
+        let (logo_data_url, logo_image) = match (&style.logo_buffer, &style.logo_path) {
+            (Some(buffer), _) => (Some(encode_logo_data_url(buffer)?), decode_logo(buffer)),
+            (None, Some(path)) => match std::fs::read(path) {
+                Ok(bytes) => (Some(encode_logo_data_url(&bytes)?), decode_logo(&bytes)),
+                Err(_) => (Some(path.clone()), None),
+            },
+            (None, None) => (None, None),
+        };
+        let logo = LogoStyle {
+            size_ratio: style.logo_size_ratio.unwrap_or(0.2),
+            background_color: parse_optional_color(style.logo_background_color.as_deref())?,
+            padding_ratio: style.logo_padding_ratio.unwrap_or(0.1),
+        };
+        if logo_data_url.is_some() {
+            parse_optional_color(style.background_color.as_deref())?;
+            parse_optional_color(style.foreground_color.as_deref())?;
+        }
 
-    let encoder = image::codecs::webp::WebPEncoder::new_lossless(cursor);
-    encoder.encode(
-        rgba_image.as_raw(),
-        pixmap_width,
-        pixmap_height,
-        image::ExtendedColorType::Rgba8
-    ).map_err(|e| Error::from_reason(format!("Failed to encode WebP: {}", e)))?;
+        Ok(Self {
+            style,
+            format,
+            logo_data_url,
+            logo_image,
+            logo,
+        })
+    }
+
+    fn render(&self, item: &BatchItem) -> Result<Either<String, Buffer>> {
+        let style = &self.style;
+        let size = style.size.unwrap_or(200);
+        let create_options = style.create_options(item.gs1.is_some())?;
+
+        let Some(logo_data_url) = &self.logo_data_url else {
+            let ecl = nodeqr::EcLevel::from_str_or_m(style.error_correction.as_deref());
+            let dark_color = style.foreground_color.as_deref();
+            let light_color = style.background_color.as_deref();
+            return match self.format {
+                None => Ok(Either::A(nodeqr::render_svg(
+                    item.payload()?,
+                    ecl,
+                    &create_options,
+                    Some(size),
+                    None,
+                    dark_color,
+                    light_color,
+                )?)),
+                Some(format) => {
+                    let plan = nodeqr::plan_render(
+                        item.payload()?,
+                        ecl,
+                        &create_options,
+                        Some(size),
+                        None,
+                        dark_color,
+                        light_color,
+                    )?;
+                    Ok(Either::B(raster::render(&plan_scene(&plan), format, style.encoding())?.into()))
+                }
+            };
+        };
 
-    Ok(webp_buffer.into())
+        let mut generator = QrGenerator::new(
+            item.payload()?,
+            size,
+            style.margin.unwrap_or(20),
+            parse_ec_level(style.error_correction.as_deref()),
+            &create_options,
+        )?;
+        apply_colors(
+            &mut generator,
+            style.background_color.as_deref(),
+            style.foreground_color.as_deref(),
+        )?;
+
+        if let (Some(format), Some(image)) = (self.format, &self.logo_image) {
+            let scene = generator.raster_scene(Some(image), &self.logo);
+            return Ok(Either::B(raster::render(&scene, format, style.encoding())?.into()));
+        }
+        let svg = generator.build_svg(Some(logo_data_url.clone()), &self.logo);
+        match self.format {
+            None => Ok(Either::A(svg)),
+            Some(format) => Ok(Either::B(rasterize_svg(&svg, None, None, format, style.encoding())?.into())),
+        }
+    }
+}
+
+/// Encode and render every item with one shared style, in parallel on the
+/// rayon thread pool, crossing the napi boundary once for the whole batch.
+/// `format` is `"svg"` (default), `"png"`, `"jpeg"`, `"webp"` or `"avif"`.
+/// Results keep the order of `items`; an item that fails gets its `error` and
+/// `code` instead of failing the batch. Only an invalid style, logo or format
+/// rejects the whole batch.
+#[napi]
+pub fn generate_batch(
+    items: Vec<BatchItem>,
+    shared_style: Option<BatchStyle>,
+    format: Option<String>,
+) -> AsyncTask<tasks::BatchTask> {
+    AsyncTask::new(tasks::BatchTask(Some(Box::new(move || {
+        use rayon::prelude::*;
+
+        let batch = Batch::new(shared_style.unwrap_or_default(), format.as_deref())?;
+        Ok(items
+            .par_iter()
+            .map(|item| match batch.render(item) {
+                Ok(output) => BatchResult {
+                    output: Some(output),
+                    error: None,
+                    code: None,
+                    needed: None,
+                    available: None,
+                },
+                Err(error) => {
+                    let (needed, available) = match error.status {
+                        ErrorCode::DataTooLong { needed, available } => {
+                            (Some(needed as u32), Some(available as u32))
+                        }
+                        _ => (None, None),
+                    };
+                    BatchResult {
+                        output: None,
+                        code: Some(error.status.as_ref().to_string()),
+                        error: Some(error.reason),
+                        needed,
+                        available,
+                    }
+                }
+            })
+            .collect())
+    }))))
 }
 
 #[napi(object)]
@@ -532,7 +1818,7 @@ fn try_decode_luma(
                         quircs::EccLevel::Q => "Q",
                         quircs::EccLevel::H => "H",
                     };
-                    let payload = String::from_utf8_lossy(&data.payload).to_string();
+                    let payload = payload_text(&code, &data);
                     return Ok(DecodeResult {
                         valid: true,
                         data: Some(payload),
@@ -551,20 +1837,81 @@ fn try_decode_luma(
     Err(last_error)
 }
 
-fn decode_qr_from_image(input: &[u8]) -> std::result::Result<DecodeResult, String> {
+/// The text of a decoded symbol. Kanji segments carry Shift JIS and the
+/// others their own bytes, usually UTF-8, but quirc joins every segment into
+/// one payload and only reports the highest mode in it. Symbols with Kanji
+/// therefore have their segments read back from the modules so each is
+/// decoded with its own character set. When that reading disagrees with
+/// quirc's error-corrected payload, the whole payload is taken as Shift JIS
+/// only if it is valid Shift JIS and not valid UTF-8.
+fn payload_text(code: &quircs::Code, data: &quircs::Data) -> String {
+    if data.data_type != Some(quircs::DataType::Kanji) {
+        return String::from_utf8_lossy(&data.payload).to_string();
+    }
+
+    let ecl = match data.ecc_level {
+        quircs::EccLevel::L => EcLevel::L,
+        quircs::EccLevel::M => EcLevel::M,
+        quircs::EccLevel::Q => EcLevel::Q,
+        quircs::EccLevel::H => EcLevel::H,
+    };
+    let size = code.size as usize;
+    let segments = nodeqr::read_segments(data.version, ecl, data.mask as u32, |row, col| {
+        let index = row * size + col;
+        code.cell_bitmap[index / 8] >> (index % 8) & 1 != 0
+    });
+
+    match segments {
+        Some(segments)
+            if segments
+                .iter()
+                .flat_map(|segment| &segment.bytes)
+                .eq(data.payload.iter()) =>
+        {
+            // Consecutive non-Kanji segments are decoded together, so a
+            // character split across them survives
+            let mut text = String::new();
+            let mut bytes = Vec::new();
+            for segment in segments {
+                if segment.kanji {
+                    text.push_str(&String::from_utf8_lossy(&bytes));
+                    bytes.clear();
+                    text.push_str(&sjis::decode_sjis(&segment.bytes));
+                } else {
+                    bytes.extend(segment.bytes);
+                }
+            }
+            text.push_str(&String::from_utf8_lossy(&bytes));
+            text
+        }
+        _ => match std::str::from_utf8(&data.payload) {
+            Ok(text) => text.to_string(),
+            Err(_) => {
+                let text = sjis::decode_sjis(&data.payload);
+                if text.contains(char::REPLACEMENT_CHARACTER) {
+                    String::from_utf8_lossy(&data.payload).to_string()
+                } else {
+                    text
+                }
+            }
+        },
+    }
+}
+
+fn decode_qr_from_image(input: &[u8]) -> std::result::Result<DecodeResult, QrError> {
     let img = image::load_from_memory(input)
-        .map_err(|e| format!("Failed to load image: {}", e))?;
+        .map_err(|e| QrError::Decode(format!("Failed to load image: {}", e)))?;
     let mut gray = img.to_luma8();
     let (width, height) = gray.dimensions();
 
     match try_decode_luma(&gray, width, height) {
-        Ok(result) => return Ok(result),
+        Ok(result) => Ok(result),
         Err(first_error) => {
             for p in gray.iter_mut() {
                 *p = 255 - *p;
             }
             match try_decode_luma(&gray, width, height) {
-                Ok(result) => return Ok(result),
+                Ok(result) => Ok(result),
                 Err(second_error) => {
                     let error = second_error
                         .or(first_error)
@@ -587,7 +1934,7 @@ fn decode_qr_from_image(input: &[u8]) -> std::result::Result<DecodeResult, Strin
 pub fn decode(input: Buffer) -> Result<Option<String>> {
     match decode_qr_from_image(&input) {
         Ok(result) => Ok(result.data),
-        Err(e) => Err(Error::from_reason(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -595,7 +1942,7 @@ pub fn decode(input: Buffer) -> Result<Option<String>> {
 pub fn decode_detailed(input: Buffer) -> Result<DecodeResult> {
     match decode_qr_from_image(&input) {
         Ok(result) => Ok(result),
-        Err(e) => Err(Error::from_reason(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -603,6 +1950,6 @@ pub fn decode_detailed(input: Buffer) -> Result<DecodeResult> {
 pub fn validate_qr(input: Buffer) -> Result<DecodeResult> {
     match decode_qr_from_image(&input) {
         Ok(result) => Ok(result),
-        Err(e) => Err(Error::from_reason(e)),
+        Err(e) => Err(e.into()),
     }
 }
\ No newline at end of file
@@ -0,0 +1,458 @@
+//! Micro QR Code (ISO/IEC 18004, versions M1 to M4) encoder.
+//!
+//! Micro QR is not part of `node-qrcode`. It shares the segment encoding,
+//! bit buffer and Reed-Solomon code of `nodeqr.rs` and returns the same
+//! `BitMatrix`, so every renderer draws it without changes. A symbol has a
+//! single finder pattern, timing patterns along the top row and left column,
+//! one error-correction block and four mask patterns, and needs only a
+//! 2-module quiet zone.
+//!
+//! The data is stored as one segment in the most compact mode that covers the
+//! whole input: Numeric, Alphanumeric, Kanji (when enabled) or Byte. M1 holds
+//! digits only and M2 digits or alphanumerics.
+
+use std::ops::RangeInclusive;
+
+use crate::error::QrError;
+use crate::nodeqr::{
+    check_input_present, get_bch_digit, is_alphanumeric_char, is_kanji_char, is_numeric_char,
+    rs_encode, segments_from_array, BitBuffer, BitMatrix, CreateOptions, EcLevel, Mode, ModuleRole,
+    QrData, Segment, Symbol,
+};
+
+/// Quiet zone a Micro QR symbol needs on every side, in modules.
+pub const QUIET_ZONE: i64 = 2;
+
+/// Total codewords of M1 to M4.
+const TOTAL_CODEWORDS: [usize; 4] = [5, 10, 17, 24];
+
+/// Data capacity in bits of M1 to M4 at levels L, M and Q, `0` where the
+/// level does not exist. M1 only offers error detection and is listed under L.
+/// The 84 and 68 bit capacities of M3 (and 20 of M1) end in a 4-bit codeword.
+#[rustfmt::skip]
+const DATA_BITS: [[usize; 3]; 4] = [
+    [20, 0, 0],
+    [40, 32, 0],
+    [84, 68, 0],
+    [128, 112, 80],
+];
+
+fn get_symbol_size(version: usize) -> usize {
+    version * 2 + 9
+}
+
+/// Data capacity of `version` at `ecl`, or `None` when the level is not
+/// available in that version.
+fn get_data_bits(version: usize, ecl: EcLevel) -> Option<usize> {
+    let index = match ecl {
+        EcLevel::L => 0,
+        EcLevel::M => 1,
+        EcLevel::Q => 2,
+        EcLevel::H => return None,
+    };
+    Some(DATA_BITS[version - 1][index]).filter(|&bits| bits > 0)
+}
+
+/// Symbol number carried in the format information, one per version and
+/// level combination.
+fn get_symbol_number(version: usize, ecl: EcLevel) -> u32 {
+    match (version, ecl) {
+        (1, _) => 0,
+        (2, EcLevel::L) => 1,
+        (2, _) => 2,
+        (3, EcLevel::L) => 3,
+        (3, _) => 4,
+        (4, EcLevel::L) => 5,
+        (4, EcLevel::M) => 6,
+        _ => 7,
+    }
+}
+
+/// Mode indicator value. It is `version - 1` bits long, so M1 has none.
+fn mode_indicator(mode: Mode) -> u32 {
+    match mode {
+        Mode::Numeric => 0,
+        Mode::Alphanumeric => 1,
+        Mode::Byte => 2,
+        Mode::Kanji => 3,
+        Mode::Fnc1First => unreachable!("Micro QR Codes do not encode FNC1"),
+    }
+}
+
+/// Character count indicator length, or `None` when `version` cannot hold
+/// `mode` at all.
+fn cc_bits(mode: Mode, version: usize) -> Option<usize> {
+    let bits = match mode {
+        Mode::Numeric => [3, 4, 5, 6],
+        Mode::Alphanumeric => [0, 3, 4, 5],
+        Mode::Byte => [0, 0, 4, 5],
+        Mode::Kanji => [0, 0, 3, 4],
+        Mode::Fnc1First => return None,
+    };
+    Some(bits[version - 1]).filter(|&bits| bits > 0)
+}
+
+/// Terminator length: 3, 5, 7 or 9 zero bits.
+fn terminator_bits(version: usize) -> usize {
+    version * 2 + 1
+}
+
+/// Bits `segment` takes in `version`, or `None` when it cannot be stored.
+fn get_segment_bits(segment: &Segment, version: usize) -> Option<usize> {
+    let cc_bits = cc_bits(segment.mode, version)?;
+    if segment.get_length() >= 1 << cc_bits {
+        return None;
+    }
+    Some(version - 1 + cc_bits + segment.get_bits_length())
+}
+
+/// The single segment holding `data`. Caller-defined segments are accepted
+/// as long as there is only one. Shared with `rmqr.rs`.
+pub(crate) fn get_segment(data: QrData, kanji: bool) -> Result<Segment, QrError> {
+    Ok(match data {
+        QrData::Text(text) => {
+            let mode = if text.chars().all(is_numeric_char) {
+                Mode::Numeric
+            } else if text.chars().all(|c| is_numeric_char(c) || is_alphanumeric_char(c)) {
+                Mode::Alphanumeric
+            } else if kanji && text.chars().all(is_kanji_char) {
+                Mode::Kanji
+            } else {
+                Mode::Byte
+            };
+            Segment::new(mode, text)
+        }
+        QrData::Bytes(bytes) => Segment::from_bytes(bytes),
+        QrData::Segments(inputs) if inputs.len() == 1 => segments_from_array(&inputs)?.remove(0),
+        QrData::Segments(_) => {
+            return Err(QrError::InvalidOption(
+                "Micro QR and rMQR Codes take a single segment".to_string(),
+            ))
+        }
+    })
+}
+
+/// Resolve the version constraints, counting M1 to M4 as 1 to 4.
+fn version_range(options: &CreateOptions) -> Result<RangeInclusive<usize>, QrError> {
+    for v in [options.version, options.min_version, options.max_version].into_iter().flatten() {
+        if !(1..=4).contains(&v) {
+            return Err(QrError::InvalidOption(format!(
+                "Invalid Micro QR Code version: {}. Expected 1 to 4 (M1 to M4)",
+                v
+            )));
+        }
+    }
+
+    if let Some(v) = options.version {
+        return Ok(v..=v);
+    }
+
+    let min = options.min_version.unwrap_or(1);
+    let max = options.max_version.unwrap_or(4);
+    if min > max {
+        return Err(QrError::InvalidOption(format!(
+            "Invalid Micro QR Code version range: minimum {} is greater than maximum {}",
+            min, max
+        )));
+    }
+    Ok(min..=max)
+}
+
+/// Smallest version in `versions` that holds `segment` at `ecl`.
+fn get_best_version(segment: &Segment, ecl: EcLevel, versions: RangeInclusive<usize>) -> Option<usize> {
+    versions.into_iter().find(|&v| {
+        match (get_data_bits(v, ecl), get_segment_bits(segment, v)) {
+            (Some(capacity), Some(bits)) => bits <= capacity,
+            _ => false,
+        }
+    })
+}
+
+/// `QrError::DataTooLong` for `segment`: its bits in the largest version of
+/// `versions` that offers `ecl` and its mode, against that version's data
+/// bits. With no such version nothing is available, and the bits are counted
+/// in the first version that could hold the mode.
+fn data_too_long(segment: &Segment, ecl: EcLevel, versions: RangeInclusive<usize>, message: String) -> QrError {
+    let holds_mode = |v: usize| get_data_bits(v, ecl).is_some() && cc_bits(segment.mode, v).is_some();
+    let allowed = versions.rev().find(|&v| holds_mode(v));
+    let version = allowed.or_else(|| (1..=4).find(|&v| holds_mode(v))).unwrap_or(4);
+    QrError::DataTooLong {
+        message,
+        needed: version - 1 + cc_bits(segment.mode, version).unwrap_or(0) + segment.get_bits_length(),
+        available: allowed.and_then(|v| get_data_bits(v, ecl)).unwrap_or(0),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Data and error correction codewords
+// ---------------------------------------------------------------------------
+
+/// Encode `segment` and append the error correction codewords. The result is
+/// the final bit stream; for M1 and M3 the last data codeword is 4 bits long.
+fn create_data(version: usize, ecl: EcLevel, segment: &Segment) -> BitBuffer {
+    // `get_best_version` only returns versions and levels that exist.
+    let capacity = get_data_bits(version, ecl).unwrap_or(0);
+    let cc_bits = cc_bits(segment.mode, version).unwrap_or(0);
+
+    let mut buffer = BitBuffer::new();
+    buffer.put(mode_indicator(segment.mode), version - 1);
+    buffer.put(segment.get_length() as u32, cc_bits);
+    segment.write(&mut buffer);
+
+    let terminator = terminator_bits(version).min(capacity - buffer.get_length_in_bits());
+    buffer.put(0, terminator);
+
+    // Pad codewords fill the 8-bit codewords; a trailing 4-bit codeword is
+    // padded with 0000.
+    let byte_bits = capacity / 8 * 8;
+    if buffer.get_length_in_bits() < byte_bits {
+        while !buffer.get_length_in_bits().is_multiple_of(8) {
+            buffer.put_bit(false);
+        }
+        let mut i = 0;
+        while buffer.get_length_in_bits() < byte_bits {
+            buffer.put(if i % 2 != 0 { 0x11 } else { 0xEC }, 8);
+            i += 1;
+        }
+    }
+    while buffer.get_length_in_bits() < capacity {
+        buffer.put_bit(false);
+    }
+
+    // A 4-bit codeword sits in the high nibble of the last byte, which is how
+    // the Reed-Solomon code sees it.
+    let data = &buffer.buffer;
+    let ec_count = TOTAL_CODEWORDS[version - 1] - data.len();
+    let mut ec_data = vec![0u8; ec_count];
+    rs_encode(data, &mut ec_data);
+
+    let mut message = BitBuffer::new();
+    for (i, &codeword) in data.iter().enumerate() {
+        if i == data.len() - 1 && capacity % 8 == 4 {
+            message.put(codeword as u32 >> 4, 4);
+        } else {
+            message.put(codeword as u32, 8);
+        }
+    }
+    for &codeword in &ec_data {
+        message.put(codeword as u32, 8);
+    }
+
+    message
+}
+
+// ---------------------------------------------------------------------------
+// Matrix assembly
+// ---------------------------------------------------------------------------
+
+/// Finder pattern with its separator, timing patterns and the reserved
+/// format information area.
+fn setup_function_patterns(matrix: &mut BitMatrix) {
+    let size = matrix.size();
+
+    for r in 0..=7 {
+        for c in 0..=7 {
+            let dark = ((0..=6).contains(&r) && (c == 0 || c == 6))
+                || ((0..=6).contains(&c) && (r == 0 || r == 6))
+                || ((2..=4).contains(&r) && (2..=4).contains(&c));
+            matrix.set(r, c, dark, ModuleRole::Finder);
+        }
+    }
+
+    for i in 8..size {
+        let value = i % 2 == 0;
+        matrix.set(0, i, value, ModuleRole::Timing);
+        matrix.set(i, 0, value, ModuleRole::Timing);
+    }
+
+    for i in 1..=8 {
+        matrix.set(8, i, false, ModuleRole::Format);
+        matrix.set(i, 8, false, ModuleRole::Format);
+    }
+}
+
+/// Place the bit stream in two-module columns, zig-zagging from the bottom
+/// right corner. Unlike a QR Code there is no vertical timing column to skip.
+/// The first `data_bits` bits are data, the rest error correction.
+fn setup_data(matrix: &mut BitMatrix, message: &BitBuffer, data_bits: usize) {
+    let size = matrix.size();
+    let total_bits = message.get_length_in_bits();
+    let mut bit_index = 0usize;
+    let mut upward = true;
+
+    let mut col = size - 1;
+    while col > 0 {
+        for i in 0..size {
+            let row = if upward { size - 1 - i } else { i };
+            for c in [col, col - 1] {
+                if matrix.is_reserved(row, c) {
+                    continue;
+                }
+                let dark = bit_index < total_bits
+                    && (message.buffer[bit_index / 8] >> (7 - bit_index % 8)) & 1 == 1;
+                let role = if bit_index < data_bits {
+                    ModuleRole::Data(0)
+                } else if bit_index < total_bits {
+                    ModuleRole::ErrorCorrection(0)
+                } else {
+                    ModuleRole::Remainder
+                };
+                matrix.set(row, c, dark, role);
+                bit_index += 1;
+            }
+        }
+        upward = !upward;
+        col -= 2;
+    }
+}
+
+fn get_mask_at(mask_pattern: u32, i: usize, j: usize) -> bool {
+    match mask_pattern {
+        0 => i.is_multiple_of(2),
+        1 => ((i / 2) + (j / 3)).is_multiple_of(2),
+        2 => ((i * j) % 2 + (i * j) % 3).is_multiple_of(2),
+        3 => ((i + j) % 2 + (i * j) % 3).is_multiple_of(2),
+        _ => panic!("bad Micro QR maskPattern: {}", mask_pattern),
+    }
+}
+
+fn apply_mask(pattern: u32, matrix: &mut BitMatrix) {
+    matrix.apply_mask(|row, col| get_mask_at(pattern, row, col));
+}
+
+/// Mask evaluation score: dark modules along the right and bottom edges,
+/// with the smaller count weighted by 16. Higher is better.
+fn get_mask_score(matrix: &BitMatrix) -> usize {
+    let last = matrix.size() - 1;
+    let right = (1..=last).filter(|&row| matrix.get(row, last) != 0).count();
+    let bottom = (1..=last).filter(|&col| matrix.get(last, col) != 0).count();
+
+    if right <= bottom {
+        right * 16 + bottom
+    } else {
+        bottom * 16 + right
+    }
+}
+
+/// Highest-scoring pattern, preferring the earliest pattern on ties.
+fn get_best_mask(matrix: &mut BitMatrix) -> u32 {
+    let mut best_pattern = 0u32;
+    let mut best_score = 0usize;
+
+    for p in 0..4u32 {
+        apply_mask(p, matrix);
+        let score = get_mask_score(matrix);
+        if p == 0 || score > best_score {
+            best_score = score;
+            best_pattern = p;
+        }
+        apply_mask(p, matrix); // undo
+    }
+
+    best_pattern
+}
+
+fn format_get_encoded_bits(symbol_number: u32, mask: u32) -> u32 {
+    let g15: u32 = (1 << 10) | (1 << 8) | (1 << 5) | (1 << 4) | (1 << 2) | (1 << 1) | 1;
+    let g15_mask: u32 = (1 << 14) | (1 << 10) | (1 << 6) | (1 << 2) | 1;
+    let g15_bch = get_bch_digit(g15);
+
+    let data = (symbol_number << 2) | mask;
+    let mut d = data << 10;
+
+    while get_bch_digit(d) - g15_bch >= 0 {
+        d ^= g15 << (get_bch_digit(d) - g15_bch);
+    }
+
+    ((data << 10) | d) ^ g15_mask
+}
+
+/// Write the 15 format bits: bits 0-7 down column 8 from row 1, bits 14-7
+/// along row 8 from column 1.
+fn setup_format_info(matrix: &mut BitMatrix, symbol_number: u32, mask_pattern: u32) {
+    let bits = format_get_encoded_bits(symbol_number, mask_pattern);
+
+    for i in 0..8usize {
+        matrix.set(i + 1, 8, (bits >> i) & 1 == 1, ModuleRole::Format);
+        matrix.set(8, i + 1, (bits >> (14 - i)) & 1 == 1, ModuleRole::Format);
+    }
+}
+
+/// Micro QR counterpart of `nodeqr::create_symbol`. `options.version`,
+/// `min_version` and `max_version` count M1 to M4 as 1 to 4, and
+/// `mask_pattern` takes 0 to 3.
+pub fn create(data: QrData, ecl: EcLevel, options: &CreateOptions) -> Result<Symbol, QrError> {
+    if let Some(p) = options.mask_pattern.filter(|&p| p > 3) {
+        return Err(QrError::InvalidOption(format!(
+            "Invalid Micro QR Code mask pattern: {}. Expected 0 to 3",
+            p
+        )));
+    }
+
+    check_input_present(&data)?;
+
+    if options.eci.is_some() {
+        return Err(QrError::InvalidOption(
+            "Micro QR Codes do not support ECI".to_string(),
+        ));
+    }
+
+    if options.gs1 {
+        return Err(QrError::InvalidOption(
+            "Micro QR Codes do not support GS1 data".to_string(),
+        ));
+    }
+
+    if options.boost_ecl {
+        return Err(QrError::InvalidOption(
+            "Micro QR Codes do not support boosting the error correction level".to_string(),
+        ));
+    }
+
+    if ecl == EcLevel::H {
+        return Err(QrError::InvalidOption(
+            "Micro QR Codes support error correction levels L, M and Q only".to_string(),
+        ));
+    }
+
+    let versions = version_range(options)?;
+    let segment = get_segment(data, options.kanji)?;
+
+    let version = match get_best_version(&segment, ecl, versions.clone()) {
+        Some(v) => v,
+        None => {
+            let message = match get_best_version(&segment, ecl, 1..=4) {
+                Some(required) => format!(
+                    "The allowed Micro QR Code versions (M{} to M{}) cannot contain this amount of data. \
+                     Minimum version required to store current data is: M{}",
+                    versions.start(),
+                    versions.end(),
+                    required
+                ),
+                None => "The amount of data is too big to be stored in a Micro QR Code".to_string(),
+            };
+            return Err(data_too_long(&segment, ecl, versions, message));
+        }
+    };
+
+    let message = create_data(version, ecl, &segment);
+
+    let mut modules = BitMatrix::new(get_symbol_size(version));
+    setup_function_patterns(&mut modules);
+    setup_data(&mut modules, &message, get_data_bits(version, ecl).unwrap_or(0));
+
+    let mask_pattern = match options.mask_pattern {
+        Some(p) => p,
+        None => get_best_mask(&mut modules),
+    };
+
+    apply_mask(mask_pattern, &mut modules);
+    setup_format_info(&mut modules, get_symbol_number(version, ecl), mask_pattern);
+
+    Ok(Symbol {
+        modules,
+        version,
+        ecl,
+        mask_pattern: Some(mask_pattern),
+    })
+}
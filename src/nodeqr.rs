@@ -8,13 +8,21 @@
 //! The structure intentionally mirrors the original JavaScript files
 //! (`lib/core/*` and `lib/renderer/svg-tag.js`) so the two can be diffed.
 //!
-//! Kanji mode is intentionally unsupported: `node-qrcode` only enables Kanji
-//! when a `toSJISFunc` is supplied, which `qrbit` never does. Kanji characters
-//! therefore fall through to Byte mode exactly as they do in the JS library.
+//! Kanji mode is off by default: `node-qrcode` only enables Kanji when a
+//! `toSJISFunc` is supplied, so by default Kanji characters fall through to
+//! Byte mode exactly as they do in the JS library. Setting
+//! `CreateOptions::kanji` enables it with the built-in Shift JIS table in
+//! `sjis.rs`.
 
-use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::sync::OnceLock;
 
+use crate::error::QrError;
+use crate::gs1;
+use crate::microqr;
+use crate::rmqr;
+use crate::sjis::to_sjis;
+
 // ---------------------------------------------------------------------------
 // error-correction-level.js
 // ---------------------------------------------------------------------------
@@ -48,6 +56,25 @@ impl EcLevel {
         }
     }
 
+    /// The next stronger level, `None` for H.
+    fn next(self) -> Option<EcLevel> {
+        match self {
+            EcLevel::L => Some(EcLevel::M),
+            EcLevel::M => Some(EcLevel::Q),
+            EcLevel::Q => Some(EcLevel::H),
+            EcLevel::H => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EcLevel::L => "L",
+            EcLevel::M => "M",
+            EcLevel::Q => "Q",
+            EcLevel::H => "H",
+        }
+    }
+
     /// Mirrors `ECLevel.from(value, ECLevel.M)`.
     pub fn from_str_or_m(value: Option<&str>) -> EcLevel {
         match value {
@@ -64,14 +91,18 @@ impl EcLevel {
 }
 
 // ---------------------------------------------------------------------------
-// mode.js (Kanji omitted — falls back to Byte just like the JS default)
+// mode.js
 // ---------------------------------------------------------------------------
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-enum Mode {
+pub(crate) enum Mode {
     Numeric,
     Alphanumeric,
     Byte,
+    Kanji,
+    /// FNC1 in first position, marking GS1 data. Not part of node-qrcode; it
+    /// is a bare mode indicator with no character count or data.
+    Fnc1First,
 }
 
 impl Mode {
@@ -81,6 +112,41 @@ impl Mode {
             Mode::Numeric => 1 << 0,
             Mode::Alphanumeric => 1 << 1,
             Mode::Byte => 1 << 2,
+            Mode::Kanji => 1 << 3,
+            Mode::Fnc1First => 0b0101,
+        }
+    }
+
+    /// `Mode.toString(mode)`.
+    fn id(self) -> &'static str {
+        match self {
+            Mode::Numeric => "Numeric",
+            Mode::Alphanumeric => "Alphanumeric",
+            Mode::Byte => "Byte",
+            Mode::Kanji => "Kanji",
+            Mode::Fnc1First => "FNC1",
+        }
+    }
+
+    /// `Mode.fromString(value)` for the modes a caller can pick.
+    fn from_name(name: &str) -> Option<Mode> {
+        match name.to_lowercase().as_str() {
+            "numeric" => Some(Mode::Numeric),
+            "alphanumeric" => Some(Mode::Alphanumeric),
+            "byte" => Some(Mode::Byte),
+            "kanji" => Some(Mode::Kanji),
+            _ => None,
+        }
+    }
+
+    /// Whether this mode's character set contains `c`.
+    fn can_encode(self, c: char) -> bool {
+        match self {
+            Mode::Numeric => is_numeric_char(c),
+            Mode::Alphanumeric => is_numeric_char(c) || is_alphanumeric_char(c),
+            Mode::Byte => true,
+            Mode::Kanji => is_kanji_char(c),
+            Mode::Fnc1First => false,
         }
     }
 
@@ -97,6 +163,8 @@ impl Mode {
             Mode::Numeric => [10, 12, 14][idx],
             Mode::Alphanumeric => [9, 11, 13][idx],
             Mode::Byte => [8, 16, 16][idx],
+            Mode::Kanji => [8, 10, 12][idx],
+            Mode::Fnc1First => 0,
         }
     }
 }
@@ -120,7 +188,7 @@ fn get_symbol_total_codewords(version: usize) -> usize {
     CODEWORDS_COUNT[version]
 }
 
-fn get_bch_digit(mut data: u32) -> i32 {
+pub(crate) fn get_bch_digit(mut data: u32) -> i32 {
     let mut digit = 0;
     while data != 0 {
         digit += 1;
@@ -180,8 +248,8 @@ fn gf() -> &'static GaloisField {
         let mut exp = [0u8; 512];
         let mut log = [0u8; 256];
         let mut x: u32 = 1;
-        for i in 0..255 {
-            exp[i] = x as u8;
+        for (i, e) in exp.iter_mut().enumerate().take(255) {
+            *e = x as u8;
             log[x as usize] = i as u8;
             x <<= 1;
             if x & 0x100 != 0 {
@@ -195,10 +263,6 @@ fn gf() -> &'static GaloisField {
     })
 }
 
-fn gf_exp(n: usize) -> u8 {
-    gf().exp[n]
-}
-
 fn gf_mul(x: u8, y: u8) -> u8 {
     if x == 0 || y == 0 {
         return 0;
@@ -208,63 +272,62 @@ fn gf_mul(x: u8, y: u8) -> u8 {
 }
 
 // ---------------------------------------------------------------------------
-// polynomial.js
+// polynomial.js — generator polynomials
 // ---------------------------------------------------------------------------
 
-fn poly_mul(p1: &[u8], p2: &[u8]) -> Vec<u8> {
-    let mut coeff = vec![0u8; p1.len() + p2.len() - 1];
-    for (i, &a) in p1.iter().enumerate() {
-        for (j, &b) in p2.iter().enumerate() {
-            coeff[i + j] ^= gf_mul(a, b);
-        }
-    }
-    coeff
-}
-
-fn poly_mod(divident: &[u8], divisor: &[u8]) -> Vec<u8> {
-    let mut result: Vec<u8> = divident.to_vec();
-
-    while (result.len() as isize - divisor.len() as isize) >= 0 {
-        let coeff = result[0];
-        for i in 0..divisor.len() {
-            result[i] ^= gf_mul(divisor[i], coeff);
-        }
-        // remove all zeros from buffer head
-        let mut offset = 0;
-        while offset < result.len() && result[offset] == 0 {
-            offset += 1;
+/// Largest number of error correction codewords per block in any QR Code,
+/// Micro QR or rMQR symbol.
+const MAX_EC_DEGREE: usize = 30;
+
+/// `Polynomial.generateECPolynomial(degree)` for every degree up to
+/// `MAX_EC_DEGREE`, built once. Each entry holds the coefficients after the
+/// implicit leading 1, highest power first.
+fn generator_polynomials() -> &'static [Vec<u8>] {
+    static POLYNOMIALS: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+    POLYNOMIALS.get_or_init(|| {
+        let g = gf();
+        let mut polynomials = Vec::with_capacity(MAX_EC_DEGREE + 1);
+        // Full coefficients, leading 1 included; multiplied by (x + a^i)
+        // in place for each degree.
+        let mut poly = vec![1u8];
+        for i in 0..=MAX_EC_DEGREE {
+            polynomials.push(poly[1..].to_vec());
+            poly.push(0);
+            for j in (1..poly.len()).rev() {
+                poly[j] ^= gf_mul(poly[j - 1], g.exp[i]);
+            }
         }
-        result = result[offset..].to_vec();
-    }
-
-    result
-}
-
-fn generate_ec_polynomial(degree: usize) -> Vec<u8> {
-    let mut poly = vec![1u8];
-    for i in 0..degree {
-        poly = poly_mul(&poly, &[1, gf_exp(i)]);
-    }
-    poly
+        polynomials
+    })
 }
 
 // ---------------------------------------------------------------------------
 // reed-solomon-encoder.js
 // ---------------------------------------------------------------------------
 
-fn rs_encode(data: &[u8], degree: usize, gen_poly: &[u8]) -> Vec<u8> {
-    let mut padded = vec![0u8; data.len() + degree];
-    padded[..data.len()].copy_from_slice(data);
-
-    let remainder = poly_mod(&padded, gen_poly);
-
-    let start = degree as isize - remainder.len() as isize;
-    if start > 0 {
-        let mut buff = vec![0u8; degree];
-        buff[start as usize..].copy_from_slice(&remainder);
-        buff
-    } else {
-        remainder
+/// Write the `ec.len()` error correction codewords of `data` into `ec`: the
+/// remainder of `data * x^degree` divided by the generator polynomial,
+/// computed with a shift register instead of long division.
+pub(crate) fn rs_encode(data: &[u8], ec: &mut [u8]) {
+    let g = gf();
+    let generator = &generator_polynomials()[ec.len()];
+    ec.fill(0);
+
+    for &codeword in data {
+        let factor = codeword ^ ec[0];
+        ec.copy_within(1.., 0);
+        if let Some(last) = ec.last_mut() {
+            *last = 0;
+        }
+        if factor == 0 {
+            continue;
+        }
+        let log_factor = g.log[factor as usize] as usize;
+        for (e, &c) in ec.iter_mut().zip(generator) {
+            if c != 0 {
+                *e ^= g.exp[g.log[c as usize] as usize + log_factor];
+            }
+        }
     }
 }
 
@@ -272,30 +335,30 @@ fn rs_encode(data: &[u8], degree: usize, gen_poly: &[u8]) -> Vec<u8> {
 // bit-buffer.js
 // ---------------------------------------------------------------------------
 
-struct BitBuffer {
-    buffer: Vec<u8>,
+pub(crate) struct BitBuffer {
+    pub(crate) buffer: Vec<u8>,
     length: usize,
 }
 
 impl BitBuffer {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         BitBuffer {
             buffer: Vec::new(),
             length: 0,
         }
     }
 
-    fn put(&mut self, num: u32, length: usize) {
+    pub(crate) fn put(&mut self, num: u32, length: usize) {
         for i in 0..length {
             self.put_bit(((num >> (length - i - 1)) & 1) == 1);
         }
     }
 
-    fn get_length_in_bits(&self) -> usize {
+    pub(crate) fn get_length_in_bits(&self) -> usize {
         self.length
     }
 
-    fn put_bit(&mut self, bit: bool) {
+    pub(crate) fn put_bit(&mut self, bit: bool) {
         let buf_index = self.length / 8;
         if self.buffer.len() <= buf_index {
             self.buffer.push(0);
@@ -311,39 +374,286 @@ impl BitBuffer {
 // bit-matrix.js
 // ---------------------------------------------------------------------------
 
+/// What a module is part of, recorded as the symbol is assembled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleRole {
+    /// Finder pattern with its separator, and rMQR's sub-finder and corner
+    /// patterns.
+    Finder,
+    Timing,
+    Alignment,
+    /// Format information, including the dark module.
+    Format,
+    Version,
+    /// Data codeword of the error correction block with this index.
+    Data(u8),
+    /// Error correction codeword of the block with this index.
+    ErrorCorrection(u8),
+    /// Remainder bits after the last codeword.
+    Remainder,
+}
+
+impl ModuleRole {
+    /// Function modules are reserved: data placement and masking skip them.
+    fn is_function(self) -> bool {
+        !matches!(
+            self,
+            ModuleRole::Data(_) | ModuleRole::ErrorCorrection(_) | ModuleRole::Remainder
+        )
+    }
+
+    /// Stable numeric code, from 0 for `Finder` to 7 for `Remainder` in
+    /// declaration order.
+    pub fn code(self) -> u8 {
+        match self {
+            ModuleRole::Finder => 0,
+            ModuleRole::Timing => 1,
+            ModuleRole::Alignment => 2,
+            ModuleRole::Format => 3,
+            ModuleRole::Version => 4,
+            ModuleRole::Data(_) => 5,
+            ModuleRole::ErrorCorrection(_) => 6,
+            ModuleRole::Remainder => 7,
+        }
+    }
+
+    /// Block index of a data or error correction module, otherwise 0.
+    pub fn block(self) -> u8 {
+        match self {
+            ModuleRole::Data(block) | ModuleRole::ErrorCorrection(block) => block,
+            _ => 0,
+        }
+    }
+}
+
+/// Module grid, bit-packed: row `r` is the `stride` words from `r * stride`,
+/// with column `c` at bit `c % 64` of word `c / 64`. Bits past `width` are
+/// always clear. A second plane marks the reserved function modules, and
+/// `roles` holds the role of every module, row by row.
+#[derive(Clone)]
 pub struct BitMatrix {
-    pub size: usize,
-    pub data: Vec<u8>,
-    reserved: Vec<u8>,
+    /// Modules per row.
+    pub width: usize,
+    /// Number of rows. Only rMQR symbols are not square.
+    pub height: usize,
+    /// Words per row.
+    stride: usize,
+    modules: Vec<u64>,
+    reserved: Vec<u64>,
+    roles: Vec<ModuleRole>,
 }
 
 impl BitMatrix {
-    fn new(size: usize) -> Self {
+    pub(crate) fn new(size: usize) -> Self {
+        Self::with_dimensions(size, size)
+    }
+
+    pub(crate) fn with_dimensions(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
         BitMatrix {
-            size,
-            data: vec![0u8; size * size],
-            reserved: vec![0u8; size * size],
+            width,
+            height,
+            stride,
+            modules: vec![0u64; stride * height],
+            reserved: vec![0u64; stride * height],
+            roles: vec![ModuleRole::Remainder; width * height],
         }
     }
 
-    fn set(&mut self, row: usize, col: usize, value: bool, reserved: bool) {
-        let index = row * self.size + col;
-        self.data[index] = value as u8;
-        if reserved {
-            self.reserved[index] = 1;
+    /// Side length of a square symbol, `bitMatrix.size` in node-qrcode.
+    pub fn size(&self) -> usize {
+        self.width
+    }
+
+    /// Set a module and record its role. Function roles reserve the module.
+    pub(crate) fn set(&mut self, row: usize, col: usize, value: bool, role: ModuleRole) {
+        let index = row * self.stride + col / 64;
+        let bit = 1u64 << (col % 64);
+        if value {
+            self.modules[index] |= bit;
+        } else {
+            self.modules[index] &= !bit;
         }
+        if role.is_function() {
+            self.reserved[index] |= bit;
+        }
+        self.roles[row * self.width + col] = role;
     }
 
     pub fn get(&self, row: usize, col: usize) -> u8 {
-        self.data[row * self.size + col]
+        (self.modules[row * self.stride + col / 64] >> (col % 64)) as u8 & 1
+    }
+
+    pub fn role(&self, row: usize, col: usize) -> ModuleRole {
+        self.roles[row * self.width + col]
+    }
+
+    pub(crate) fn is_reserved(&self, row: usize, col: usize) -> bool {
+        self.reserved[row * self.stride + col / 64] >> (col % 64) & 1 != 0
+    }
+
+    /// Rows packed 8 modules to a byte, leftmost module in the most
+    /// significant bit, each row padded to a whole byte like a PBM bitmap.
+    pub fn to_packed(&self) -> Vec<u8> {
+        let row_bytes = self.width.div_ceil(8);
+        let mut packed = Vec::with_capacity(row_bytes * self.height);
+        for row in 0..self.height {
+            for byte in 0..row_bytes {
+                let word = self.row(row)[byte / 8] >> (byte % 8 * 8);
+                packed.push((word as u8).reverse_bits());
+            }
+        }
+        packed
+    }
+
+    /// One line of `0` (light) and `1` (dark) characters per row.
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                text.push(if self.get(row, col) != 0 { '1' } else { '0' });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The packed words of `row`.
+    fn row(&self, row: usize) -> &[u64] {
+        &self.modules[row * self.stride..(row + 1) * self.stride]
+    }
+
+    /// Bits of word `word` that hold one of the first `count` columns.
+    fn columns_mask(word: usize, count: usize) -> u64 {
+        match count.saturating_sub(word * 64) {
+            0 => 0,
+            n if n >= 64 => u64::MAX,
+            n => (1u64 << n) - 1,
+        }
+    }
+
+    /// Flip every unreserved module where `mask(row, col)` holds, a word at
+    /// a time. `mask` is only evaluated over one `MASK_ROW_PERIOD`.
+    pub(crate) fn apply_mask(&mut self, mask: impl Fn(usize, usize) -> bool) {
+        let mut period = vec![0u64; MASK_ROW_PERIOD * self.stride];
+        fill_mask_period(&mut period, self.width, mask);
+        xor_mask(&mut self.modules, &self.reserved, self.stride, &period);
+    }
+}
+
+/// Rows after which every QR, Micro QR and rMQR mask pattern repeats: they
+/// depend on the row modulo 2, 3 or 4 (and the column modulo 6).
+const MASK_ROW_PERIOD: usize = 12;
+
+/// Pack `mask(row, col)` for the first `MASK_ROW_PERIOD` rows of a symbol
+/// `width` modules wide into `period`, rows of `period.len() /
+/// MASK_ROW_PERIOD` words like `BitMatrix`.
+fn fill_mask_period(period: &mut [u64], width: usize, mask: impl Fn(usize, usize) -> bool) {
+    let stride = period.len() / MASK_ROW_PERIOD;
+    period.fill(0);
+    for row in 0..MASK_ROW_PERIOD {
+        for col in 0..width {
+            if mask(row, col) {
+                period[row * stride + col / 64] |= 1 << (col % 64);
+            }
+        }
+    }
+}
+
+/// Flip the modules where the repeating `period` rows are set and the
+/// module is not `reserved`, a whole word at a time.
+fn xor_mask(modules: &mut [u64], reserved: &[u64], stride: usize, period: &[u64]) {
+    let rows = modules.chunks_mut(stride).zip(reserved.chunks(stride));
+    for (row, (modules, reserved)) in rows.enumerate() {
+        let mask = &period[row % MASK_ROW_PERIOD * stride..][..stride];
+        for ((module, &reserved), &mask) in modules.iter_mut().zip(reserved).zip(mask) {
+            *module ^= mask & !reserved;
+        }
+    }
+}
+
+/// Packed modules like a `BitMatrix`, without its reserved plane and roles:
+/// the scratch `get_mask_penalties` scores the patterns on.
+#[derive(Clone)]
+struct ModuleGrid {
+    width: usize,
+    height: usize,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl ModuleGrid {
+    fn new(width: usize, height: usize, words: Vec<u64>) -> Self {
+        let stride = width.div_ceil(64);
+        debug_assert_eq!(words.len(), stride * height);
+        ModuleGrid {
+            width,
+            height,
+            stride,
+            words,
+        }
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.stride..(row + 1) * self.stride]
     }
 
-    fn xor(&mut self, row: usize, col: usize, value: bool) {
-        self.data[row * self.size + col] ^= value as u8;
+    fn set(&mut self, row: usize, col: usize, dark: bool) {
+        let word = &mut self.words[row * self.stride + col / 64];
+        if dark {
+            *word |= 1 << (col % 64);
+        } else {
+            *word &= !(1 << (col % 64));
+        }
     }
 
-    fn is_reserved(&self, row: usize, col: usize) -> bool {
-        self.reserved[row * self.size + col] != 0
+    /// The grid with rows and columns swapped, so column scans can reuse the
+    /// row scans. Transposes 64x64 blocks of words.
+    fn transposed(&self) -> ModuleGrid {
+        let mut result = ModuleGrid::new(
+            self.height,
+            self.width,
+            vec![0u64; self.height.div_ceil(64) * self.width],
+        );
+        let mut block = [0u64; 64];
+        for row_block in 0..result.stride {
+            for word in 0..self.stride {
+                for (r, slot) in block.iter_mut().enumerate() {
+                    let row = row_block * 64 + r;
+                    *slot = if row < self.height {
+                        self.words[row * self.stride + word]
+                    } else {
+                        0
+                    };
+                }
+                transpose_block(&mut block);
+                for (c, &bits) in block.iter().enumerate() {
+                    let row = word * 64 + c;
+                    if row < result.height {
+                        result.words[row * result.stride + row_block] = bits;
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Transpose a 64x64 bit block in place (bit `c` of word `r` swaps with bit
+/// `r` of word `c`) by swapping ever smaller sub-blocks.
+fn transpose_block(block: &mut [u64; 64]) {
+    let mut j = 32;
+    let mut m: u64 = 0x0000_0000_FFFF_FFFF;
+    while j != 0 {
+        let mut k = 0;
+        while k < 64 {
+            let t = ((block[k] >> j) ^ block[k + j]) & m;
+            block[k] ^= t << j;
+            block[k + j] ^= t;
+            k = (k + j + 1) & !j;
+        }
+        j >>= 1;
+        m ^= m << j;
     }
 }
 
@@ -386,10 +696,7 @@ fn alignment_positions(version: usize) -> Vec<[usize; 2]> {
     for i in 0..pos_len {
         for j in 0..pos_len {
             // Skip positions occupied by finder patterns.
-            if (i == 0 && j == 0)
-                || (i == 0 && j == pos_len - 1)
-                || (i == pos_len - 1 && j == 0)
-            {
+            if (i == 0 && (j == 0 || j == pos_len - 1)) || (i == pos_len - 1 && j == 0) {
                 continue;
             }
             coords.push([pos[i], pos[j]]);
@@ -439,44 +746,53 @@ fn format_get_encoded_bits(ecl: EcLevel, mask: u32) -> u32 {
 // Segments — character classification (regex.js equivalents)
 // ---------------------------------------------------------------------------
 
-fn is_numeric_char(c: char) -> bool {
+pub(crate) fn is_numeric_char(c: char) -> bool {
     c.is_ascii_digit()
 }
 
-fn is_alphanumeric_char(c: char) -> bool {
+pub(crate) fn is_alphanumeric_char(c: char) -> bool {
     // Matches `[A-Z $%*+\-./:]` (note: digits are handled by the numeric class,
     // so the alphanumeric *segmentation* regex excludes them by design).
     matches!(c,
         'A'..='Z' | ' ' | '$' | '%' | '*' | '+' | '-' | '.' | '/' | ':')
 }
 
+/// Whether `c` can be stored in Kanji mode, i.e. has a double-byte Shift JIS
+/// code. node-qrcode approximates this with its `KANJI` regex.
+pub(crate) fn is_kanji_char(c: char) -> bool {
+    to_sjis(c).is_some()
+}
+
 #[derive(Clone, Debug)]
 struct RawSegment {
     data: String,
     mode: Mode,
 }
 
-/// Mirrors `getSegmentsFromString` with Kanji disabled: the input string is
-/// partitioned into maximal runs of Numeric, Alphanumeric and Byte characters.
-/// Because the three character classes are disjoint and we scan left to right,
-/// the produced segments are already ordered by index.
-fn get_segments_from_string(data: &str) -> Vec<RawSegment> {
+/// Mirrors `getSegmentsFromString`: the input string is partitioned into
+/// maximal runs of Numeric, Alphanumeric, Byte and (when `kanji` is enabled)
+/// Kanji characters. Because the character classes are disjoint and we scan
+/// left to right, the produced segments are already ordered by index.
+fn get_segments_from_string(data: &str, kanji: bool) -> Vec<RawSegment> {
     #[derive(PartialEq, Clone, Copy)]
     enum Class {
         Numeric,
         Alphanumeric,
         Byte,
+        Kanji,
     }
 
-    fn classify(c: char) -> Class {
+    let classify = |c: char| {
         if is_numeric_char(c) {
             Class::Numeric
         } else if is_alphanumeric_char(c) {
             Class::Alphanumeric
+        } else if kanji && is_kanji_char(c) {
+            Class::Kanji
         } else {
             Class::Byte
         }
-    }
+    };
 
     let mut segments: Vec<RawSegment> = Vec::new();
     let mut current: Option<(Class, String)> = None;
@@ -493,6 +809,7 @@ fn get_segments_from_string(data: &str) -> Vec<RawSegment> {
                             Class::Numeric => Mode::Numeric,
                             Class::Alphanumeric => Mode::Alphanumeric,
                             Class::Byte => Mode::Byte,
+                            Class::Kanji => Mode::Kanji,
                         },
                     });
                 }
@@ -507,6 +824,7 @@ fn get_segments_from_string(data: &str) -> Vec<RawSegment> {
                 Class::Numeric => Mode::Numeric,
                 Class::Alphanumeric => Mode::Alphanumeric,
                 Class::Byte => Mode::Byte,
+                Class::Kanji => Mode::Kanji,
             },
         });
     }
@@ -519,7 +837,7 @@ fn get_segments_from_string(data: &str) -> Vec<RawSegment> {
 // ---------------------------------------------------------------------------
 
 fn numeric_bits_length(length: usize) -> usize {
-    10 * (length / 3) + if length % 3 != 0 { (length % 3) * 3 + 1 } else { 0 }
+    10 * (length / 3) + if !length.is_multiple_of(3) { (length % 3) * 3 + 1 } else { 0 }
 }
 
 fn alphanumeric_bits_length(length: usize) -> usize {
@@ -530,29 +848,45 @@ fn byte_bits_length(length: usize) -> usize {
     length * 8
 }
 
+fn kanji_bits_length(length: usize) -> usize {
+    length * 13
+}
+
 fn segment_bits_length(length: usize, mode: Mode) -> usize {
     match mode {
         Mode::Numeric => numeric_bits_length(length),
         Mode::Alphanumeric => alphanumeric_bits_length(length),
         Mode::Byte => byte_bits_length(length),
+        Mode::Kanji => kanji_bits_length(length),
+        Mode::Fnc1First => 0,
     }
 }
 
 const ALPHA_NUM_CHARS: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
 
 /// A finalized segment ready to be encoded, equivalent to the NumericData /
-/// AlphanumericData / ByteData JS classes.
+/// AlphanumericData / ByteData / KanjiData JS classes.
 #[derive(Clone, Debug)]
-struct Segment {
-    mode: Mode,
-    /// For Numeric/Alphanumeric the textual data; unused for Byte.
+pub(crate) struct Segment {
+    pub(crate) mode: Mode,
+    /// For Numeric/Alphanumeric/Kanji the textual data; unused for Byte.
     text: String,
-    /// For Byte mode, the UTF-8 bytes (equivalent to `TextEncoder().encode`).
+    /// For Byte mode, the UTF-8 bytes (equivalent to `TextEncoder().encode`),
+    /// or the raw input bytes.
     bytes: Vec<u8>,
 }
 
 impl Segment {
-    fn new(mode: Mode, data: &str) -> Self {
+    /// A Byte-mode segment holding arbitrary bytes rather than UTF-8 text.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Self {
+        Segment {
+            mode: Mode::Byte,
+            text: String::new(),
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    pub(crate) fn new(mode: Mode, data: &str) -> Self {
         match mode {
             Mode::Byte => Segment {
                 mode,
@@ -567,19 +901,35 @@ impl Segment {
         }
     }
 
-    /// `getLength()` — char count for Numeric/Alphanumeric, byte count for Byte.
-    fn get_length(&self) -> usize {
+    /// `getLength()` — char count for Numeric/Alphanumeric/Kanji, byte count
+    /// for Byte.
+    pub(crate) fn get_length(&self) -> usize {
         match self.mode {
             Mode::Byte => self.bytes.len(),
             _ => self.text.chars().count(),
         }
     }
 
-    fn get_bits_length(&self) -> usize {
+    pub(crate) fn get_bits_length(&self) -> usize {
         segment_bits_length(self.get_length(), self.mode)
     }
 
-    fn write(&self, buffer: &mut BitBuffer) {
+    /// The message bytes a reader gets back from this segment: the
+    /// characters of Numeric and Alphanumeric data, the bytes of Byte data
+    /// and the Shift JIS codes of Kanji characters.
+    fn message_bytes(&self) -> Vec<u8> {
+        match self.mode {
+            Mode::Byte => self.bytes.clone(),
+            Mode::Kanji => self
+                .text
+                .chars()
+                .flat_map(|c| to_sjis(c).unwrap().to_be_bytes())
+                .collect(),
+            _ => self.text.as_bytes().to_vec(),
+        }
+    }
+
+    pub(crate) fn write(&self, buffer: &mut BitBuffer) {
         match self.mode {
             Mode::Numeric => {
                 let digits: Vec<u8> = self.text.bytes().collect();
@@ -622,196 +972,294 @@ impl Segment {
                     buffer.put(b as u32, 8);
                 }
             }
+            Mode::Kanji => {
+                for c in self.text.chars() {
+                    // Kanji segments only ever hold characters `is_kanji_char`
+                    // accepted, so the lookup cannot fail.
+                    let sjis = to_sjis(c).unwrap() as u32;
+                    let value = if (0x8140..=0x9FFC).contains(&sjis) {
+                        sjis - 0x8140
+                    } else {
+                        sjis - 0xC140
+                    };
+                    buffer.put(((value >> 8) & 0xFF) * 0xC0 + (value & 0xFF), 13);
+                }
+            }
+            Mode::Fnc1First => {}
         }
     }
 }
 
 // ---------------------------------------------------------------------------
-// Segments — graph node generation (segments.js)
+// ECI header (not part of node-qrcode)
 // ---------------------------------------------------------------------------
 
-/// UTF-8 byte length, equivalent to `getStringByteLength`.
-fn get_string_byte_length(s: &str) -> usize {
-    s.as_bytes().len()
+/// Largest ECI assignment number a designator can hold.
+const ECI_MAX: u32 = 999_999;
+
+const ECI_MODE_INDICATOR: u32 = 0b0111;
+
+/// Length of the ECI designator for `assignment`: one, two or three bytes
+/// with a `0`, `10` or `110` prefix.
+fn eci_designator_bits(assignment: u32) -> usize {
+    match assignment {
+        0..=127 => 8,
+        128..=16_383 => 16,
+        _ => 24,
+    }
 }
 
-#[derive(Clone, Debug)]
-struct GNode {
-    data: String,
-    mode: Mode,
-    length: usize,
+/// Bits taken by the optional ECI header (mode indicator plus designator).
+fn eci_header_bits(eci: Option<u32>) -> usize {
+    eci.map_or(0, |assignment| 4 + eci_designator_bits(assignment))
 }
 
-fn build_nodes(segs: &[RawSegment]) -> Vec<Vec<GNode>> {
-    let mut nodes = Vec::with_capacity(segs.len());
-    for seg in segs {
-        let char_len = seg.data.chars().count();
-        match seg.mode {
-            Mode::Numeric => nodes.push(vec![
-                GNode { data: seg.data.clone(), mode: Mode::Numeric, length: char_len },
-                GNode { data: seg.data.clone(), mode: Mode::Alphanumeric, length: char_len },
-                GNode { data: seg.data.clone(), mode: Mode::Byte, length: char_len },
-            ]),
-            Mode::Alphanumeric => nodes.push(vec![
-                GNode { data: seg.data.clone(), mode: Mode::Alphanumeric, length: char_len },
-                GNode { data: seg.data.clone(), mode: Mode::Byte, length: char_len },
-            ]),
-            Mode::Byte => nodes.push(vec![GNode {
-                data: seg.data.clone(),
-                mode: Mode::Byte,
-                length: get_string_byte_length(&seg.data),
-            }]),
-        }
-    }
-    nodes
-}
-
-/// Insertion-ordered adjacency used to faithfully mirror the iteration order of
-/// JavaScript object keys in the dijkstra step.
-struct Graph {
-    /// node id -> ordered list of (neighbour id, edge cost)
-    map: HashMap<String, Vec<(String, i64)>>,
-    /// node id -> the underlying GNode
-    table: HashMap<String, GNode>,
-}
-
-fn build_graph(nodes: &[Vec<GNode>], version: usize) -> Graph {
-    let mut map: HashMap<String, Vec<(String, i64)>> = HashMap::new();
-    let mut table: HashMap<String, GNode> = HashMap::new();
-    let mut last_count: HashMap<String, usize> = HashMap::new();
-
-    map.insert("start".to_string(), Vec::new());
-
-    let mut prev_node_ids: Vec<String> = vec!["start".to_string()];
-
-    for (i, node_group) in nodes.iter().enumerate() {
-        let mut current_node_ids: Vec<String> = Vec::new();
-
-        for (j, node) in node_group.iter().enumerate() {
-            let key = format!("{}{}", i, j);
-
-            current_node_ids.push(key.clone());
-            table.insert(key.clone(), node.clone());
-            last_count.insert(key.clone(), 0);
-            map.entry(key.clone()).or_default();
-
-            for prev_node_id in &prev_node_ids {
-                let prev_is_table = table.contains_key(prev_node_id);
-                let prev_mode = table.get(prev_node_id).map(|n| n.mode);
-
-                let cost: i64 = if prev_is_table && prev_mode == Some(node.mode) {
-                    let lc = *last_count.get(prev_node_id).unwrap();
-                    let c = segment_bits_length(lc + node.length, node.mode) as i64
-                        - segment_bits_length(lc, node.mode) as i64;
-                    *last_count.get_mut(prev_node_id).unwrap() += node.length;
-                    c
-                } else {
-                    if prev_is_table {
-                        last_count.insert(prev_node_id.clone(), node.length);
-                    }
-                    segment_bits_length(node.length, node.mode) as i64
-                        + 4
-                        + node.mode.cc_bits(version) as i64
-                };
+fn write_eci_header(buffer: &mut BitBuffer, assignment: u32) {
+    buffer.put(ECI_MODE_INDICATOR, 4);
+    match eci_designator_bits(assignment) {
+        8 => buffer.put(assignment, 8),
+        16 => buffer.put(0b10 << 14 | assignment, 16),
+        _ => buffer.put(0b110 << 21 | assignment, 24),
+    }
+}
 
-                map.entry(prev_node_id.clone())
-                    .or_default()
-                    .push((key.clone(), cost));
-            }
-        }
+// ---------------------------------------------------------------------------
+// Structured Append header (not part of node-qrcode)
+// ---------------------------------------------------------------------------
 
-        prev_node_ids = current_node_ids;
+/// Most symbols a Structured Append sequence can link.
+pub const STRUCTURED_APPEND_MAX: usize = 16;
+
+const STRUCTURED_APPEND_MODE_INDICATOR: u32 = 0b0011;
+
+/// Mode indicator, 4-bit position, 4-bit total and 8-bit parity.
+const STRUCTURED_APPEND_HEADER_BITS: usize = 20;
+
+/// Links a symbol into a Structured Append sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StructuredAppend {
+    /// Position of this symbol in the sequence, starting at 0.
+    pub index: u8,
+    /// Number of symbols in the sequence, 2 to 16.
+    pub total: u8,
+    /// XOR of every byte of the complete message, as encoded: Kanji
+    /// characters count as their Shift JIS bytes.
+    pub parity: u8,
+}
+
+impl StructuredAppend {
+    fn validate(&self) -> Result<(), QrError> {
+        if !(2..=STRUCTURED_APPEND_MAX).contains(&(self.total as usize)) {
+            return Err(QrError::InvalidOption(format!(
+                "Invalid Structured Append total: {}. Expected 2 to {}",
+                self.total, STRUCTURED_APPEND_MAX
+            )));
+        }
+        if self.index >= self.total {
+            return Err(QrError::InvalidOption(format!(
+                "Invalid Structured Append index: {}. Expected 0 to {}",
+                self.index,
+                self.total - 1
+            )));
+        }
+        Ok(())
     }
+}
 
-    for prev_node_id in &prev_node_ids {
-        map.entry(prev_node_id.clone())
-            .or_default()
-            .push(("end".to_string(), 0));
-    }
+/// Parity byte of a Structured Append sequence: the XOR of every message
+/// byte of the segments of all its symbols.
+fn structured_append_parity<'a>(segments: impl IntoIterator<Item = &'a Segment>) -> u8 {
+    segments
+        .into_iter()
+        .flat_map(Segment::message_bytes)
+        .fold(0, |parity, b| parity ^ b)
+}
 
-    Graph { map, table }
+fn write_structured_append_header(buffer: &mut BitBuffer, header: &StructuredAppend) {
+    buffer.put(STRUCTURED_APPEND_MODE_INDICATOR, 4);
+    buffer.put(header.index as u32, 4);
+    buffer.put(header.total as u32 - 1, 4);
+    buffer.put(header.parity as u32, 8);
 }
 
-/// Faithful port of `dijkstrajs.find_path` (naive priority queue, stable sort).
-fn dijkstra_find_path(graph: &Graph, s: &str, d: &str) -> Vec<String> {
-    let mut predecessors: HashMap<String, String> = HashMap::new();
-    let mut costs: HashMap<String, i64> = HashMap::new();
-    costs.insert(s.to_string(), 0);
+// ---------------------------------------------------------------------------
+// Segments — optimal mode selection (segments.js)
+// ---------------------------------------------------------------------------
+
+/// The modes a raw segment may be encoded in, in node-qrcode's `buildNodes`
+/// order.
+fn candidate_modes(mode: Mode) -> &'static [Mode] {
+    match mode {
+        Mode::Numeric => &[Mode::Numeric, Mode::Alphanumeric, Mode::Byte],
+        Mode::Alphanumeric => &[Mode::Alphanumeric, Mode::Byte],
+        Mode::Byte => &[Mode::Byte],
+        Mode::Kanji => &[Mode::Kanji, Mode::Byte],
+        Mode::Fnc1First => unreachable!("raw segments are never FNC1"),
+    }
+}
 
-    // Priority queue of (value, cost). `push` sorts by cost (stable), `pop`
-    // takes the front element — matching the JS `Array.sort` + `shift`.
-    let mut queue: Vec<(String, i64)> = vec![(s.to_string(), 0)];
+/// Length of `seg` encoded in `mode`: bytes for Byte (`getStringByteLength`),
+/// characters otherwise.
+fn node_length(seg: &RawSegment, mode: Mode) -> usize {
+    match mode {
+        Mode::Byte => seg.data.len(),
+        _ => seg.data.chars().count(),
+    }
+}
+
+/// Best path found so far to one candidate node of a raw segment.
+#[derive(Clone, Copy, Default)]
+struct PathNode {
+    /// Bits from the start of the data, as summed by the graph edges.
+    cost: usize,
+    /// Index of the previous segment's node on the path.
+    prev: usize,
+    /// `rank` of that previous node.
+    prev_rank: usize,
+    /// Position of this node in the order dijkstrajs pops its segment's
+    /// nodes; ties between equally short paths go to the earlier one.
+    rank: usize,
+}
+
+/// Pick the mode of every raw segment, reproducing node-qrcode's
+/// `buildGraph` + `dijkstra.find_path` result. The graph is layered (one
+/// layer per raw segment, one node per candidate mode), so a single forward
+/// pass finds the shortest path. Edge costs keep node-qrcode's `lastCount`
+/// bookkeeping, and ties are broken the way dijkstrajs's stable queue breaks
+/// them: by the cost, then pop order, of the predecessor, then node order.
+fn find_optimal_modes(segs: &[RawSegment], version: usize) -> Vec<Mode> {
+    let mut back: Vec<usize> = Vec::with_capacity(segs.len() * 3);
+    let mut prev_modes: &[Mode] = &[];
+    let mut prev = [PathNode::default(); 3];
 
-    while !queue.is_empty() {
-        // Stable sort by cost ascending; pop front.
-        queue.sort_by(|a, b| a.1.cmp(&b.1));
-        let (u, cost_of_s_to_u) = queue.remove(0);
+    for seg in segs {
+        let modes = candidate_modes(seg.mode);
+        let mut nodes = [PathNode::default(); 3];
+        // `table[prevNodeId].lastCount`, reset for every layer.
+        let mut last_count = [0usize; 3];
+
+        for (j, &mode) in modes.iter().enumerate() {
+            let length = node_length(seg, mode);
+            let new_segment_cost = segment_bits_length(length, mode) + 4 + mode.cc_bits(version);
+
+            if prev_modes.is_empty() {
+                nodes[j] = PathNode {
+                    cost: new_segment_cost,
+                    ..PathNode::default()
+                };
+                continue;
+            }
 
-        if let Some(adjacent) = graph.map.get(&u) {
-            for (v, cost_of_e) in adjacent {
-                let new_cost = cost_of_s_to_u + cost_of_e;
-                let first_visit = !costs.contains_key(v);
-                if first_visit || *costs.get(v).unwrap() > new_cost {
-                    costs.insert(v.clone(), new_cost);
-                    queue.push((v.clone(), new_cost));
-                    predecessors.insert(v.clone(), u.clone());
+            let mut best: Option<PathNode> = None;
+            for (n, &prev_mode) in prev_modes.iter().enumerate() {
+                let edge = if prev_mode == mode {
+                    let lc = last_count[n];
+                    last_count[n] += length;
+                    segment_bits_length(lc + length, mode) - segment_bits_length(lc, mode)
+                } else {
+                    last_count[n] = length;
+                    new_segment_cost
+                };
+                let candidate = PathNode {
+                    cost: prev[n].cost + edge,
+                    prev: n,
+                    prev_rank: prev[n].rank,
+                    rank: 0,
+                };
+                if best.is_none_or(|b| (candidate.cost, candidate.prev_rank) < (b.cost, b.prev_rank)) {
+                    best = Some(candidate);
                 }
             }
+            nodes[j] = best.unwrap_or_default();
+        }
+
+        let mut order = [(0, 0, 0); 3];
+        for (j, key) in order.iter_mut().enumerate() {
+            *key = (nodes[j].cost, nodes[j].prev_rank, j);
+        }
+        let order = &mut order[..modes.len()];
+        order.sort_unstable();
+        for (rank, &(_, _, j)) in order.iter().enumerate() {
+            nodes[j].rank = rank;
         }
+
+        back.extend(nodes[..modes.len()].iter().map(|node| node.prev));
+        back.resize(back.len() + 3 - modes.len(), 0);
+        prev = nodes;
+        prev_modes = modes;
     }
 
-    // extract_shortest_path_from_predecessor_list
-    let mut path: Vec<String> = Vec::new();
-    let mut u = Some(d.to_string());
-    while let Some(node) = u {
-        path.push(node.clone());
-        u = predecessors.get(&node).cloned();
+    // Every last node links to the end at no cost, so the end is reached
+    // through the node popped first.
+    let mut j = (0..prev_modes.len()).find(|&j| prev[j].rank == 0).unwrap_or(0);
+    let mut modes = vec![Mode::Byte; segs.len()];
+    for (i, seg) in segs.iter().enumerate().rev() {
+        modes[i] = candidate_modes(seg.mode)[j];
+        j = back[i * 3 + j];
     }
-    path.reverse();
-    path
+    modes
 }
 
-fn merge_segments(segs: Vec<GNode>) -> Vec<GNode> {
-    let mut acc: Vec<GNode> = Vec::new();
-    for curr in segs {
-        if let Some(prev) = acc.last_mut() {
-            if prev.mode == curr.mode {
-                prev.data.push_str(&curr.data);
-                continue;
-            }
+fn segments_from_string(data: &str, version: usize, kanji: bool) -> Vec<Segment> {
+    let segs = get_segments_from_string(data, kanji);
+    let modes = find_optimal_modes(&segs, version);
+
+    // `mergeSegments`: adjacent runs that ended up in the same mode are joined.
+    let mut merged: Vec<(Mode, String)> = Vec::new();
+    for (seg, mode) in segs.iter().zip(modes) {
+        match merged.last_mut() {
+            Some((last_mode, text)) if *last_mode == mode => text.push_str(&seg.data),
+            _ => merged.push((mode, seg.data.clone())),
         }
-        acc.push(curr);
     }
-    acc
-}
 
-fn segments_from_array_gnodes(nodes: Vec<GNode>) -> Vec<Segment> {
-    nodes
+    merged
         .into_iter()
-        .map(|n| Segment::new(n.mode, &n.data))
+        .map(|(mode, text)| Segment::new(mode, &text))
         .collect()
 }
 
-fn segments_from_string(data: &str, version: usize) -> Vec<Segment> {
-    let segs = get_segments_from_string(data);
-    let nodes = build_nodes(&segs);
-    let graph = build_graph(&nodes, version);
-    let path = dijkstra_find_path(&graph, "start", "end");
-
-    let mut optimized: Vec<GNode> = Vec::new();
-    if path.len() >= 2 {
-        for key in &path[1..path.len() - 1] {
-            optimized.push(graph.table.get(key).unwrap().clone());
-        }
-    }
-
-    segments_from_array_gnodes(merge_segments(optimized))
+fn raw_split(data: &str, kanji: bool) -> Vec<Segment> {
+    get_segments_from_string(data, kanji)
+        .into_iter()
+        .map(|s| Segment::new(s.mode, &s.data))
+        .collect()
 }
 
-fn raw_split(data: &str) -> Vec<Segment> {
-    get_segments_from_string(data)
+/// `Mode.getBestModeForData`, with Kanji always available.
+fn get_best_mode_for_data(data: &str) -> Mode {
+    [Mode::Numeric, Mode::Alphanumeric, Mode::Kanji]
         .into_iter()
-        .map(|s| Segment::new(s.mode, &s.data))
+        .find(|mode| data.chars().all(|c| mode.can_encode(c)))
+        .unwrap_or(Mode::Byte)
+}
+
+/// `fromArray` for caller-defined segments: each segment keeps its order and
+/// mode, which must be able to hold all of its data. Kanji segments use the
+/// built-in Shift JIS table whether or not `CreateOptions::kanji` is set.
+pub(crate) fn segments_from_array(inputs: &[SegmentInput]) -> Result<Vec<Segment>, QrError> {
+    inputs
+        .iter()
+        .map(|input| {
+            let mode = Mode::from_name(input.mode).ok_or_else(|| {
+                QrError::InvalidOption(format!(
+                    "Invalid segment mode: {}. Expected numeric, alphanumeric, byte or kanji",
+                    input.mode
+                ))
+            })?;
+            if input.data.is_empty() {
+                return Err(QrError::InvalidOption(format!("Invalid {} segment: no data", mode.id())));
+            }
+            if !input.data.chars().all(|c| mode.can_encode(c)) {
+                return Err(QrError::Encode(format!(
+                    "\"{}\" cannot be encoded with mode {}. Suggested mode is: {}",
+                    input.data,
+                    mode.id(),
+                    get_best_mode_for_data(input.data).id()
+                )));
+            }
+            Ok(Segment::new(mode, input.data))
+        })
         .collect()
 }
 
@@ -841,6 +1289,8 @@ fn get_capacity(version: usize, ecl: EcLevel, mode: Option<Mode>) -> usize {
         Mode::Numeric => ((usable_bits as f64 / 10.0) * 3.0).floor() as usize,
         Mode::Alphanumeric => ((usable_bits as f64 / 11.0) * 2.0).floor() as usize,
         Mode::Byte => usable_bits / 8,
+        Mode::Kanji => usable_bits / 13,
+        Mode::Fnc1First => 0,
     }
 }
 
@@ -848,31 +1298,47 @@ fn get_best_version_for_data_length(
     mode: Mode,
     length: usize,
     ecl: EcLevel,
+    versions: RangeInclusive<usize>,
 ) -> Option<usize> {
-    (1..=40).find(|&v| length <= get_capacity(v, ecl, Some(mode)))
+    versions.into_iter().find(|&v| length <= get_capacity(v, ecl, Some(mode)))
 }
 
-fn get_total_bits_from_data_array(segments: &[Segment], version: usize) -> usize {
-    let mut total = 0;
+fn get_total_bits_from_data_array(segments: &[Segment], version: usize, header_bits: usize) -> usize {
+    let mut total = header_bits;
     for data in segments {
         total += get_reserved_bits_count(data.mode, version) + data.get_bits_length();
     }
     total
 }
 
-fn get_best_version_for_mixed_data(segments: &[Segment], ecl: EcLevel) -> Option<usize> {
-    (1..=40).find(|&v| get_total_bits_from_data_array(segments, v) <= get_capacity(v, ecl, None))
+fn get_best_version_for_mixed_data(
+    segments: &[Segment],
+    ecl: EcLevel,
+    header_bits: usize,
+    versions: RangeInclusive<usize>,
+) -> Option<usize> {
+    versions.into_iter().find(|&v| {
+        get_total_bits_from_data_array(segments, v, header_bits) <= get_capacity(v, ecl, None)
+    })
 }
 
-fn get_best_version_for_data(data: &[Segment], ecl: EcLevel) -> Option<usize> {
-    if data.len() > 1 {
-        return get_best_version_for_mixed_data(data, ecl);
+/// `getBestVersionForData`, restricted to `versions` (node-qrcode always
+/// searches `1..=40`). ECI and Structured Append headers are sized like extra
+/// segments, so they always take the mixed-data path.
+fn get_best_version_for_data(
+    data: &[Segment],
+    ecl: EcLevel,
+    header_bits: usize,
+    versions: RangeInclusive<usize>,
+) -> Option<usize> {
+    if data.len() > 1 || header_bits > 0 {
+        return get_best_version_for_mixed_data(data, ecl, header_bits, versions);
     }
     if data.is_empty() {
-        return Some(1);
+        return Some(*versions.start());
     }
     let seg = &data[0];
-    get_best_version_for_data_length(seg.mode, seg.get_length(), ecl)
+    get_best_version_for_data_length(seg.mode, seg.get_length(), ecl, versions)
 }
 
 // ---------------------------------------------------------------------------
@@ -880,7 +1346,7 @@ fn get_best_version_for_data(data: &[Segment], ecl: EcLevel) -> Option<usize> {
 // ---------------------------------------------------------------------------
 
 fn setup_finder_pattern(matrix: &mut BitMatrix, version: usize) {
-    let size = matrix.size as isize;
+    let size = matrix.size() as isize;
     let pos = finder_positions(version);
 
     for p in &pos {
@@ -895,21 +1361,21 @@ fn setup_finder_pattern(matrix: &mut BitMatrix, version: usize) {
                 if col + c <= -1 || size <= col + c {
                     continue;
                 }
-                let dark = (r >= 0 && r <= 6 && (c == 0 || c == 6))
-                    || (c >= 0 && c <= 6 && (r == 0 || r == 6))
-                    || (r >= 2 && r <= 4 && c >= 2 && c <= 4);
-                matrix.set((row + r) as usize, (col + c) as usize, dark, true);
+                let dark = ((0..=6).contains(&r) && (c == 0 || c == 6))
+                    || ((0..=6).contains(&c) && (r == 0 || r == 6))
+                    || ((2..=4).contains(&r) && (2..=4).contains(&c));
+                matrix.set((row + r) as usize, (col + c) as usize, dark, ModuleRole::Finder);
             }
         }
     }
 }
 
 fn setup_timing_pattern(matrix: &mut BitMatrix) {
-    let size = matrix.size;
+    let size = matrix.size();
     for r in 8..(size - 8) {
         let value = r % 2 == 0;
-        matrix.set(r, 6, value, true);
-        matrix.set(6, r, value, true);
+        matrix.set(r, 6, value, ModuleRole::Timing);
+        matrix.set(6, r, value, ModuleRole::Timing);
     }
 }
 
@@ -921,56 +1387,69 @@ fn setup_alignment_pattern(matrix: &mut BitMatrix, version: usize) {
         for r in -2..=2isize {
             for c in -2..=2isize {
                 let dark = r == -2 || r == 2 || c == -2 || c == 2 || (r == 0 && c == 0);
-                matrix.set((row + r) as usize, (col + c) as usize, dark, true);
+                matrix.set((row + r) as usize, (col + c) as usize, dark, ModuleRole::Alignment);
             }
         }
     }
 }
 
 fn setup_version_info(matrix: &mut BitMatrix, version: usize) {
-    let size = matrix.size;
+    let size = matrix.size();
     let bits = version_get_encoded_bits(version as u32);
     for i in 0..18usize {
         let row = i / 3;
         let col = i % 3 + size - 8 - 3;
         let mod_bit = ((bits >> i) & 1) == 1;
-        matrix.set(row, col, mod_bit, true);
-        matrix.set(col, row, mod_bit, true);
+        matrix.set(row, col, mod_bit, ModuleRole::Version);
+        matrix.set(col, row, mod_bit, ModuleRole::Version);
     }
 }
 
 fn setup_format_info(matrix: &mut BitMatrix, ecl: EcLevel, mask_pattern: u32) {
-    let size = matrix.size;
-    let bits = format_get_encoded_bits(ecl, mask_pattern);
+    for (row, col, dark) in format_info_modules(matrix.size(), ecl, mask_pattern) {
+        matrix.set(row, col, dark, ModuleRole::Format);
+    }
+}
 
-    for i in 0..15usize {
-        let mod_bit = ((bits >> i) & 1) == 1;
+/// The `(row, col, dark)` modules `setup_format_info` writes, in order: each
+/// of the 15 format bits vertically then horizontally, then the fixed module.
+fn format_info_modules(
+    size: usize,
+    ecl: EcLevel,
+    mask_pattern: u32,
+) -> impl Iterator<Item = (usize, usize, bool)> {
+    let bits = format_get_encoded_bits(ecl, mask_pattern);
 
-        // vertical
-        if i < 6 {
-            matrix.set(i, 8, mod_bit, true);
-        } else if i < 8 {
-            matrix.set(i + 1, 8, mod_bit, true);
-        } else {
-            matrix.set(size - 15 + i, 8, mod_bit, true);
-        }
+    (0..15usize)
+        .flat_map(move |i| {
+            let mod_bit = ((bits >> i) & 1) == 1;
 
-        // horizontal
-        if i < 8 {
-            matrix.set(8, size - i - 1, mod_bit, true);
-        } else if i < 9 {
-            matrix.set(8, 15 - i - 1 + 1, mod_bit, true);
-        } else {
-            matrix.set(8, 15 - i - 1, mod_bit, true);
-        }
-    }
+            // vertical
+            let row = if i < 6 {
+                i
+            } else if i < 8 {
+                i + 1
+            } else {
+                size - 15 + i
+            };
+
+            // horizontal
+            let col = if i < 8 {
+                size - i - 1
+            } else if i < 9 {
+                15 - i - 1 + 1
+            } else {
+                15 - i - 1
+            };
 
-    // fixed module
-    matrix.set(size - 8, 8, true, true);
+            [(row, 8, mod_bit), (8, col, mod_bit)]
+        })
+        // fixed module
+        .chain(std::iter::once((size - 8, 8, true)))
 }
 
-fn setup_data(matrix: &mut BitMatrix, data: &[u8]) {
-    let size = matrix.size as isize;
+fn setup_data(matrix: &mut BitMatrix, data: &[u8], roles: &[ModuleRole]) {
+    let size = matrix.size() as isize;
     let mut inc: isize = -1;
     let mut row: isize = size - 1;
     let mut bit_index: isize = 7;
@@ -989,7 +1468,8 @@ fn setup_data(matrix: &mut BitMatrix, data: &[u8]) {
                     if byte_index < data.len() {
                         dark = ((data[byte_index] >> bit_index) & 1) == 1;
                     }
-                    matrix.set(row as usize, (col - c) as usize, dark, false);
+                    let role = roles.get(byte_index).copied().unwrap_or(ModuleRole::Remainder);
+                    matrix.set(row as usize, (col - c) as usize, dark, role);
                     bit_index -= 1;
 
                     if bit_index == -1 {
@@ -1018,28 +1498,20 @@ fn setup_data(matrix: &mut BitMatrix, data: &[u8]) {
 
 fn get_mask_at(mask_pattern: u32, i: usize, j: usize) -> bool {
     match mask_pattern {
-        0 => (i + j) % 2 == 0,
-        1 => i % 2 == 0,
-        2 => j % 3 == 0,
-        3 => (i + j) % 3 == 0,
-        4 => ((i / 2) + (j / 3)) % 2 == 0,
+        0 => (i + j).is_multiple_of(2),
+        1 => i.is_multiple_of(2),
+        2 => j.is_multiple_of(3),
+        3 => (i + j).is_multiple_of(3),
+        4 => ((i / 2) + (j / 3)).is_multiple_of(2),
         5 => (i * j) % 2 + (i * j) % 3 == 0,
-        6 => ((i * j) % 2 + (i * j) % 3) % 2 == 0,
-        7 => ((i * j) % 3 + (i + j) % 2) % 2 == 0,
+        6 => ((i * j) % 2 + (i * j) % 3).is_multiple_of(2),
+        7 => ((i * j) % 3 + (i + j) % 2).is_multiple_of(2),
         _ => panic!("bad maskPattern: {}", mask_pattern),
     }
 }
 
 fn apply_mask(pattern: u32, data: &mut BitMatrix) {
-    let size = data.size;
-    for col in 0..size {
-        for row in 0..size {
-            if data.is_reserved(row, col) {
-                continue;
-            }
-            data.xor(row, col, get_mask_at(pattern, row, col));
-        }
-    }
+    data.apply_mask(|row, col| get_mask_at(pattern, row, col));
 }
 
 const N1: i64 = 3;
@@ -1047,100 +1519,103 @@ const N2: i64 = 3;
 const N3: i64 = 40;
 const N4: i64 = 10;
 
-fn get_penalty_n1(data: &BitMatrix) -> i64 {
-    let size = data.size;
-    let mut points: i64 = 0;
+/// Word `word` of `row` moved `shift` (< 64) columns to the left, so bit `c`
+/// holds the module at column `word * 64 + c + shift`.
+fn shifted_word(row: &[u64], word: usize, shift: usize) -> u64 {
+    match (shift, row.get(word + 1)) {
+        (0, _) => row[word],
+        (_, Some(&next)) => row[word] >> shift | next << (64 - shift),
+        (_, None) => row[word] >> shift,
+    }
+}
 
-    for row in 0..size {
-        let mut same_count_col = 0i64;
-        let mut same_count_row = 0i64;
-        let mut last_col: i64 = -1;
-        let mut last_row: i64 = -1;
+/// N1 points of the rows: runs of five or more same-colored modules, found
+/// from the color changes of each word.
+fn get_penalty_n1_rows(data: &ModuleGrid) -> i64 {
+    let mut points: i64 = 0;
 
-        for col in 0..size {
-            let mut module = data.get(row, col) as i64;
-            if module == last_col {
-                same_count_col += 1;
-            } else {
-                if same_count_col >= 5 {
-                    points += N1 + (same_count_col - 5);
-                }
-                last_col = module;
-                same_count_col = 1;
+    for row in 0..data.height {
+        let words = data.row(row);
+        let mut run_start = 0;
+        let mut score_run = |end: usize| {
+            let length = (end - run_start) as i64;
+            if length >= 5 {
+                points += N1 + (length - 5);
             }
+            run_start = end;
+        };
 
-            module = data.get(col, row) as i64;
-            if module == last_row {
-                same_count_row += 1;
-            } else {
-                if same_count_row >= 5 {
-                    points += N1 + (same_count_row - 5);
-                }
-                last_row = module;
-                same_count_row = 1;
+        for (word, &bits) in words.iter().enumerate() {
+            let previous = if word == 0 { bits << 1 } else { bits << 1 | words[word - 1] >> 63 };
+            let mut changes = (bits ^ previous) & BitMatrix::columns_mask(word, data.width);
+            if word == 0 {
+                changes &= !1;
+            }
+            while changes != 0 {
+                score_run(word * 64 + changes.trailing_zeros() as usize);
+                changes &= changes - 1;
             }
         }
-
-        if same_count_col >= 5 {
-            points += N1 + (same_count_col - 5);
-        }
-        if same_count_row >= 5 {
-            points += N1 + (same_count_row - 5);
-        }
+        score_run(data.width);
     }
 
     points
 }
 
-fn get_penalty_n2(data: &BitMatrix) -> i64 {
-    let size = data.size;
-    let mut points: i64 = 0;
+fn get_penalty_n1(data: &ModuleGrid, transposed: &ModuleGrid) -> i64 {
+    get_penalty_n1_rows(data) + get_penalty_n1_rows(transposed)
+}
 
-    for row in 0..(size - 1) {
-        for col in 0..(size - 1) {
-            let last = data.get(row, col) as i64
-                + data.get(row, col + 1) as i64
-                + data.get(row + 1, col) as i64
-                + data.get(row + 1, col + 1) as i64;
+/// 2x2 blocks of one color: both rows agree at `col` and `col + 1`, and the
+/// upper row agrees with itself across the two columns.
+fn get_penalty_n2(data: &ModuleGrid) -> i64 {
+    let mut points: i64 = 0;
 
-            if last == 4 || last == 0 {
-                points += 1;
-            }
+    for row in 0..data.height.saturating_sub(1) {
+        let (upper, lower) = (data.row(row), data.row(row + 1));
+        for word in 0..data.stride {
+            let (a, b) = (upper[word], lower[word]);
+            let (a1, b1) = (shifted_word(upper, word, 1), shifted_word(lower, word, 1));
+            let same = !(a ^ b) & !(a1 ^ b1) & !(a ^ a1);
+            let valid = BitMatrix::columns_mask(word, data.width.saturating_sub(1));
+            points += (same & valid).count_ones() as i64;
         }
     }
 
     points * N2
 }
 
-fn get_penalty_n3(data: &BitMatrix) -> i64 {
-    let size = data.size;
+/// N3 points of the rows: the 11-module patterns `10111010000` and
+/// `00001011101`, matched at 64 start columns at once.
+fn get_penalty_n3_rows(data: &ModuleGrid) -> i64 {
+    const PATTERNS: [u32; 2] = [0x5D0, 0x05D];
     let mut points: i64 = 0;
 
-    for row in 0..size {
-        let mut bits_col = 0u32;
-        let mut bits_row = 0u32;
-        for col in 0..size {
-            bits_col = ((bits_col << 1) & 0x7FF) | data.get(row, col) as u32;
-            if col >= 10 && (bits_col == 0x5D0 || bits_col == 0x05D) {
-                points += 1;
-            }
-
-            bits_row = ((bits_row << 1) & 0x7FF) | data.get(col, row) as u32;
-            if col >= 10 && (bits_row == 0x5D0 || bits_row == 0x05D) {
-                points += 1;
+    for row in 0..data.height {
+        let words = data.row(row);
+        for word in 0..data.stride {
+            let starts = BitMatrix::columns_mask(word, data.width.saturating_sub(10));
+            for pattern in PATTERNS {
+                let mut matches = starts;
+                for k in 0..11 {
+                    let modules = shifted_word(words, word, k);
+                    matches &= if pattern >> (10 - k) & 1 != 0 { modules } else { !modules };
+                }
+                points += matches.count_ones() as i64;
             }
         }
     }
 
-    points * N3
+    points
 }
 
-fn get_penalty_n4(data: &BitMatrix) -> i64 {
-    let mut dark_count: i64 = 0;
-    let modules_count = data.data.len() as i64;
-    for &b in &data.data {
-        dark_count += b as i64;
-    }
+fn get_penalty_n3(data: &ModuleGrid, transposed: &ModuleGrid) -> i64 {
+    (get_penalty_n3_rows(data) + get_penalty_n3_rows(transposed)) * N3
+}
+
+fn get_penalty_n4(data: &ModuleGrid) -> i64 {
+    let dark_count: i64 = data.words.iter().map(|w| w.count_ones() as i64).sum();
+    let modules_count = (data.width * data.height) as i64;
 
     // k = abs(ceil((darkCount * 100 / modulesCount) / 5) - 10)
     let ratio = (dark_count as f64) * 100.0 / (modules_count as f64);
@@ -1149,35 +1624,78 @@ fn get_penalty_n4(data: &BitMatrix) -> i64 {
     k * N4
 }
 
-fn get_best_mask(data: &mut BitMatrix, ecl: EcLevel) -> u32 {
-    let mut best_pattern = 0u32;
-    let mut lower_penalty = i64::MAX;
+/// Penalty score of each of the eight mask patterns, indexed by pattern.
+/// The unmasked modules, the reserved plane and their transposes are built
+/// once; each pattern then only flips its mask and writes its format bits
+/// into two scratch grids, so `data` itself is left untouched.
+fn get_mask_penalties(data: &BitMatrix, ecl: EcLevel) -> [i64; 8] {
+    let mut penalties = [0i64; 8];
+    let size = data.size();
+    let base = ModuleGrid::new(size, size, data.modules.clone());
+    let reserved = ModuleGrid::new(size, size, data.reserved.clone());
+    let base_transposed = base.transposed();
+    let reserved_transposed = reserved.transposed();
+    let mut masked = base.clone();
+    let mut transposed = base_transposed.clone();
+    let mut period = vec![0u64; MASK_ROW_PERIOD * base.stride];
 
     for p in 0..8u32 {
-        setup_format_info(data, ecl, p);
-        apply_mask(p, data);
+        fill_mask_period(&mut period, size, |row, col| get_mask_at(p, row, col));
+        masked.words.copy_from_slice(&base.words);
+        xor_mask(&mut masked.words, &reserved.words, masked.stride, &period);
+
+        fill_mask_period(&mut period, size, |row, col| get_mask_at(p, col, row));
+        transposed.words.copy_from_slice(&base_transposed.words);
+        xor_mask(&mut transposed.words, &reserved_transposed.words, transposed.stride, &period);
+
+        for (row, col, dark) in format_info_modules(size, ecl, p) {
+            masked.set(row, col, dark);
+            transposed.set(col, row, dark);
+        }
+
+        penalties[p as usize] = get_penalty_n1(&masked, &transposed)
+            + get_penalty_n2(&masked)
+            + get_penalty_n3(&masked, &transposed)
+            + get_penalty_n4(&masked);
+    }
 
-        let penalty =
-            get_penalty_n1(data) + get_penalty_n2(data) + get_penalty_n3(data) + get_penalty_n4(data);
+    penalties
+}
 
-        apply_mask(p, data); // undo
+/// Lowest-penalty pattern, preferring the earliest pattern on ties.
+fn lowest_penalty_mask(penalties: &[i64; 8]) -> u32 {
+    let mut best_pattern = 0u32;
+    let mut lower_penalty = i64::MAX;
 
+    for (p, &penalty) in penalties.iter().enumerate() {
         if penalty < lower_penalty {
             lower_penalty = penalty;
-            best_pattern = p;
+            best_pattern = p as u32;
         }
     }
 
     best_pattern
 }
 
+fn get_best_mask(data: &BitMatrix, ecl: EcLevel) -> u32 {
+    lowest_penalty_mask(&get_mask_penalties(data, ecl))
+}
+
 // ---------------------------------------------------------------------------
 // qrcode.js — createData / createCodewords / createSymbol
 // ---------------------------------------------------------------------------
 
-fn create_data(version: usize, ecl: EcLevel, segments: &[Segment]) -> Vec<u8> {
+fn create_data(version: usize, ecl: EcLevel, options: &CreateOptions, segments: &[Segment]) -> Vec<u8> {
     let mut buffer = BitBuffer::new();
 
+    if let Some(header) = &options.structured_append {
+        write_structured_append_header(&mut buffer, header);
+    }
+
+    if let Some(assignment) = options.eci {
+        write_eci_header(&mut buffer, assignment);
+    }
+
     for data in segments {
         buffer.put(data.mode.bit(), 4);
         buffer.put(data.get_length() as u32, data.mode.cc_bits(version));
@@ -1192,7 +1710,7 @@ fn create_data(version: usize, ecl: EcLevel, segments: &[Segment]) -> Vec<u8> {
         buffer.put(0, 4);
     }
 
-    while buffer.get_length_in_bits() % 8 != 0 {
+    while !buffer.get_length_in_bits().is_multiple_of(8) {
         buffer.put_bit(false);
     }
 
@@ -1207,84 +1725,789 @@ fn create_data(version: usize, ecl: EcLevel, segments: &[Segment]) -> Vec<u8> {
 fn create_codewords(bit_buffer: &BitBuffer, version: usize, ecl: EcLevel) -> Vec<u8> {
     let total_codewords = get_symbol_total_codewords(version);
     let ec_total = get_total_codewords_count(version, ecl);
-    let data_total = total_codewords - ec_total;
     let ec_total_blocks = get_blocks_count(version, ecl);
 
+    interleave_codewords(bit_buffer, total_codewords, ec_total, ec_total_blocks)
+}
+
+/// Block structure shared by QR and rMQR: returns the number of group 1
+/// blocks, the data codewords per group 1 block and the EC codewords per
+/// block. Group 2 blocks hold one more data codeword than group 1 blocks.
+fn block_layout(total_codewords: usize, ec_total: usize, ec_total_blocks: usize) -> (usize, usize, usize) {
+    let data_total = total_codewords - ec_total;
+
     let blocks_in_group2 = total_codewords % ec_total_blocks;
     let blocks_in_group1 = ec_total_blocks - blocks_in_group2;
 
     let total_in_group1 = total_codewords / ec_total_blocks;
     let data_in_group1 = data_total / ec_total_blocks;
 
-    let ec_count = total_in_group1 - data_in_group1;
-
-    let gen_poly = generate_ec_polynomial(ec_count);
+    (blocks_in_group1, data_in_group1, total_in_group1 - data_in_group1)
+}
 
-    let mut offset = 0usize;
-    let mut dc_data: Vec<Vec<u8>> = vec![Vec::new(); ec_total_blocks];
-    let mut ec_data: Vec<Vec<u8>> = vec![Vec::new(); ec_total_blocks];
-    let mut max_data_size = 0usize;
+/// Split the data codewords into `ec_total_blocks` blocks, append each
+/// block's error correction codewords and interleave the result. Shared with
+/// the rMQR encoder, which uses the same block structure.
+pub(crate) fn interleave_codewords(
+    bit_buffer: &BitBuffer,
+    total_codewords: usize,
+    ec_total: usize,
+    ec_total_blocks: usize,
+) -> Vec<u8> {
+    let data_total = total_codewords - ec_total;
+    let (blocks_in_group1, data_in_group1, ec_count) =
+        block_layout(total_codewords, ec_total, ec_total_blocks);
 
     // bit_buffer.buffer is exactly `data_total` bytes long here.
     let buffer = &bit_buffer.buffer;
+    let block_data = |b: usize| {
+        let start = b * data_in_group1 + b.saturating_sub(blocks_in_group1);
+        let size = data_in_group1 + usize::from(b >= blocks_in_group1);
+        &buffer[start..start + size]
+    };
 
-    for b in 0..ec_total_blocks {
-        let data_size = if b < blocks_in_group1 {
-            data_in_group1
-        } else {
-            data_in_group1 + 1
-        };
-
-        dc_data[b] = buffer[offset..offset + data_size].to_vec();
-        ec_data[b] = rs_encode(&dc_data[b], ec_count, &gen_poly);
+    let mut result = vec![0u8; total_codewords];
+    let (data_part, ec_part) = result.split_at_mut(data_total);
 
-        offset += data_size;
-        max_data_size = max_data_size.max(data_size);
-    }
-
-    let mut result = vec![0u8; total_codewords];
     let mut index = 0usize;
-
-    for i in 0..max_data_size {
-        for r in 0..ec_total_blocks {
-            if i < dc_data[r].len() {
-                result[index] = dc_data[r][i];
+    for i in 0..=data_in_group1 {
+        for b in 0..ec_total_blocks {
+            if let Some(&codeword) = block_data(b).get(i) {
+                data_part[index] = codeword;
                 index += 1;
             }
         }
     }
 
-    for i in 0..ec_count {
-        for r in 0..ec_total_blocks {
-            result[index] = ec_data[r][i];
-            index += 1;
+    // Each block's EC codewords are computed into a scratch buffer and then
+    // spread over the interleaved tail.
+    let mut ec = [0u8; MAX_EC_DEGREE];
+    let ec = &mut ec[..ec_count];
+    for b in 0..ec_total_blocks {
+        rs_encode(block_data(b), ec);
+        for (i, &codeword) in ec.iter().enumerate() {
+            ec_part[i * ec_total_blocks + b] = codeword;
         }
     }
 
     result
 }
 
-/// Equivalent of `QRCode.create(data, { errorCorrectionLevel })` (no explicit
-/// version or mask). Returns the assembled module matrix.
-pub fn create(data: &str, ecl: EcLevel) -> Result<BitMatrix, String> {
-    if data.is_empty() {
-        return Err("No input text".to_string());
+/// Role of each codeword in the order `interleave_codewords` emits them.
+pub(crate) fn codeword_roles(total_codewords: usize, ec_total: usize, ec_total_blocks: usize) -> Vec<ModuleRole> {
+    let (blocks_in_group1, data_in_group1, ec_count) =
+        block_layout(total_codewords, ec_total, ec_total_blocks);
+
+    let mut roles = Vec::with_capacity(total_codewords);
+    for i in 0..=data_in_group1 {
+        for b in 0..ec_total_blocks {
+            if i < data_in_group1 || b >= blocks_in_group1 {
+                roles.push(ModuleRole::Data(b as u8));
+            }
+        }
+    }
+    for _ in 0..ec_count {
+        roles.extend((0..ec_total_blocks).map(|b| ModuleRole::ErrorCorrection(b as u8)));
+    }
+    roles
+}
+
+/// Symbol family to encode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Symbology {
+    /// A regular QR Code, versions 1 to 40.
+    #[default]
+    Qr,
+    /// A Micro QR Code, versions M1 to M4 (see `microqr.rs`).
+    MicroQr,
+    /// A rectangular Micro QR Code, R7x43 to R17x139 (see `rmqr.rs`).
+    Rmqr,
+}
+
+impl Symbology {
+    /// Parse the `symbology` option; `None` selects a regular QR Code.
+    pub fn parse(value: Option<&str>) -> Result<Symbology, QrError> {
+        match value {
+            None => Ok(Symbology::Qr),
+            Some(s) => match s.to_lowercase().as_str() {
+                "qr" => Ok(Symbology::Qr),
+                "microqr" => Ok(Symbology::MicroQr),
+                "rmqr" => Ok(Symbology::Rmqr),
+                _ => Err(QrError::InvalidOption(format!(
+                    "Invalid symbology: {}. Expected qr, microqr or rmqr",
+                    s
+                ))),
+            },
+        }
+    }
+}
+
+/// Symbol options beyond the error-correction level, mirroring the optional
+/// fields of node-qrcode's `QRCode.create(data, options)`. The default leaves
+/// every choice to the encoder, which reproduces node-qrcode's output exactly.
+#[derive(Clone, Debug, Default)]
+pub struct CreateOptions {
+    /// Pin the symbol to this exact version (node-qrcode `version`).
+    pub version: Option<usize>,
+    /// Smallest version the encoder may pick.
+    pub min_version: Option<usize>,
+    /// Largest version the encoder may pick.
+    pub max_version: Option<usize>,
+    /// Force this mask pattern (0-7) instead of the lowest-penalty one
+    /// (node-qrcode `maskPattern`).
+    pub mask_pattern: Option<u32>,
+    /// Encode Shift JIS characters in Kanji mode (node-qrcode `toSJISFunc`).
+    pub kanji: bool,
+    /// Prefix the data with an ECI header declaring this character set
+    /// assignment number, e.g. 26 for UTF-8.
+    pub eci: Option<u32>,
+    /// Mark the symbol as one part of a Structured Append sequence.
+    /// `create_structured_append` fills this in for every part it builds.
+    pub structured_append: Option<StructuredAppend>,
+    /// Treat the text as a bracketed GS1 element string such as
+    /// `(01)09506000134352(17)251231`, validate it and encode it after an
+    /// FNC1 in first position mode indicator.
+    pub gs1: bool,
+    /// Raise the error correction level as far as the data still fits in
+    /// the same version. QR Code symbols only.
+    pub boost_ecl: bool,
+    /// Symbol family. For Micro QR the version options count M1 to M4 as
+    /// 1 to 4.
+    pub symbology: Symbology,
+}
+
+impl CreateOptions {
+    /// Bits taken by the Structured Append and ECI headers ahead of the data.
+    fn header_bits(&self) -> usize {
+        let structured_append = if self.structured_append.is_some() {
+            STRUCTURED_APPEND_HEADER_BITS
+        } else {
+            0
+        };
+        structured_append + eci_header_bits(self.eci)
+    }
+
+    /// Resolve the version constraints into the inclusive range the encoder
+    /// searches, validating every bound.
+    fn version_range(&self) -> Result<RangeInclusive<usize>, QrError> {
+        for v in [self.version, self.min_version, self.max_version].into_iter().flatten() {
+            if !(1..=40).contains(&v) {
+                return Err(QrError::InvalidOption(format!(
+                    "Invalid QR Code version: {}. Expected 1 to 40",
+                    v
+                )));
+            }
+        }
+
+        if let Some(v) = self.version {
+            return Ok(v..=v);
+        }
+
+        let min = self.min_version.unwrap_or(1);
+        let max = self.max_version.unwrap_or(40);
+        if min > max {
+            return Err(QrError::InvalidOption(format!(
+                "Invalid QR Code version range: minimum {} is greater than maximum {}",
+                min, max
+            )));
+        }
+        Ok(min..=max)
+    }
+}
+
+/// A finished symbol and the choices the encoder made for it.
+pub struct Symbol {
+    pub modules: BitMatrix,
+    /// QR versions count 1 to 40, Micro QR M1 to M4 as 1 to 4 and rMQR
+    /// R7x43 to R17x139 as 1 to 32.
+    pub version: usize,
+    /// The level encoded with, raised when `boost_ecl` is set.
+    pub ecl: EcLevel,
+    /// The applied mask pattern; `None` for rMQR's single fixed mask.
+    pub mask_pattern: Option<u32>,
+}
+
+/// Mask penalty report for the symbol `create` would build.
+pub struct MaskPenalties {
+    pub version: usize,
+    /// N1+N2+N3+N4 penalty of each mask pattern, indexed by pattern.
+    pub penalties: [i64; 8],
+    /// The pattern `create` picks when no mask is forced.
+    pub best_mask_pattern: u32,
+}
+
+/// How much data fits in some number of bits, per mode: characters for
+/// Numeric, Alphanumeric and Kanji, bytes for Byte.
+pub struct Capacity {
+    pub bits: usize,
+    pub numeric: usize,
+    pub alphanumeric: usize,
+    pub byte: usize,
+    pub kanji: usize,
+}
+
+/// One segment of an encoding plan.
+pub struct PlannedSegment {
+    /// `Mode.toString(mode)`, e.g. `"Alphanumeric"`.
+    pub mode: &'static str,
+    pub data: String,
+    /// Characters, or bytes in Byte mode.
+    pub length: usize,
+    /// Mode indicator, character count and data bits.
+    pub bits: usize,
+}
+
+/// The encoding plan of the symbol `create` would build.
+pub struct Inspection {
+    pub version: usize,
+    /// The level encoded with, raised when `boost_ecl` is set.
+    pub ecl: EcLevel,
+    /// The forced mask pattern, or the lowest-penalty one.
+    pub mask_pattern: u32,
+    pub segments: Vec<PlannedSegment>,
+    /// Structured Append and ECI header bits.
+    pub header_bits: usize,
+    /// Header and segment bits, without terminator and padding.
+    pub total_bits: usize,
+    /// Free bits, and how much of each mode still fits in one more segment.
+    pub remaining: Capacity,
+    /// `getCapacity` of this version at every level, from L to H.
+    pub capacities: Vec<(EcLevel, Capacity)>,
+}
+
+/// The payload passed to `create`.
+/// A caller-defined segment: `data` to encode in the mode named `mode`
+/// (`"numeric"`, `"alphanumeric"`, `"byte"` or `"kanji"`).
+#[derive(Clone, Copy, Debug)]
+pub struct SegmentInput<'a> {
+    pub mode: &'a str,
+    pub data: &'a str,
+}
+
+#[derive(Clone, Debug)]
+pub enum QrData<'a> {
+    /// Text, split into Numeric/Alphanumeric/Byte(/Kanji) segments exactly like
+    /// node-qrcode.
+    Text(&'a str),
+    /// Arbitrary bytes, stored as a single Byte-mode segment.
+    Bytes(&'a [u8]),
+    /// Caller-defined segments, encoded in order with the given modes like
+    /// node-qrcode's `[{ data, mode }]` input.
+    Segments(Vec<SegmentInput<'a>>),
+}
+
+impl<'a> From<&'a str> for QrData<'a> {
+    fn from(text: &'a str) -> Self {
+        QrData::Text(text)
+    }
+}
+
+impl<'a> From<&'a [u8]> for QrData<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        QrData::Bytes(bytes)
+    }
+}
+
+/// Equivalent of `QRCode.create(data, { errorCorrectionLevel, version,
+/// maskPattern })`. Returns the assembled module matrix.
+pub fn create<'a>(
+    data: impl Into<QrData<'a>>,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<BitMatrix, QrError> {
+    create_symbol(data, ecl, options).map(|symbol| symbol.modules)
+}
+
+/// `create`, also reporting the version, level and mask of the symbol. Micro
+/// QR and rMQR symbols are handed to `microqr::create` and `rmqr::create`.
+pub fn create_symbol<'a>(
+    data: impl Into<QrData<'a>>,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<Symbol, QrError> {
+    let data = data.into();
+    match options.symbology {
+        Symbology::MicroQr => return microqr::create(data, ecl, options),
+        Symbology::Rmqr => return rmqr::create(data, ecl, options),
+        Symbology::Qr => {}
+    }
+
+    validate_mask_pattern(options.mask_pattern)?;
+
+    let (mut modules, version, ecl) = create_unmasked(data, ecl, options)?;
+    let mask_pattern = finish_symbol(&mut modules, ecl, options.mask_pattern);
+
+    Ok(Symbol {
+        modules,
+        version,
+        ecl,
+        mask_pattern: Some(mask_pattern),
+    })
+}
+
+/// Split `data` across as few Structured Append symbols as possible, at most
+/// `STRUCTURED_APPEND_MAX`, each within the allowed versions. Data that fits a
+/// single symbol comes back as one ordinary symbol without the header. Every
+/// part of a sequence uses the version of the largest one so the symbols print
+/// at the same size.
+pub fn create_structured_append<'a>(
+    data: impl Into<QrData<'a>>,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<Vec<BitMatrix>, QrError> {
+    if options.symbology != Symbology::Qr {
+        return Err(QrError::InvalidOption(
+            "Structured Append is only available for QR Code symbols".to_string(),
+        ));
+    }
+    if options.gs1 {
+        return Err(QrError::InvalidOption(
+            "GS1 data cannot be split with Structured Append".to_string(),
+        ));
+    }
+
+    let data = data.into();
+    let options = CreateOptions {
+        structured_append: None,
+        ..options.clone()
+    };
+
+    validate_mask_pattern(options.mask_pattern)?;
+    validate_input(&data, &options)?;
+    let versions = options.version_range()?;
+
+    if let QrData::Segments(_) = data {
+        return Err(QrError::InvalidOption(
+            "Manual segments cannot be split with Structured Append".to_string(),
+        ));
+    }
+
+    for total in 1..=STRUCTURED_APPEND_MAX {
+        let Some(parts) = split_data(&data, total) else {
+            break;
+        };
+
+        let mut planned = Vec::with_capacity(total);
+        for (index, part) in parts.into_iter().enumerate() {
+            // The parity is filled in once every part is planned; the header
+            // is the same size whatever its value.
+            let part_options = CreateOptions {
+                structured_append: (total > 1).then_some(StructuredAppend {
+                    index: index as u8,
+                    total: total as u8,
+                    parity: 0,
+                }),
+                ..options.clone()
+            };
+            let segments = plan_segments(part, ecl, &part_options, &versions)?;
+            let version = get_best_version_for_data(
+                &segments,
+                ecl,
+                part_options.header_bits(),
+                versions.clone(),
+            );
+            match version {
+                Some(version) => planned.push((part_options, segments, version)),
+                None => break,
+            }
+        }
+
+        if planned.len() < total {
+            continue;
+        }
+
+        let parity = structured_append_parity(planned.iter().flat_map(|(_, segments, _)| segments));
+        for (part_options, _, _) in &mut planned {
+            if let Some(header) = &mut part_options.structured_append {
+                header.parity = parity;
+            }
+        }
+
+        let version = planned.iter().map(|(_, _, v)| *v).max().unwrap_or(*versions.start());
+        // Boosted parts share the highest level every one of them can take.
+        let ecl = if options.boost_ecl {
+            planned
+                .iter()
+                .map(|(part_options, segments, _)| {
+                    boost_ec_level(segments, ecl, part_options.header_bits(), version)
+                })
+                .min_by_key(|level| level.table_index())
+                .unwrap_or(ecl)
+        } else {
+            ecl
+        };
+        return Ok(planned
+            .iter()
+            .map(|(part_options, segments, _)| {
+                let mut modules = place_modules(version, ecl, part_options, segments);
+                finish_symbol(&mut modules, ecl, options.mask_pattern);
+                modules
+            })
+            .collect());
+    }
+
+    // Every part repeats the headers, so the data as one stream is compared
+    // with the room the parts leave for it.
+    let largest = *versions.end();
+    let segments = plan_segments(data, ecl, &options, &versions)?;
+    let part_bits = get_capacity(largest, ecl, None)
+        .saturating_sub(options.header_bits() + STRUCTURED_APPEND_HEADER_BITS);
+    Err(QrError::DataTooLong {
+        message: format!(
+            "The amount of data is too big to be stored in {} Structured Append QR Codes",
+            STRUCTURED_APPEND_MAX
+        ),
+        needed: get_total_bits_from_data_array(&segments, largest, 0),
+        available: STRUCTURED_APPEND_MAX * part_bits,
+    })
+}
+
+/// Cut `data` into `parts` runs of roughly equal byte length, never inside a
+/// UTF-8 character. `None` when there is too little data to go round.
+fn split_data<'a>(data: &QrData<'a>, parts: usize) -> Option<Vec<QrData<'a>>> {
+    let mut chunks = Vec::with_capacity(parts);
+    let mut start = 0;
+
+    match *data {
+        QrData::Text(text) => {
+            for i in 1..=parts {
+                let mut end = text.len() * i / parts;
+                while !text.is_char_boundary(end) {
+                    end += 1;
+                }
+                if end <= start {
+                    return None;
+                }
+                chunks.push(QrData::Text(&text[start..end]));
+                start = end;
+            }
+        }
+        QrData::Bytes(bytes) => {
+            for i in 1..=parts {
+                let end = bytes.len() * i / parts;
+                if end <= start {
+                    return None;
+                }
+                chunks.push(QrData::Bytes(&bytes[start..end]));
+                start = end;
+            }
+        }
+        QrData::Segments(_) => return None,
+    }
+
+    Some(chunks)
+}
+
+/// Score all eight mask patterns for the symbol `create` would build, so a
+/// caller can pick one to force through `CreateOptions::mask_pattern`.
+pub fn mask_penalties<'a>(
+    data: impl Into<QrData<'a>>,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<MaskPenalties, QrError> {
+    if options.symbology != Symbology::Qr {
+        return Err(QrError::InvalidOption(
+            "Mask penalties are only available for QR Code symbols".to_string(),
+        ));
     }
 
-    // Estimate version from the raw (non-optimized) segments.
-    let raw_segments = raw_split(data);
-    let estimated_version = get_best_version_for_data(&raw_segments, ecl);
+    let (modules, version, ecl) = create_unmasked(data.into(), ecl, options)?;
+    let penalties = get_mask_penalties(&modules, ecl);
 
-    let segments = segments_from_string(data, estimated_version.unwrap_or(40));
+    Ok(MaskPenalties {
+        version,
+        penalties,
+        best_mask_pattern: lowest_penalty_mask(&penalties),
+    })
+}
 
-    let best_version = match get_best_version_for_data(&segments, ecl) {
+/// Plan the symbol `create` would build, reporting the version, mask,
+/// segments, bit usage and capacities without rendering it.
+pub fn inspect<'a>(
+    data: impl Into<QrData<'a>>,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<Inspection, QrError> {
+    if options.symbology != Symbology::Qr {
+        return Err(QrError::InvalidOption(
+            "Inspection is only available for QR Code symbols".to_string(),
+        ));
+    }
+    validate_mask_pattern(options.mask_pattern)?;
+
+    let (segments, version, ecl) = plan_symbol(data.into(), ecl, options)?;
+    let mask_pattern = match options.mask_pattern {
+        Some(p) => p,
+        None => get_best_mask(&place_modules(version, ecl, options, &segments), ecl),
+    };
+
+    let header_bits = options.header_bits();
+    let total_bits = get_total_bits_from_data_array(&segments, version, header_bits);
+    let free_bits = get_capacity(version, ecl, None) - total_bits;
+    let fit = |mode: Mode| {
+        free_bits
+            .checked_sub(get_reserved_bits_count(mode, version))
+            .map_or(0, |bits| characters_in_bits(mode, bits))
+    };
+
+    Ok(Inspection {
+        version,
+        ecl,
+        mask_pattern,
+        segments: segments
+            .iter()
+            .map(|segment| PlannedSegment {
+                mode: segment.mode.id(),
+                data: match segment.mode {
+                    Mode::Byte => String::from_utf8_lossy(&segment.bytes).into_owned(),
+                    _ => segment.text.clone(),
+                },
+                length: segment.get_length(),
+                bits: get_reserved_bits_count(segment.mode, version) + segment.get_bits_length(),
+            })
+            .collect(),
+        header_bits,
+        total_bits,
+        remaining: Capacity {
+            bits: free_bits,
+            numeric: fit(Mode::Numeric),
+            alphanumeric: fit(Mode::Alphanumeric),
+            byte: fit(Mode::Byte),
+            kanji: fit(Mode::Kanji),
+        },
+        capacities: [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H]
+            .into_iter()
+            .map(|level| {
+                let capacity = Capacity {
+                    bits: get_capacity(version, level, None),
+                    numeric: get_capacity(version, level, Some(Mode::Numeric)),
+                    alphanumeric: get_capacity(version, level, Some(Mode::Alphanumeric)),
+                    byte: get_capacity(version, level, Some(Mode::Byte)),
+                    kanji: get_capacity(version, level, Some(Mode::Kanji)),
+                };
+                (level, capacity)
+            })
+            .collect(),
+    })
+}
+
+/// Characters of `mode` that `bits` of segment data can hold, counting the
+/// shorter trailing groups of Numeric and Alphanumeric data.
+fn characters_in_bits(mode: Mode, bits: usize) -> usize {
+    match mode {
+        Mode::Numeric => bits / 10 * 3 + [0, 0, 0, 0, 1, 1, 1, 2, 2, 2][bits % 10],
+        Mode::Alphanumeric => bits / 11 * 2 + usize::from(bits % 11 >= 6),
+        Mode::Byte => bits / 8,
+        Mode::Kanji => bits / 13,
+        Mode::Fnc1First => 0,
+    }
+}
+
+fn validate_mask_pattern(mask_pattern: Option<u32>) -> Result<(), QrError> {
+    match mask_pattern {
+        Some(p) if p > 7 => Err(QrError::InvalidOption(format!(
+            "Invalid mask pattern: {}. Expected 0 to 7",
+            p
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn validate_input(data: &QrData, options: &CreateOptions) -> Result<(), QrError> {
+    check_input_present(data)?;
+
+    if let Some(eci) = options.eci.filter(|&eci| eci > ECI_MAX) {
+        return Err(QrError::InvalidOption(format!(
+            "Invalid ECI assignment number: {}. Expected 0 to {}",
+            eci, ECI_MAX
+        )));
+    }
+
+    if let Some(header) = &options.structured_append {
+        header.validate()?;
+    }
+
+    Ok(())
+}
+
+/// Reject empty input. Shared with the Micro QR and rMQR encoders.
+pub(crate) fn check_input_present(data: &QrData) -> Result<(), QrError> {
+    let message = match data {
+        QrData::Text("") => "No input text",
+        QrData::Bytes([]) => "No input data",
+        QrData::Segments(inputs) if inputs.is_empty() => "No input segments",
+        _ => return Ok(()),
+    };
+    Err(QrError::InvalidOption(message.to_string()))
+}
+
+/// Everything in `createSymbol` up to mask selection: picks the version,
+/// encodes the data and places every module, leaving the data unmasked.
+fn create_unmasked(
+    data: QrData,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<(BitMatrix, usize, EcLevel), QrError> {
+    let (segments, version, ecl) = plan_symbol(data, ecl, options)?;
+    Ok((place_modules(version, ecl, options, &segments), version, ecl))
+}
+
+/// Pick the segments, version and error correction level of the symbol,
+/// raising the level when `options.boost_ecl` is set.
+fn plan_symbol(
+    data: QrData,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<(Vec<Segment>, usize, EcLevel), QrError> {
+    validate_input(&data, options)?;
+
+    let gs1_data;
+    let data = if options.gs1 {
+        gs1_data = gs1_element_string(&data)?;
+        QrData::Text(&gs1_data)
+    } else {
+        data
+    };
+
+    let versions = options.version_range()?;
+    let segments = plan_segments(data, ecl, options, &versions)?;
+    let header_bits = options.header_bits();
+
+    let version = match get_best_version_for_data(&segments, ecl, header_bits, versions.clone()) {
         Some(v) => v,
-        None => return Err("The amount of data is too big to be stored in a QR Code".to_string()),
+        None => {
+            let message = match get_best_version_for_data(&segments, ecl, header_bits, 1..=40) {
+                Some(required) if options.version.is_some() => format!(
+                    "The chosen QR Code version cannot contain this amount of data. \
+                     Minimum version required to store current data is: {}",
+                    required
+                ),
+                Some(required) => format!(
+                    "The allowed QR Code versions ({} to {}) cannot contain this amount of data. \
+                     Minimum version required to store current data is: {}",
+                    versions.start(),
+                    versions.end(),
+                    required
+                ),
+                None => "The amount of data is too big to be stored in a QR Code".to_string(),
+            };
+            let largest = *versions.end();
+            return Err(QrError::DataTooLong {
+                message,
+                needed: get_total_bits_from_data_array(&segments, largest, header_bits),
+                available: get_capacity(largest, ecl, None),
+            });
+        }
+    };
+
+    let ecl = if options.boost_ecl {
+        boost_ec_level(&segments, ecl, header_bits, version)
+    } else {
+        ecl
+    };
+
+    Ok((segments, version, ecl))
+}
+
+/// Raise `ecl` one level at a time for as long as `segments` still fit in
+/// `version`, so the symbol gains error correction without growing.
+fn boost_ec_level(segments: &[Segment], ecl: EcLevel, header_bits: usize, version: usize) -> EcLevel {
+    let mut ecl = ecl;
+    while let Some(next) = ecl.next() {
+        if get_best_version_for_data(segments, next, header_bits, version..=version).is_none() {
+            break;
+        }
+        ecl = next;
+    }
+    ecl
+}
+
+/// The error correction level `create` encodes with: `ecl` itself, or the
+/// raised level when `options.boost_ecl` is set.
+pub fn resolve_ec_level<'a>(
+    data: impl Into<QrData<'a>>,
+    ecl: EcLevel,
+    options: &CreateOptions,
+) -> Result<EcLevel, QrError> {
+    match options.symbology {
+        Symbology::MicroQr => microqr::create(data.into(), ecl, options).map(|symbol| symbol.ecl),
+        Symbology::Rmqr => rmqr::create(data.into(), ecl, options).map(|symbol| symbol.ecl),
+        Symbology::Qr => plan_symbol(data.into(), ecl, options).map(|(_, _, ecl)| ecl),
+    }
+}
+
+/// The element string of GS1 `data`, which must be text in bracketed form.
+fn gs1_element_string(data: &QrData) -> Result<String, QrError> {
+    match data {
+        QrData::Text(text) => gs1::element_string(text).map_err(QrError::Encode),
+        QrData::Bytes(_) | QrData::Segments(_) => {
+            Err(QrError::InvalidOption("GS1 data must be given as text".to_string()))
+        }
+    }
+}
+
+/// GS1 data starts with the FNC1 mode indicator. Inside Alphanumeric
+/// segments `%` stands for FNC1, so a literal `%` is written as `%%` there.
+/// The GS separator is not an Alphanumeric character and always lands in a
+/// Byte segment, which keeps it and `%` as they are.
+fn apply_gs1_segments(segments: Vec<Segment>) -> Vec<Segment> {
+    let mut result = vec![Segment::new(Mode::Fnc1First, "")];
+    for segment in segments {
+        if segment.mode == Mode::Alphanumeric {
+            let escaped = segment.text.replace('%', "%%");
+            result.push(Segment::new(Mode::Alphanumeric, &escaped));
+        } else {
+            result.push(segment);
+        }
+    }
+    result
+}
+
+/// Split `data` into segments the way `createSymbol` does for a symbol within
+/// `versions`.
+fn plan_segments(
+    data: QrData,
+    ecl: EcLevel,
+    options: &CreateOptions,
+    versions: &RangeInclusive<usize>,
+) -> Result<Vec<Segment>, QrError> {
+    let segments = match data {
+        QrData::Text(text) => {
+            // Estimate version from the raw (non-optimized) segments; a pinned
+            // version is the only candidate in `versions`.
+            let estimated_version = get_best_version_for_data(
+                &raw_split(text, options.kanji),
+                ecl,
+                options.header_bits(),
+                versions.clone(),
+            );
+
+            segments_from_string(
+                text,
+                estimated_version.unwrap_or(*versions.end()),
+                options.kanji,
+            )
+        }
+        QrData::Bytes(bytes) => vec![Segment::from_bytes(bytes)],
+        QrData::Segments(inputs) => segments_from_array(&inputs)?,
     };
 
-    let version = best_version;
+    Ok(if options.gs1 {
+        apply_gs1_segments(segments)
+    } else {
+        segments
+    })
+}
 
-    let data_bits = create_data(version, ecl, &segments);
+/// Encode `segments` into a `version` symbol and place every module except
+/// the mask and final format bits.
+fn place_modules(
+    version: usize,
+    ecl: EcLevel,
+    options: &CreateOptions,
+    segments: &[Segment],
+) -> BitMatrix {
+    let data_bits = create_data(version, ecl, options, segments);
 
     let module_count = get_symbol_size(version);
     let mut modules = BitMatrix::new(module_count);
@@ -1300,14 +2523,180 @@ pub fn create(data: &str, ecl: EcLevel) -> Result<BitMatrix, String> {
         setup_version_info(&mut modules, version);
     }
 
-    setup_data(&mut modules, &data_bits);
+    let roles = codeword_roles(
+        get_symbol_total_codewords(version),
+        get_total_codewords_count(version, ecl),
+        get_blocks_count(version, ecl),
+    );
+    setup_data(&mut modules, &data_bits, &roles);
 
-    let mask_pattern = get_best_mask(&mut modules, ecl);
+    modules
+}
 
-    apply_mask(mask_pattern, &mut modules);
-    setup_format_info(&mut modules, ecl, mask_pattern);
+/// Apply the forced mask, or the lowest-penalty one, and write the final
+/// format bits. Returns the applied pattern.
+fn finish_symbol(modules: &mut BitMatrix, ecl: EcLevel, mask_pattern: Option<u32>) -> u32 {
+    let mask_pattern = match mask_pattern {
+        Some(p) => p,
+        None => get_best_mask(modules, ecl),
+    };
 
-    Ok(modules)
+    apply_mask(mask_pattern, modules);
+    setup_format_info(modules, ecl, mask_pattern);
+    mask_pattern
+}
+
+// ---------------------------------------------------------------------------
+// Reading segments back (not part of node-qrcode)
+// ---------------------------------------------------------------------------
+
+/// The payload of one decoded segment: Shift JIS bytes for a Kanji segment,
+/// the characters or raw bytes of any other mode.
+pub(crate) struct ReadSegment {
+    pub kanji: bool,
+    pub bytes: Vec<u8>,
+}
+
+/// Read the data segments of a QR symbol back from its modules, with the
+/// version, level and mask from its format information; `dark(row, col)`
+/// is the module at `row`, `col`. There is no error correction, so a damaged
+/// symbol reads back wrong: callers check the bytes against a decoder's
+/// corrected payload. Like quirc, reading stops at a mode other than
+/// Numeric, Alphanumeric, Byte, Kanji or ECI. `None` when the bits run out
+/// inside a segment.
+pub(crate) fn read_segments(
+    version: usize,
+    ecl: EcLevel,
+    mask_pattern: u32,
+    dark: impl Fn(usize, usize) -> bool,
+) -> Option<Vec<ReadSegment>> {
+    let size = get_symbol_size(version);
+    let mut layout = BitMatrix::new(size);
+    setup_finder_pattern(&mut layout, version);
+    setup_timing_pattern(&mut layout);
+    setup_alignment_pattern(&mut layout, version);
+    setup_format_info(&mut layout, ecl, 0);
+    if version >= 7 {
+        setup_version_info(&mut layout, version);
+    }
+
+    // The codewords, in the order `setup_data` placed them
+    let total_codewords = get_symbol_total_codewords(version);
+    let mut codewords = vec![0u8; total_codewords];
+    let mut bit_index = 0usize;
+    let mut col = size - 1;
+    let mut upward = true;
+    while col > 0 {
+        if col == 6 {
+            col -= 1;
+        }
+        for i in 0..size {
+            let row = if upward { size - 1 - i } else { i };
+            for c in [col, col - 1] {
+                if layout.is_reserved(row, c) || bit_index >= total_codewords * 8 {
+                    continue;
+                }
+                if dark(row, c) != get_mask_at(mask_pattern, row, c) {
+                    codewords[bit_index / 8] |= 0x80 >> (bit_index % 8);
+                }
+                bit_index += 1;
+            }
+        }
+        upward = !upward;
+        col = col.saturating_sub(2);
+    }
+
+    // Undo the interleaving of the data codewords
+    let ec_total = get_total_codewords_count(version, ecl);
+    let ec_total_blocks = get_blocks_count(version, ecl);
+    let (blocks_in_group1, data_in_group1, _) =
+        block_layout(total_codewords, ec_total, ec_total_blocks);
+    let mut blocks = vec![Vec::with_capacity(data_in_group1 + 1); ec_total_blocks];
+    let mut codewords = codewords.into_iter();
+    for i in 0..=data_in_group1 {
+        for (b, block) in blocks.iter_mut().enumerate() {
+            if i < data_in_group1 || b >= blocks_in_group1 {
+                block.push(codewords.next()?);
+            }
+        }
+    }
+    let data = blocks.concat();
+
+    let mut position = 0usize;
+    let mut take = |bits: usize| -> Option<u32> {
+        if position + bits > data.len() * 8 {
+            return None;
+        }
+        let mut value = 0u32;
+        for i in position..position + bits {
+            value = (value << 1) | ((data[i / 8] >> (7 - i % 8)) & 1) as u32;
+        }
+        position += bits;
+        Some(value)
+    };
+
+    let mut segments = Vec::new();
+    while let Some(mode) = take(4) {
+        let mut bytes = Vec::new();
+        let kanji = mode == Mode::Kanji.bit();
+        match mode {
+            m if m == Mode::Numeric.bit() => {
+                let mut count = take(Mode::Numeric.cc_bits(version))? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = take([4, 7, 10][digits - 1])?;
+                    bytes.extend(format!("{:0width$}", value, width = digits).bytes());
+                    count -= digits;
+                }
+            }
+            m if m == Mode::Alphanumeric.bit() => {
+                let mut count = take(Mode::Alphanumeric.cc_bits(version))? as usize;
+                while count > 0 {
+                    if count >= 2 {
+                        let value = take(11)? as usize;
+                        bytes.push(*ALPHA_NUM_CHARS.get(value / 45)?);
+                        bytes.push(ALPHA_NUM_CHARS[value % 45]);
+                        count -= 2;
+                    } else {
+                        bytes.push(*ALPHA_NUM_CHARS.get(take(6)? as usize)?);
+                        count -= 1;
+                    }
+                }
+            }
+            m if m == Mode::Byte.bit() => {
+                let count = take(Mode::Byte.cc_bits(version))?;
+                for _ in 0..count {
+                    bytes.push(take(8)? as u8);
+                }
+            }
+            m if m == Mode::Kanji.bit() => {
+                let count = take(Mode::Kanji.cc_bits(version))?;
+                for _ in 0..count {
+                    let value = take(13)?;
+                    let intermediate = ((value / 0xC0) << 8) | (value % 0xC0);
+                    let sjis = if intermediate + 0x8140 <= 0x9FFC {
+                        intermediate + 0x8140
+                    } else {
+                        intermediate + 0xC140
+                    };
+                    bytes.extend((sjis as u16).to_be_bytes());
+                }
+            }
+            ECI_MODE_INDICATOR => {
+                // One to three designator bytes, by the leading bits
+                let first = take(8)?;
+                if first & 0xC0 == 0x80 {
+                    take(8)?;
+                } else if first & 0xE0 == 0xC0 {
+                    take(16)?;
+                }
+                continue;
+            }
+            _ => break,
+        }
+        segments.push(ReadSegment { kanji, bytes });
+    }
+    Some(segments)
 }
 
 // ---------------------------------------------------------------------------
@@ -1318,6 +2707,8 @@ pub fn create(data: &str, ecl: EcLevel) -> Result<BitMatrix, String> {
 struct Color {
     hex: String,
     a: u8,
+    /// The `r`, `g`, `b` and `a` node-qrcode's raster renderers paint with.
+    rgba: [u8; 4],
 }
 
 /// Mimics JavaScript `parseInt(str, 16)`: consumes the leading run of valid hex
@@ -1336,11 +2727,11 @@ fn js_parse_int_hex(s: &str) -> u32 {
 }
 
 /// Faithful port of `hex2rgba`.
-fn hex2rgba(hex: &str) -> Result<Color, String> {
+fn hex2rgba(hex: &str) -> Result<Color, QrError> {
     let mut hex_code: Vec<char> = hex.replace('#', "").chars().collect();
 
     if hex_code.len() < 3 || hex_code.len() == 5 || hex_code.len() > 8 {
-        return Err(format!("Invalid hex color: {}", hex));
+        return Err(QrError::InvalidColor(format!("Invalid hex color: {}", hex)));
     }
 
     // Short to long form (fff -> ffffff).
@@ -1362,7 +2753,9 @@ fn hex2rgba(hex: &str) -> Result<Color, String> {
     let a = (hex_value & 255) as u8;
     let hex_out: String = format!("#{}", hex_code[0..6].iter().collect::<String>());
 
-    Ok(Color { hex: hex_out, a })
+    let rgba = hex_value.to_be_bytes();
+
+    Ok(Color { hex: hex_out, a, rgba })
 }
 
 struct RenderOptions {
@@ -1379,7 +2772,7 @@ fn get_options(
     margin: Option<i64>,
     dark: Option<&str>,
     light: Option<&str>,
-) -> Result<RenderOptions, String> {
+) -> Result<RenderOptions, QrError> {
     let margin = match margin {
         None => 4,
         Some(m) if m < 0 => 4,
@@ -1444,13 +2837,15 @@ fn format_half(y: f64) -> String {
     }
 }
 
-fn qr_to_path(data: &[u8], size: usize, margin: i64) -> String {
+fn qr_to_path(matrix: &BitMatrix, margin: i64) -> String {
+    let size = matrix.width;
+    let dark = |row: usize, col: usize| matrix.get(row, col) != 0;
     let mut path = String::new();
     let mut move_by: i64 = 0;
     let mut new_row = false;
     let mut line_length: i64 = 0;
 
-    for i in 0..data.len() {
+    for i in 0..size * matrix.height {
         let col = (i % size) as i64;
         let row = (i / size) as i64;
 
@@ -1458,10 +2853,10 @@ fn qr_to_path(data: &[u8], size: usize, margin: i64) -> String {
             new_row = true;
         }
 
-        if data[i] != 0 {
+        if dark(row as usize, col as usize) {
             line_length += 1;
 
-            if !(i > 0 && col > 0 && data[i - 1] != 0) {
+            if !(i > 0 && col > 0 && dark(row as usize, col as usize - 1)) {
                 if new_row {
                     path.push_str(&svg_cmd_xy("M", col + margin, 0.5 + row as f64 + margin as f64));
                 } else {
@@ -1471,7 +2866,7 @@ fn qr_to_path(data: &[u8], size: usize, margin: i64) -> String {
                 new_row = false;
             }
 
-            if !(col + 1 < size as i64 && data[i + 1] != 0) {
+            if !(col + 1 < size as i64 && dark(row as usize, col as usize + 1)) {
                 path.push_str(&svg_cmd_x("h", line_length));
                 line_length = 0;
             }
@@ -1486,19 +2881,178 @@ fn qr_to_path(data: &[u8], size: usize, margin: i64) -> String {
 /// Faithful port of `svg-tag.js`'s `render`. Produces the exact string that
 /// `QRCode.toString(text, { type: 'svg', ... })` returns.
 pub fn render_svg(
-    text: &str,
+    data: QrData,
+    ecl: EcLevel,
+    create_options: &CreateOptions,
+    width: Option<u32>,
+    margin: Option<i64>,
+    dark_color: Option<&str>,
+    light_color: Option<&str>,
+) -> Result<String, QrError> {
+    let (matrix, opts) =
+        prepare_render(data, ecl, create_options, width, margin, dark_color, light_color)?;
+
+    Ok(svg_tag(&matrix, &opts))
+}
+
+/// The symbol `render_svg` draws, with its resolved quiet zone, size and
+/// colors, for renderers that paint the matrix straight into pixels.
+pub struct RenderPlan {
+    pub matrix: BitMatrix,
+    /// Quiet zone in modules.
+    pub margin: usize,
+    /// Width and height of the SVG `render_svg` returns, in pixels.
+    pub width: f64,
+    pub height: f64,
+    /// Straight RGBA colors of the dark and light modules.
+    pub dark: [u8; 4],
+    pub light: [u8; 4],
+}
+
+/// Build the symbol and layout `render_svg` would, without the SVG.
+pub fn plan_render(
+    data: QrData,
+    ecl: EcLevel,
+    create_options: &CreateOptions,
+    width: Option<u32>,
+    margin: Option<i64>,
+    dark_color: Option<&str>,
+    light_color: Option<&str>,
+) -> Result<RenderPlan, QrError> {
+    let (matrix, opts) =
+        prepare_render(data, ecl, create_options, width, margin, dark_color, light_color)?;
+
+    // Without `width` the SVG has no size and takes its viewBox, one unit
+    // per module.
+    let qrcodesize = matrix.width as i64 + opts.margin * 2;
+    let qrcodeheight = matrix.height as i64 + opts.margin * 2;
+    let (width, height) = match opts.width {
+        None => (qrcodesize, qrcodeheight),
+        Some(w) => (w as i64, (w as i64 * qrcodeheight + qrcodesize / 2) / qrcodesize),
+    };
+
+    Ok(RenderPlan {
+        matrix,
+        margin: opts.margin as usize,
+        width: width as f64,
+        height: height as f64,
+        dark: opts.dark.rgba,
+        light: opts.light.rgba,
+    })
+}
+
+fn prepare_render(
+    data: QrData,
     ecl: EcLevel,
+    create_options: &CreateOptions,
     width: Option<u32>,
     margin: Option<i64>,
     dark_color: Option<&str>,
     light_color: Option<&str>,
-) -> Result<String, String> {
+) -> Result<(BitMatrix, RenderOptions), QrError> {
+    // Micro QR only needs a 2-module quiet zone.
+    let margin = match create_options.symbology {
+        Symbology::MicroQr => margin.or(Some(microqr::QUIET_ZONE)),
+        Symbology::Rmqr => margin.or(Some(rmqr::QUIET_ZONE)),
+        Symbology::Qr => margin,
+    };
     let opts = get_options(width, margin, dark_color, light_color)?;
-    let matrix = create(text, ecl)?;
+    let matrix = create(data, ecl, create_options)?;
 
-    let size = matrix.size;
-    let data = &matrix.data;
-    let qrcodesize = size as i64 + opts.margin * 2;
+    Ok((matrix, opts))
+}
+
+/// Render each symbol of a Structured Append sequence built by
+/// `create_structured_append` exactly like `render_svg`.
+pub fn render_svg_parts(
+    symbols: &[BitMatrix],
+    width: Option<u32>,
+    margin: Option<i64>,
+    dark_color: Option<&str>,
+    light_color: Option<&str>,
+) -> Result<Vec<String>, QrError> {
+    let opts = get_options(width, margin, dark_color, light_color)?;
+
+    Ok(symbols.iter().map(|matrix| svg_tag(matrix, &opts)).collect())
+}
+
+/// Render a Structured Append sequence built by `create_structured_append`
+/// onto one sheet, `columns` symbols per row (default: a near-square grid).
+/// Each cell keeps its own quiet zone and `width` sizes a single cell, like in
+/// `render_svg`.
+pub fn render_svg_sheet(
+    symbols: &[BitMatrix],
+    columns: Option<u32>,
+    width: Option<u32>,
+    margin: Option<i64>,
+    dark_color: Option<&str>,
+    light_color: Option<&str>,
+) -> Result<String, QrError> {
+    let opts = get_options(width, margin, dark_color, light_color)?;
+
+    let count = symbols.len();
+    if count == 0 {
+        return Err(QrError::InvalidOption("No QR Code symbols to render".to_string()));
+    }
+    let columns = match columns {
+        Some(0) => {
+            return Err(QrError::InvalidOption(
+                "Invalid sheet columns: 0. Expected at least 1".to_string(),
+            ))
+        }
+        Some(c) => (c as usize).min(count),
+        None => (count as f64).sqrt().ceil() as usize,
+    };
+    let rows = count.div_ceil(columns);
+
+    // Every part shares one version, so every cell has the same size.
+    let cell = symbols[0].size() as i64 + opts.margin * 2;
+    let sheet_width = cell * columns as i64;
+    let sheet_height = cell * rows as i64;
+
+    let bg = if opts.light.a == 0 {
+        String::new()
+    } else {
+        format!(
+            "<path {} d=\"M0 0h{}v{}H0z\"/>",
+            get_color_attrib(&opts.light, "fill"),
+            sheet_width,
+            sheet_height
+        )
+    };
+
+    let mut paths = String::new();
+    for (i, matrix) in symbols.iter().enumerate() {
+        paths.push_str(&format!(
+            "<path {} transform=\"translate({} {})\" d=\"{}\"/>",
+            get_color_attrib(&opts.dark, "stroke"),
+            cell * (i % columns) as i64,
+            cell * (i / columns) as i64,
+            qr_to_path(matrix, opts.margin)
+        ));
+    }
+
+    let view_box = format!("viewBox=\"0 0 {} {}\"", sheet_width, sheet_height);
+
+    let width_attr = match opts.width {
+        None => String::new(),
+        Some(w) => format!(
+            "width=\"{}\" height=\"{}\" ",
+            w as usize * columns,
+            w as usize * rows
+        ),
+    };
+
+    Ok(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" {}{} shape-rendering=\"crispEdges\">{}{}</svg>\n",
+        width_attr, view_box, bg, paths
+    ))
+}
+
+/// The body of `svg-tag.js`'s `render` for an already built symbol.
+fn svg_tag(matrix: &BitMatrix, opts: &RenderOptions) -> String {
+    let qrcodesize = matrix.width as i64 + opts.margin * 2;
+    let qrcodeheight = matrix.height as i64 + opts.margin * 2;
 
     let bg = if opts.light.a == 0 {
         String::new()
@@ -1507,25 +3061,30 @@ pub fn render_svg(
             "<path {} d=\"M0 0h{}v{}H0z\"/>",
             get_color_attrib(&opts.light, "fill"),
             qrcodesize,
-            qrcodesize
+            qrcodeheight
         )
     };
 
     let path = format!(
         "<path {} d=\"{}\"/>",
         get_color_attrib(&opts.dark, "stroke"),
-        qr_to_path(data, size, opts.margin)
+        qr_to_path(matrix, opts.margin)
     );
 
-    let view_box = format!("viewBox=\"0 0 {} {}\"", qrcodesize, qrcodesize);
+    let view_box = format!("viewBox=\"0 0 {} {}\"", qrcodesize, qrcodeheight);
 
+    // `width` sets the horizontal size; a rectangular symbol keeps its
+    // aspect ratio.
     let width_attr = match opts.width {
         None => String::new(),
-        Some(w) => format!("width=\"{}\" height=\"{}\" ", w, w),
+        Some(w) => {
+            let height = (w as i64 * qrcodeheight + qrcodesize / 2) / qrcodesize;
+            format!("width=\"{}\" height=\"{}\" ", w, height)
+        }
     };
 
-    Ok(format!(
+    format!(
         "<svg xmlns=\"http://www.w3.org/2000/svg\" {}{} shape-rendering=\"crispEdges\">{}{}</svg>\n",
         width_attr, view_box, bg, path
-    ))
+    )
 }
@@ -0,0 +1,199 @@
+//! Writes a symbol as a one-page vector PDF for print. Dark modules are
+//! filled rectangles, one per horizontal run like the SVG path, so they stay
+//! sharp at any size; the logo is embedded as an image XObject with its alpha
+//! channel as a soft mask. The page is the size of the whole SVG, quiet zone
+//! included.
+
+use image::RgbaImage;
+use miniz_oxide::deflate::{compress_to_vec_zlib, CompressionLevel};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref};
+
+use crate::error::QrError;
+use crate::nodeqr::BitMatrix;
+use crate::raster::Patch;
+
+/// Points per CSS pixel, the page scale when no physical size is given.
+const POINTS_PER_PIXEL: f64 = 0.75;
+
+/// A symbol laid out in the user units of its SVG: a `width` x `height` page
+/// filled with `light`, whose module grid starts at `x`, `y`.
+pub struct Scene<'a> {
+    pub matrix: &'a BitMatrix,
+    pub width: f64,
+    pub height: f64,
+    pub x: f64,
+    pub y: f64,
+    pub module_width: f64,
+    pub module_height: f64,
+    /// Straight RGBA colors. PDF fills are opaque, so only a fully
+    /// transparent `light` changes the output: it leaves the page unpainted.
+    pub dark: [u8; 4],
+    pub light: [u8; 4],
+    pub logo: Option<LogoPlacement<'a>>,
+}
+
+/// Where a logo goes: a square box of `size` at `x`, `y`, which the image
+/// fits into keeping its aspect ratio, and an optional knockout patch behind
+/// it.
+pub struct LogoPlacement<'a> {
+    pub image: &'a RgbaImage,
+    pub x: f64,
+    pub y: f64,
+    pub size: f64,
+    pub patch: Option<Patch>,
+}
+
+/// The page width in points: `size` in `unit` (`"mm"` by default, `"cm"`,
+/// `"in"`, `"pt"` or `"px"`), or the scene width as CSS pixels without one.
+pub fn page_width(scene: &Scene, size: Option<f64>, unit: Option<&str>) -> Result<f64, QrError> {
+    let Some(size) = size else {
+        return Ok(scene.width * POINTS_PER_PIXEL);
+    };
+    let points_per_unit = match unit.unwrap_or("mm").to_lowercase().as_str() {
+        "mm" => 72.0 / 25.4,
+        "cm" => 72.0 / 2.54,
+        "in" => 72.0,
+        "pt" => 1.0,
+        "px" => POINTS_PER_PIXEL,
+        other => {
+            return Err(QrError::InvalidOption(format!(
+                "Unsupported PDF unit '{}': expected mm, cm, in, pt or px",
+                other
+            )))
+        }
+    };
+    if !(size.is_finite() && size > 0.0) {
+        return Err(QrError::InvalidOption("PDF width must be a positive number".to_string()));
+    }
+    Ok(size * points_per_unit)
+}
+
+/// Write the scene on a page `page_width` points wide, keeping its aspect
+/// ratio.
+pub fn render(scene: &Scene, page_width: f64) -> Result<Vec<u8>, QrError> {
+    let scale = page_width / scene.width;
+    let page_height = scene.height * scale;
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let page_id = Ref::new(3);
+    let content_id = Ref::new(4);
+    let image_id = Ref::new(5);
+    let mask_id = Ref::new(6);
+    let image_name = Name(b"Logo");
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids([page_id]).count(1);
+
+    let image = scene.logo.as_ref().map(|logo| logo.image);
+    let mut page = pdf.page(page_id);
+    page.media_box(Rect::new(0.0, 0.0, page_width as f32, page_height as f32));
+    page.parent(page_tree_id);
+    page.contents(content_id);
+    if image.is_some() {
+        page.resources().x_objects().pair(image_name, image_id);
+    }
+    page.finish();
+
+    // Draw in the scene's units with the origin at the top left, like the SVG
+    let mut content = Content::new();
+    content.transform([scale as f32, 0.0, 0.0, -scale as f32, 0.0, page_height as f32]);
+
+    if scene.light[3] != 0 {
+        set_fill(&mut content, scene.light);
+        content.rect(0.0, 0.0, scene.width as f32, scene.height as f32);
+        content.fill_nonzero();
+    }
+
+    set_fill(&mut content, scene.dark);
+    let mut any_dark = false;
+    for row in 0..scene.matrix.height {
+        let mut col = 0;
+        while col < scene.matrix.width {
+            if scene.matrix.get(row, col) == 0 {
+                col += 1;
+                continue;
+            }
+            let run_start = col;
+            while col < scene.matrix.width && scene.matrix.get(row, col) != 0 {
+                col += 1;
+            }
+            content.rect(
+                (scene.x + run_start as f64 * scene.module_width) as f32,
+                (scene.y + row as f64 * scene.module_height) as f32,
+                ((col - run_start) as f64 * scene.module_width) as f32,
+                scene.module_height as f32,
+            );
+            any_dark = true;
+        }
+    }
+    if any_dark {
+        content.fill_nonzero();
+    }
+
+    if let Some(logo) = &scene.logo {
+        if let Some(patch) = &logo.patch {
+            set_fill(&mut content, patch.color);
+            content.rect(patch.x, patch.y, patch.size, patch.size);
+            content.fill_nonzero();
+        }
+
+        // Fit the image into the logo box and center it, like an SVG
+        // `<image>` with the default `preserveAspectRatio="xMidYMid meet"`.
+        // The image space runs bottom-up, so flip it back.
+        let (width, height) = (logo.image.width() as f64, logo.image.height() as f64);
+        let fit = (logo.size / width).min(logo.size / height);
+        let x = logo.x + (logo.size - width * fit) / 2.0;
+        let y = logo.y + (logo.size - height * fit) / 2.0;
+        content.save_state();
+        content.transform([
+            (width * fit) as f32,
+            0.0,
+            0.0,
+            -(height * fit) as f32,
+            x as f32,
+            (y + height * fit) as f32,
+        ]);
+        content.x_object(image_name);
+        content.restore_state();
+    }
+
+    let level = CompressionLevel::DefaultLevel as u8;
+    let content = compress_to_vec_zlib(&content.finish(), level);
+    pdf.stream(content_id, &content).filter(Filter::FlateDecode);
+
+    if let Some(image) = image {
+        let rgb: Vec<u8> = image.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect();
+        let alpha: Vec<u8> = image.pixels().map(|p| p[3]).collect();
+        let opaque = alpha.iter().all(|&a| a == 255);
+
+        let rgb = compress_to_vec_zlib(&rgb, level);
+        let mut xobject = pdf.image_xobject(image_id, &rgb);
+        xobject.filter(Filter::FlateDecode);
+        xobject.width(image.width() as i32);
+        xobject.height(image.height() as i32);
+        xobject.color_space().device_rgb();
+        xobject.bits_per_component(8);
+        if !opaque {
+            xobject.s_mask(mask_id);
+        }
+        xobject.finish();
+
+        if !opaque {
+            let alpha = compress_to_vec_zlib(&alpha, level);
+            let mut mask = pdf.image_xobject(mask_id, &alpha);
+            mask.filter(Filter::FlateDecode);
+            mask.width(image.width() as i32);
+            mask.height(image.height() as i32);
+            mask.color_space().device_gray();
+            mask.bits_per_component(8);
+        }
+    }
+
+    Ok(pdf.finish())
+}
+
+fn set_fill(content: &mut Content, [r, g, b, _]: [u8; 4]) {
+    content.set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+}
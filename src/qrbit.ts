@@ -4,15 +4,36 @@ import path from "node:path";
 import { Cacheable } from "cacheable";
 import { Hookified, type HookifiedOptions } from "hookified";
 import {
+	convertSvgToAvif as nativeConvertSvgToAvif,
+	convertSvgToAvifAsync as nativeConvertSvgToAvifAsync,
 	convertSvgToJpeg as nativeConvertSvgToJpeg,
+	convertSvgToJpegAsync as nativeConvertSvgToJpegAsync,
 	convertSvgToPng as nativeConvertSvgToPng,
+	convertSvgToPngAsync as nativeConvertSvgToPngAsync,
 	convertSvgToWebp as nativeConvertSvgToWebp,
-	decode as nativeDecode,
-	decodeDetailed as nativeDecodeDetailed,
-	generateQrCodeSvg as nativeGenerateQrCodeSvg,
-	generateQrSvg as nativeGenerateQrSvg,
-	generateQrSvgWithBuffer as nativeGenerateQrSvgWithBuffer,
-	validateQr as nativeValidateQr,
+	convertSvgToWebpAsync as nativeConvertSvgToWebpAsync,
+	decodeAsync as nativeDecodeAsync,
+	encodeAnimationAsync as nativeEncodeAnimationAsync,
+	decodeDetailedAsync as nativeDecodeDetailedAsync,
+	generateBatch as nativeGenerateBatch,
+	generateQrCodeImageAsync as nativeGenerateQrCodeImageAsync,
+	generateQrCodePdfAsync as nativeGenerateQrCodePdfAsync,
+	generateQrCodeSvgAsync as nativeGenerateQrCodeSvgAsync,
+	generateQrCodeSvgParts as nativeGenerateQrCodeSvgParts,
+	generateQrCodeSvgSheet as nativeGenerateQrCodeSvgSheet,
+	generateQrMatrix as nativeGenerateQrMatrix,
+	generateQrMatrixJson as nativeGenerateQrMatrixJson,
+	generateQrMatrixText as nativeGenerateQrMatrixText,
+	generateQrImageAsync as nativeGenerateQrImageAsync,
+	generateQrImageWithBufferAsync as nativeGenerateQrImageWithBufferAsync,
+	generateQrPdfAsync as nativeGenerateQrPdfAsync,
+	generateQrPdfWithBufferAsync as nativeGenerateQrPdfWithBufferAsync,
+	generateQrSvgAsync as nativeGenerateQrSvgAsync,
+	generateQrSvgWithBufferAsync as nativeGenerateQrSvgWithBufferAsync,
+	getErrorCorrectionLevel as nativeGetErrorCorrectionLevel,
+	getMaskPenalties as nativeGetMaskPenalties,
+	inspect as nativeInspect,
+	validateQrAsync as nativeValidateQrAsync,
 } from "./native.js";
 
 export enum QrBitEvents {
@@ -21,6 +42,46 @@ export enum QrBitEvents {
 	error = "error",
 }
 
+/** The `code` property of errors thrown while encoding, rendering or decoding. */
+export enum QrBitErrorCode {
+	/** The data does not fit in the allowed versions. The error's `needed` and `available` give the bits. */
+	dataTooLong = "ERR_DATA_TOO_LONG",
+	invalidColor = "ERR_INVALID_COLOR",
+	/** An option is missing, out of range or not supported by the symbology. */
+	invalidOption = "ERR_INVALID_OPTION",
+	/** The logo is not a recognised image. */
+	logoDecode = "ERR_LOGO_DECODE",
+	svgParse = "ERR_SVG_PARSE",
+	/** The data cannot be encoded as given, or an image encoder failed. */
+	encode = "ERR_ENCODE",
+	/** The image to decode could not be read. */
+	decode = "ERR_DECODE",
+}
+
+/** An error thrown by the native encoder, renderers and decoder. */
+export type QrBitError = Error & {
+	code: QrBitErrorCode;
+	/** Data bits the payload needs, for `ERR_DATA_TOO_LONG`. */
+	needed?: number;
+	/** Data bits the largest allowed symbol has, for `ERR_DATA_TOO_LONG`. */
+	available?: number;
+};
+
+/** Role of a module in `QrMatrix.roles`. */
+export enum ModuleRole {
+	/** Finder pattern with its separator, and rMQR's sub-finder and corner patterns. */
+	finder = 0,
+	timing = 1,
+	alignment = 2,
+	/** Format information, including the dark module. */
+	format = 3,
+	version = 4,
+	data = 5,
+	errorCorrection = 6,
+	/** Remainder bits after the last codeword. */
+	remainder = 7,
+}
+
 export type ECLevel =
 	| "L"
 	| "M"
@@ -31,15 +92,31 @@ export type ECLevel =
 	| "Quartile"
 	| "High";
 
+export type Symbology = "qr" | "microqr" | "rmqr";
+
+export type QrSegment = {
+	/**
+	 * The encoding mode of the segment.
+	 * @type {"numeric" | "alphanumeric" | "byte" | "kanji"}
+	 */
+	mode: "numeric" | "alphanumeric" | "byte" | "kanji";
+	/**
+	 * The characters to encode in that mode.
+	 * @type {string}
+	 */
+	data: string;
+};
+
 const logoFileDoesNotExistMessage = (logo: string) =>
 	`Logo file not found: ${logo}. Proceeding without logo.`;
 
 export type QrOptions = {
 	/**
 	 * The text content to encode in the QR code. It can be text or a url.
+	 * Either `text`, `data`, `gs1` or `segments` must be provided.
 	 * @type {string}
 	 */
-	text: string;
+	text?: string;
 	/**
 	 * The size of the QR code in pixels.
 	 * @type {number}
@@ -97,6 +174,90 @@ export type QrOptions = {
 	 * @default "H"
 	 */
 	errorCorrection?: ECLevel;
+	/**
+	 * Pin the QR code version (1-40) instead of picking the smallest version
+	 * that fits the data. Generation fails if the data does not fit.
+	 * @type {number}
+	 */
+	version?: number;
+	/**
+	 * The smallest QR code version (1-40) the encoder may pick.
+	 * @type {number}
+	 */
+	minVersion?: number;
+	/**
+	 * The largest QR code version (1-40) the encoder may pick. Generation fails
+	 * if the data does not fit.
+	 * @type {number}
+	 */
+	maxVersion?: number;
+	/**
+	 * Force a mask pattern (0-7) instead of the one with the lowest penalty
+	 * score. Use `getMaskPenalties()` to compare the patterns.
+	 * @type {number}
+	 */
+	maskPattern?: number;
+	/**
+	 * Encode Japanese text in Kanji mode (Shift JIS, 13 bits per character)
+	 * instead of UTF-8 bytes, which produces noticeably smaller codes. Off by
+	 * default to keep output identical to the `qrcode` package.
+	 * @type {boolean}
+	 * @default false
+	 */
+	kanji?: boolean;
+	/**
+	 * Declare the character set with an ECI header, e.g. `26` for UTF-8 so
+	 * older scanners don't read accented or CJK text as ISO-8859-1. Takes an
+	 * ECI assignment number (0-999999).
+	 * @type {number}
+	 */
+	eci?: number;
+	/**
+	 * Raw bytes to encode instead of `text`, such as compressed tokens, CBOR
+	 * tickets or binary signatures. Stored as-is in Byte mode.
+	 * @type {Buffer | Uint8Array}
+	 */
+	data?: Buffer | Uint8Array;
+	/**
+	 * The symbol family to generate. `"microqr"` produces a Micro QR Code
+	 * (M1-M4) with a single finder pattern and a 2-module quiet zone, for very
+	 * small labels. It holds at most 35 digits or 15 bytes, supports error
+	 * correction levels L, M and Q (`errorCorrection` defaults to M), and
+	 * counts `version`, `minVersion` and `maxVersion` as 1-4 and
+	 * `maskPattern` as 0-3. `"rmqr"` produces a rectangular Micro QR Code
+	 * (R7x43 to R17x139) for narrow labels, with error correction levels M
+	 * and H only and `version`, `minVersion` and `maxVersion` counted as 1-32;
+	 * `size` sets its width.
+	 * @type {Symbology}
+	 * @default "qr"
+	 */
+	symbology?: Symbology;
+	/**
+	 * GS1 element string to encode as a GS1 QR Code instead of `text` or
+	 * `data`, in bracketed form such as
+	 * `(01)09506000134352(17)251231(10)ABC123`. Application Identifiers
+	 * 00, 01, 02, 10, 11, 13, 15, 17, 21, 30 and 37 are supported, and
+	 * lengths, character sets, dates and check digits are validated.
+	 * @type {string}
+	 */
+	gs1?: string;
+	/**
+	 * Raise the error correction level (L to M to Q to H) for as long as the
+	 * data still fits in the same version, adding robustness at no cost in
+	 * size, which helps most with logos. `errorCorrection` is the minimum;
+	 * `getErrorCorrectionLevel()` reports the level actually used. QR Code
+	 * symbols only.
+	 * @type {boolean}
+	 * @default false
+	 */
+	boostEcl?: boolean;
+	/**
+	 * Segments to encode in order, each with its own mode, instead of `text`
+	 * or `data`. The automatic segmentation is skipped, so this gives full
+	 * control over the mode switches. Micro QR and rMQR take a single segment.
+	 * @type {QrSegment[]}
+	 */
+	segments?: QrSegment[];
 	/**
 	 * Caching is enabled by default. You can disable it by setting this option to false. You can also pass
 	 * a custom Cacheable instance.
@@ -116,6 +277,163 @@ export interface QrResult {
 export type toOptions = {
 	cache?: boolean;
 	quality?: number;
+	/** Encode WebP losslessly even when a `quality` is given. */
+	lossless?: boolean;
+	/** WebP compression effort, 0 (fastest) to 6 (smallest, default: 4). */
+	effort?: number;
+	/** AVIF encoder speed, 1 (slowest, smallest) to 10 (fastest, default: 4). */
+	speed?: number;
+};
+
+export type PdfOptions = {
+	cache?: boolean;
+	/** Physical page width in `unit` (default: the SVG size, 0.75pt per px). */
+	width?: number;
+	/** Unit of `width` (default: "mm"). */
+	unit?: "mm" | "cm" | "in" | "pt" | "px";
+};
+
+export type SheetOptions = {
+	/** Symbols per row. Defaults to a near-square grid. */
+	columns?: number;
+};
+
+/** One frame of an animated GIF or APNG. */
+export type AnimationFrame = {
+	svg: string;
+	/** How long the frame is shown, in milliseconds. Defaults to the `delay` option. */
+	delay?: number;
+};
+
+export type AnimationOptions = {
+	/** Output format (default: "gif"). */
+	format?: "gif" | "apng";
+	/** Frame size in pixels. Defaults to the first frame rendered at 2x. */
+	width?: number;
+	height?: number;
+	/** Milliseconds per frame for frames without their own `delay` (default: 1000). */
+	delay?: number;
+	/** How many times the animation plays; 0 loops forever (default: 0). */
+	loop?: number;
+};
+
+/** The payload of one code in `QrBit.generateBatch()`. */
+export type BatchItem = Pick<QrOptions, "text" | "data" | "gs1" | "segments">;
+
+/** Style shared by every code in `QrBit.generateBatch()`. */
+export type BatchStyle = Pick<
+	QrOptions,
+	| "size"
+	| "margin"
+	| "logo"
+	| "logoSizeRatio"
+	| "logoBackgroundColor"
+	| "logoPaddingRatio"
+	| "backgroundColor"
+	| "foregroundColor"
+	| "errorCorrection"
+	| "version"
+	| "minVersion"
+	| "maxVersion"
+	| "maskPattern"
+	| "kanji"
+	| "eci"
+	| "boostEcl"
+	| "symbology"
+> & {
+	/** JPEG quality 1-100 for the `"jpeg"` format (default: 90), lossy WebP quality 0-100 for `"webp"`, or AVIF quality 1-100 for `"avif"` (default: 80). */
+	quality?: number;
+	/** Encode `"webp"` losslessly even when a `quality` is given. */
+	lossless?: boolean;
+	/** WebP compression effort, 0 (fastest) to 6 (smallest, default: 4). */
+	effort?: number;
+	/** AVIF encoder speed, 1 (slowest, smallest) to 10 (fastest, default: 4). */
+	speed?: number;
+};
+
+export type BatchFormat = "svg" | "png" | "jpeg" | "webp" | "avif";
+
+export type BatchResult = {
+	/** The SVG string, or the image buffer for raster formats. Unset when the item failed. */
+	output?: string | Buffer;
+	/** Why the item failed. */
+	error?: string;
+	/** Stable code of the failure, as on thrown errors. */
+	code?: QrBitErrorCode;
+	/** Data bits the payload needs, for `ERR_DATA_TOO_LONG`. */
+	needed?: number;
+	/** Data bits the largest allowed symbol has, for `ERR_DATA_TOO_LONG`. */
+	available?: number;
+};
+
+export type MaskPenalties = {
+	/** The QR code version the penalties were scored for. */
+	version: number;
+	/** Penalty score of each mask pattern, indexed by pattern (0-7). Lower is better. */
+	penalties: number[];
+	/** The pattern picked automatically (lowest penalty, first on ties). */
+	bestMaskPattern: number;
+};
+
+export type Capacity = {
+	/** Data bits. */
+	bits: number;
+	/** Digits in Numeric mode. */
+	numeric: number;
+	/** Characters in Alphanumeric mode. */
+	alphanumeric: number;
+	/** Bytes in Byte mode. */
+	byte: number;
+	/** Characters in Kanji mode. */
+	kanji: number;
+};
+
+export type QrMatrix = {
+	/** Modules per row; the width for rMQR symbols. */
+	size: number;
+	/** Number of rows, equal to `size` except for rMQR symbols. */
+	height: number;
+	/** The version of the symbol. */
+	version: number;
+	/** The error correction level encoded with, raised when `boostEcl` is set. */
+	errorCorrection: "L" | "M" | "Q" | "H";
+	/** The applied mask pattern; undefined for rMQR's fixed mask. */
+	maskPattern?: number;
+	/** Rows packed 8 modules to a byte, leftmost module in the most significant bit, each row padded to a whole byte. */
+	packed: Buffer;
+	/** One array of 0 (light) and 1 (dark) per row. */
+	rows: number[][];
+	/** The `ModuleRole` of every module, row by row. */
+	roles: Buffer;
+	/** Error correction block of every data and error correction module, row by row; 0 for the other modules. */
+	blocks: Buffer;
+};
+
+export type Inspection = {
+	/** The QR code version the data is encoded in. */
+	version: number;
+	/** The error correction level encoded with, raised when `boostEcl` is set. */
+	errorCorrection: "L" | "M" | "Q" | "H";
+	/** The forced mask pattern, or the one picked automatically. */
+	maskPattern: number;
+	/** The segments in order, each with its mode, data, length and bit cost. */
+	segments: Array<{
+		mode: "Numeric" | "Alphanumeric" | "Byte" | "Kanji" | "FNC1";
+		data: string;
+		length: number;
+		bits: number;
+	}>;
+	/** Structured Append and ECI header bits. */
+	headerBits: number;
+	/** Header and segment bits, without terminator and padding. */
+	totalBits: number;
+	/** Free bits, and how much of each mode still fits in one more segment. */
+	remaining: Capacity;
+	/** Capacity of the version at every error correction level, from L to H. */
+	capacities: Array<{
+		errorCorrection: "L" | "M" | "Q" | "H";
+		capacity: Capacity;
+	}>;
 };
 
 export type DecodeResult = {
@@ -151,17 +469,42 @@ export class QrBit extends Hookified {
 	private _backgroundColor: string;
 	private _foregroundColor: string;
 	private _errorCorrection: ECLevel;
+	private _version: number | undefined;
+	private _minVersion: number | undefined;
+	private _maxVersion: number | undefined;
+	private _maskPattern: number | undefined;
+	private _kanji: boolean;
+	private _eci: number | undefined;
+	private _data: Buffer | undefined;
+	private _symbology: Symbology;
+	private _gs1: string | undefined;
+	private _boostEcl: boolean;
+	private _segments: QrSegment[] | undefined;
 	private _cache: Cacheable | undefined;
 	private _napi = {
-		convertSvgToJpeg: nativeConvertSvgToJpeg,
-		convertSvgToPng: nativeConvertSvgToPng,
-		convertSvgToWebp: nativeConvertSvgToWebp,
-		decode: nativeDecode,
-		decodeDetailed: nativeDecodeDetailed,
-		generateQrCodeSvg: nativeGenerateQrCodeSvg,
-		generateQrSvg: nativeGenerateQrSvg,
-		generateQrSvgWithBuffer: nativeGenerateQrSvgWithBuffer,
-		validateQr: nativeValidateQr,
+		convertSvgToJpegAsync: nativeConvertSvgToJpegAsync,
+		convertSvgToPngAsync: nativeConvertSvgToPngAsync,
+		convertSvgToWebpAsync: nativeConvertSvgToWebpAsync,
+		decodeAsync: nativeDecodeAsync,
+		decodeDetailedAsync: nativeDecodeDetailedAsync,
+		generateQrCodeImageAsync: nativeGenerateQrCodeImageAsync,
+		generateQrCodePdfAsync: nativeGenerateQrCodePdfAsync,
+		generateQrCodeSvgAsync: nativeGenerateQrCodeSvgAsync,
+		generateQrCodeSvgParts: nativeGenerateQrCodeSvgParts,
+		generateQrCodeSvgSheet: nativeGenerateQrCodeSvgSheet,
+		generateQrMatrix: nativeGenerateQrMatrix,
+		generateQrMatrixJson: nativeGenerateQrMatrixJson,
+		generateQrMatrixText: nativeGenerateQrMatrixText,
+		generateQrImageAsync: nativeGenerateQrImageAsync,
+		generateQrImageWithBufferAsync: nativeGenerateQrImageWithBufferAsync,
+		generateQrPdfAsync: nativeGenerateQrPdfAsync,
+		generateQrPdfWithBufferAsync: nativeGenerateQrPdfWithBufferAsync,
+		generateQrSvgAsync: nativeGenerateQrSvgAsync,
+		generateQrSvgWithBufferAsync: nativeGenerateQrSvgWithBufferAsync,
+		getErrorCorrectionLevel: nativeGetErrorCorrectionLevel,
+		getMaskPenalties: nativeGetMaskPenalties,
+		inspect: nativeInspect,
+		validateQrAsync: nativeValidateQrAsync,
 	};
 
 	/**
@@ -170,7 +513,7 @@ export class QrBit extends Hookified {
 	 */
 	constructor(options: QrOptions) {
 		super();
-		this._text = options.text;
+		this._text = options.text ?? "";
 		this._size = options.size ?? 200;
 		this._margin = options.margin ?? undefined;
 		this._logo = options.logo;
@@ -182,7 +525,21 @@ export class QrBit extends Hookified {
 				? undefined
 				: (options.logoBackgroundColor ?? this._backgroundColor);
 		this._logoPaddingRatio = options.logoPaddingRatio ?? 0.1;
-		this._errorCorrection = options.errorCorrection ?? "H";
+		// Micro QR has no level H, so it defaults to M instead.
+		this._errorCorrection =
+			options.errorCorrection ??
+			(options.symbology === "microqr" ? "M" : "H");
+		this._version = options.version;
+		this._minVersion = options.minVersion;
+		this._maxVersion = options.maxVersion;
+		this._maskPattern = options.maskPattern;
+		this._kanji = options.kanji ?? false;
+		this._eci = options.eci;
+		this._data = options.data ? Buffer.from(options.data) : undefined;
+		this._symbology = options.symbology ?? "qr";
+		this._gs1 = options.gs1;
+		this._boostEcl = options.boostEcl ?? false;
+		this._segments = options.segments;
 		if (options.cache !== undefined) {
 			// if it is boolean and true then create a new cacheable instance
 			if (options.cache === true) {
@@ -367,6 +724,193 @@ export class QrBit extends Hookified {
 		this._errorCorrection = value;
 	}
 
+	/**
+	 * Get the pinned QR code version.
+	 * @returns {number | undefined} The version, or undefined to pick automatically
+	 * @default undefined
+	 */
+	public get version(): number | undefined {
+		return this._version;
+	}
+
+	/**
+	 * Pin the QR code version (1-40).
+	 * @param value - The version, or undefined to pick automatically
+	 */
+	public set version(value: number | undefined) {
+		this._version = value;
+	}
+
+	/**
+	 * Get the smallest QR code version the encoder may pick.
+	 * @returns {number | undefined} The minimum version
+	 * @default undefined
+	 */
+	public get minVersion(): number | undefined {
+		return this._minVersion;
+	}
+
+	/**
+	 * Set the smallest QR code version (1-40) the encoder may pick.
+	 * @param value - The minimum version, or undefined for no lower bound
+	 */
+	public set minVersion(value: number | undefined) {
+		this._minVersion = value;
+	}
+
+	/**
+	 * Get the largest QR code version the encoder may pick.
+	 * @returns {number | undefined} The maximum version
+	 * @default undefined
+	 */
+	public get maxVersion(): number | undefined {
+		return this._maxVersion;
+	}
+
+	/**
+	 * Set the largest QR code version (1-40) the encoder may pick.
+	 * @param value - The maximum version, or undefined for no upper bound
+	 */
+	public set maxVersion(value: number | undefined) {
+		this._maxVersion = value;
+	}
+
+	/**
+	 * Get the forced mask pattern.
+	 * @returns {number | undefined} The mask pattern, or undefined to pick automatically
+	 * @default undefined
+	 */
+	public get maskPattern(): number | undefined {
+		return this._maskPattern;
+	}
+
+	/**
+	 * Force a mask pattern (0-7).
+	 * @param value - The mask pattern, or undefined to pick automatically
+	 */
+	public set maskPattern(value: number | undefined) {
+		this._maskPattern = value;
+	}
+
+	/**
+	 * Get whether Kanji mode encoding is enabled.
+	 * @returns {boolean} True if Kanji mode is enabled
+	 * @default false
+	 */
+	public get kanji(): boolean {
+		return this._kanji;
+	}
+
+	/**
+	 * Enable or disable Kanji mode encoding for Japanese text.
+	 * @param value - True to enable Kanji mode
+	 */
+	public set kanji(value: boolean) {
+		this._kanji = value;
+	}
+
+	/**
+	 * Get the ECI assignment number declared in the QR code.
+	 * @returns {number | undefined} The ECI assignment number, or undefined for no ECI header
+	 * @default undefined
+	 */
+	public get eci(): number | undefined {
+		return this._eci;
+	}
+
+	/**
+	 * Set the ECI assignment number to declare in the QR code (e.g. 26 for UTF-8).
+	 * @param value - The ECI assignment number, or undefined for no ECI header
+	 */
+	public set eci(value: number | undefined) {
+		this._eci = value;
+	}
+
+	/**
+	 * Get the raw bytes to encode instead of the text.
+	 * @returns {Buffer | undefined} The raw bytes, or undefined to encode the text
+	 * @default undefined
+	 */
+	public get data(): Buffer | undefined {
+		return this._data;
+	}
+
+	/**
+	 * Set raw bytes to encode instead of the text.
+	 * @param value - The raw bytes, or undefined to encode the text
+	 */
+	public set data(value: Buffer | Uint8Array | undefined) {
+		this._data = value ? Buffer.from(value) : undefined;
+	}
+
+	/**
+	 * Get the symbol family.
+	 * @returns {Symbology} The symbol family
+	 * @default "qr"
+	 */
+	public get symbology(): Symbology {
+		return this._symbology;
+	}
+
+	/**
+	 * Set the symbol family.
+	 * @param value - `"qr"`, `"microqr"` or `"rmqr"`
+	 */
+	public set symbology(value: Symbology) {
+		this._symbology = value;
+	}
+
+	/**
+	 * Get the GS1 element string to encode instead of the text.
+	 * @returns {string | undefined} The bracketed GS1 element string, or undefined to encode the text
+	 * @default undefined
+	 */
+	public get gs1(): string | undefined {
+		return this._gs1;
+	}
+
+	/**
+	 * Set a GS1 element string to encode instead of the text.
+	 * @param value - The bracketed GS1 element string, e.g. `(01)09506000134352(17)251231`, or undefined to encode the text
+	 */
+	public set gs1(value: string | undefined) {
+		this._gs1 = value;
+	}
+
+	/**
+	 * Get whether the error correction level is raised while the version stays the same.
+	 * @returns {boolean} True if the level is boosted
+	 * @default false
+	 */
+	public get boostEcl(): boolean {
+		return this._boostEcl;
+	}
+
+	/**
+	 * Set whether to raise the error correction level while the version stays the same.
+	 * @param value - True to boost the level
+	 */
+	public set boostEcl(value: boolean) {
+		this._boostEcl = value;
+	}
+
+	/**
+	 * Get the segments to encode instead of the text.
+	 * @returns {QrSegment[] | undefined} The segments, or undefined to encode the text
+	 * @default undefined
+	 */
+	public get segments(): QrSegment[] | undefined {
+		return this._segments;
+	}
+
+	/**
+	 * Set the segments to encode instead of the text.
+	 * @param value - The segments in order, or undefined to encode the text
+	 */
+	public set segments(value: QrSegment[] | undefined) {
+		this._segments = value;
+	}
+
 	/**
 	 * Get the cache instance.
 	 * @returns {Cacheable | undefined} The cache instance or undefined if caching is disabled
@@ -398,17 +942,6 @@ export class QrBit extends Hookified {
 			renderKey = `napi-svg`;
 		}
 
-		// set all the options
-		const qrOptions = {
-			text: this._text,
-			size: this._size,
-			margin: this._margin,
-			logo: this._logo,
-			logoSizeRatio: this._logoSizeRatio,
-			backgroundColor: this._backgroundColor,
-			foregroundColor: this._foregroundColor,
-		};
-
 		// check the cache
 		const cache = options?.cache !== false ? this._cache : undefined;
 		let cacheKey: string | undefined;
@@ -423,13 +956,10 @@ export class QrBit extends Hookified {
 		if (!this._logo) {
 			// Native Rust port of node-qrcode that produces byte-for-byte
 			// identical SVG output (path-based, crisp-edges, margin of 4 modules).
-			result = this._napi.generateQrCodeSvg({
-				text: this._text,
-				width: qrOptions.size,
-				errorCorrection: this._errorCorrection,
-				darkColor: qrOptions.foregroundColor,
-				lightColor: qrOptions.backgroundColor,
-			});
+			// Encoding runs on the libuv threadpool, off the event loop.
+			result = await this._napi.generateQrCodeSvgAsync(
+				this.codeSvgOptions(),
+			);
 		} else {
 			// If logoPath is set, use the Rust implementation
 			result = await this.toSvgNapi();
@@ -451,50 +981,140 @@ export class QrBit extends Hookified {
 		// Choose optimal path based on logo type
 		if (this._logo && Buffer.isBuffer(this._logo)) {
 			// Logo is already a buffer - use buffer function
-			const nativeOptionsBuffer = {
-				text: this._text,
-				size: this._size,
-				margin: this._margin,
+			return this._napi.generateQrSvgWithBufferAsync({
+				...this.logoOptions(),
 				logoBuffer: this._logo,
-				logoSizeRatio: this._logoSizeRatio,
-				logoBackgroundColor: this._logoBackgroundColor,
-				logoPaddingRatio: this._logoPaddingRatio,
-				backgroundColor: this._backgroundColor,
-				foregroundColor: this._foregroundColor,
-				errorCorrection: this._errorCorrection,
-			};
-			return this._napi.generateQrSvgWithBuffer(nativeOptionsBuffer);
+			});
 		} else {
 			// Logo is a string path or undefined - use original function
-			const nativeOptions = {
-				text: this._text,
-				size: this._size,
-				margin: this._margin,
+			await this.checkLogoFile();
+			return this._napi.generateQrSvgAsync({
+				...this.logoOptions(),
 				logoPath: this._logo as string,
-				logoSizeRatio: this._logoSizeRatio,
-				logoBackgroundColor: this._logoBackgroundColor,
-				logoPaddingRatio: this._logoPaddingRatio,
-				backgroundColor: this._backgroundColor,
-				foregroundColor: this._foregroundColor,
-				errorCorrection: this._errorCorrection,
-			};
-
-			if (this._logo && this.isLogoString()) {
-				if (!(await this.logoFileExists(this._logo as string))) {
-					this.emit(
-						QrBitEvents.error,
-						logoFileDoesNotExistMessage(this._logo as string),
-					);
-				}
-			}
+			});
+		}
+	}
+
+	/**
+	 * Render the QR code straight to an image with the native raster path,
+	 * which paints the modules and logo without building an SVG first. The
+	 * output matches converting `toSvg()` at its default size.
+	 * @param format - The image format
+	 * @param quality - JPEG quality 1-100, lossy WebP quality 0-100 or AVIF quality 1-100
+	 * @param lossless - Encode WebP losslessly even with a quality
+	 * @param effort - WebP compression effort 0-6
+	 * @param speed - AVIF encoder speed 1-10
+	 * @returns {Promise<Buffer>} The image buffer
+	 */
+	private async toImage(
+		format: "png" | "jpeg" | "webp" | "avif",
+		quality?: number,
+		lossless?: boolean,
+		effort?: number,
+		speed?: number,
+	): Promise<Buffer> {
+		if (!this._logo) {
+			return this._napi.generateQrCodeImageAsync(
+				this.codeSvgOptions(),
+				format,
+				quality,
+				lossless,
+				effort,
+				speed,
+			);
+		}
 
-			return this._napi.generateQrSvg(nativeOptions);
+		if (Buffer.isBuffer(this._logo)) {
+			return this._napi.generateQrImageWithBufferAsync(
+				{ ...this.logoOptions(), logoBuffer: this._logo },
+				format,
+				quality,
+				lossless,
+				effort,
+				speed,
+			);
+		}
+
+		await this.checkLogoFile();
+		return this._napi.generateQrImageAsync(
+			{ ...this.logoOptions(), logoPath: this._logo as string },
+			format,
+			quality,
+			lossless,
+			effort,
+			speed,
+		);
+	}
+
+	/**
+	 * Options for the node-qrcode compatible renderer used without a logo.
+	 */
+	private codeSvgOptions() {
+		return {
+			text: this._text,
+			width: this._size,
+			errorCorrection: this._errorCorrection,
+			darkColor: this._foregroundColor,
+			lightColor: this._backgroundColor,
+			version: this._version,
+			minVersion: this._minVersion,
+			maxVersion: this._maxVersion,
+			maskPattern: this._maskPattern,
+			kanji: this._kanji,
+			eci: this._eci,
+			data: this._data,
+			symbology: this._symbology,
+			gs1: this._gs1,
+			boostEcl: this._boostEcl,
+			segments: this._segments,
+		};
+	}
+
+	/**
+	 * Options for the logo-capable renderer, without the logo itself.
+	 */
+	private logoOptions() {
+		return {
+			text: this._text,
+			size: this._size,
+			margin: this._margin,
+			logoSizeRatio: this._logoSizeRatio,
+			logoBackgroundColor: this._logoBackgroundColor,
+			logoPaddingRatio: this._logoPaddingRatio,
+			backgroundColor: this._backgroundColor,
+			foregroundColor: this._foregroundColor,
+			errorCorrection: this._errorCorrection,
+			version: this._version,
+			minVersion: this._minVersion,
+			maxVersion: this._maxVersion,
+			maskPattern: this._maskPattern,
+			kanji: this._kanji,
+			eci: this._eci,
+			data: this._data,
+			symbology: this._symbology,
+			gs1: this._gs1,
+			boostEcl: this._boostEcl,
+			segments: this._segments,
+		};
+	}
+
+	/**
+	 * Emit an error event when the logo is a file path that does not exist.
+	 */
+	private async checkLogoFile(): Promise<void> {
+		if (this._logo && this.isLogoString()) {
+			if (!(await this.logoFileExists(this._logo as string))) {
+				this.emit(
+					QrBitEvents.error,
+					logoFileDoesNotExistMessage(this._logo as string),
+				);
+			}
 		}
 	}
 
 	/**
 	 * Generate PNG QR code with optional caching.
-	 * Generates the QR as Svg either in rust if it has a logo or native. Then does a conversion on it.
+	 * Paints the modules and logo straight into the image, without an SVG round-trip.
 	 * @param options - Generation options
 	 * @param options.cache - Whether to use caching (default: true)
 	 * @returns {Promise<Buffer>} The PNG buffer
@@ -515,8 +1135,7 @@ export class QrBit extends Hookified {
 			}
 		}
 
-		const svg = await this.toSvg(options);
-		result = QrBit.convertSvgToPng(svg);
+		result = await this.toImage("png");
 
 		if (cache && cacheKey) {
 			await cache.set(cacheKey, result);
@@ -545,7 +1164,7 @@ export class QrBit extends Hookified {
 
 	/**
 	 * Generate JPEG QR code with optional caching.
-	 * Generates the QR as SVG either in rust if it has a logo or native. Then does a conversion on it.
+	 * Paints the modules and logo straight into the image, without an SVG round-trip.
 	 * @param options - Generation options
 	 * @param options.cache - Whether to use caching (default: true)
 	 * @param options.quality - JPEG quality 1-100 (default: 90)
@@ -568,8 +1187,7 @@ export class QrBit extends Hookified {
 			}
 		}
 
-		const svg = await this.toSvg(options);
-		result = QrBit.convertSvgToJpeg(svg, undefined, undefined, quality);
+		result = await this.toImage("jpeg", quality);
 
 		if (cache && cacheKey) {
 			await cache.set(cacheKey, result);
@@ -599,17 +1217,24 @@ export class QrBit extends Hookified {
 
 	/**
 	 * Generate WebP QR code with optional caching.
-	 * Generates the QR as SVG either in rust if it has a logo or native. Then does a conversion on it.
-	 * Note: WebP encoding uses lossless compression - quality parameter is reserved for future lossy support.
+	 * Paints the modules and logo straight into the image, without an SVG round-trip.
+	 * Lossless unless a quality is given, in which case the colors are encoded
+	 * lossy and the alpha channel stays lossless.
 	 * @param options - Generation options
 	 * @param options.cache - Whether to use caching (default: true)
-	 * @param options.quality - Reserved for future lossy WebP support (currently ignored)
+	 * @param options.quality - Lossy WebP quality 0-100 (default: lossless)
+	 * @param options.lossless - Encode losslessly even when a quality is given
+	 * @param options.effort - Compression effort 0 (fastest) to 6 (smallest, default: 4)
 	 * @returns {Promise<Buffer>} The WebP buffer
 	 */
 	public async toWebp(options?: toOptions): Promise<Buffer> {
 		let result: Buffer;
-		const quality = options?.quality ?? 90;
-		const renderKey = `napi-webp-${quality}`;
+		const quality = options?.quality;
+		const lossless = options?.lossless ?? quality === undefined;
+		const effort = options?.effort ?? 4;
+		const renderKey = lossless
+			? `napi-webp-lossless-${effort}`
+			: `napi-webp-${quality ?? 80}-${effort}`;
 
 		// check the cache
 		const cache = options?.cache !== false ? this._cache : undefined;
@@ -623,8 +1248,7 @@ export class QrBit extends Hookified {
 			}
 		}
 
-		const svg = await this.toSvg(options);
-		result = QrBit.convertSvgToWebp(svg);
+		result = await this.toImage("webp", quality, lossless, effort);
 
 		if (cache && cacheKey) {
 			await cache.set(cacheKey, result);
@@ -639,7 +1263,9 @@ export class QrBit extends Hookified {
 	 * @param filePath - The file path where to save the WebP
 	 * @param options - Generation options
 	 * @param options.cache - Whether to use caching (default: true)
-	 * @param options.quality - Reserved for future lossy WebP support (currently ignored)
+	 * @param options.quality - Lossy WebP quality 0-100 (default: lossless)
+	 * @param options.lossless - Encode losslessly even when a quality is given
+	 * @param options.effort - Compression effort 0 (fastest) to 6 (smallest, default: 4)
 	 * @returns {Promise<void>} Resolves when file is written
 	 */
 	public async toWebpFile(
@@ -655,6 +1281,146 @@ export class QrBit extends Hookified {
 		await fs.promises.writeFile(filePath, webpBuffer);
 	}
 
+	/**
+	 * Generate AVIF QR code with optional caching.
+	 * Paints the modules and logo straight into the image, without an SVG round-trip,
+	 * and keeps the alpha channel.
+	 * @param options - Generation options
+	 * @param options.cache - Whether to use caching (default: true)
+	 * @param options.quality - AVIF quality 1-100 for color and alpha (default: 80)
+	 * @param options.speed - Encoder speed 1 (slowest, smallest) to 10 (fastest, default: 4)
+	 * @returns {Promise<Buffer>} The AVIF buffer
+	 */
+	public async toAvif(options?: toOptions): Promise<Buffer> {
+		let result: Buffer;
+		const quality = options?.quality ?? 80;
+		const speed = options?.speed ?? 4;
+		const renderKey = `napi-avif-${quality}-${speed}`;
+
+		// check the cache
+		const cache = options?.cache !== false ? this._cache : undefined;
+		let cacheKey: string | undefined;
+		if (cache) {
+			cacheKey = await this.generateCacheKey(renderKey);
+			const cached = await cache.get<Buffer>(cacheKey);
+			if (cached) {
+				// Ensure we return a Buffer, not Uint8Array
+				return Buffer.from(cached);
+			}
+		}
+
+		result = await this.toImage("avif", quality, undefined, undefined, speed);
+
+		if (cache && cacheKey) {
+			await cache.set(cacheKey, result);
+		}
+
+		return result;
+	}
+
+	/**
+	 * Generate AVIF QR code and save it to a file.
+	 * Creates directories if they don't exist.
+	 * @param filePath - The file path where to save the AVIF
+	 * @param options - Generation options
+	 * @param options.cache - Whether to use caching (default: true)
+	 * @param options.quality - AVIF quality 1-100 for color and alpha (default: 80)
+	 * @param options.speed - Encoder speed 1 (slowest, smallest) to 10 (fastest, default: 4)
+	 * @returns {Promise<void>} Resolves when file is written
+	 */
+	public async toAvifFile(
+		filePath: string,
+		options?: toOptions,
+	): Promise<void> {
+		const avifBuffer = await this.toAvif(options);
+
+		// Create directory if it doesn't exist
+		const dir = path.dirname(filePath);
+		await fs.promises.mkdir(dir, { recursive: true });
+
+		await fs.promises.writeFile(filePath, avifBuffer);
+	}
+
+	/**
+	 * Generate a vector PDF of the QR code for print, with optional caching.
+	 * The modules are filled rectangles and the logo is an embedded image, on
+	 * one page the size of the SVG, quiet zone included, with the same
+	 * colors, margin and logo patch as `toSvg()`. A logo file that can't be
+	 * read rejects with `ERR_INVALID_OPTION` instead of being linked by path.
+	 * @param options - Generation options
+	 * @param options.cache - Whether to use caching (default: true)
+	 * @param options.width - Physical page width in `unit` (default: the SVG size at 0.75pt per pixel)
+	 * @param options.unit - "mm" (default), "cm", "in", "pt" or "px"
+	 * @returns {Promise<Buffer>} The PDF buffer
+	 */
+	public async toPdf(options?: PdfOptions): Promise<Buffer> {
+		let result: Buffer;
+		const unit = options?.unit ?? "mm";
+		const renderKey = `napi-pdf-${options?.width ?? "auto"}-${unit}`;
+
+		// check the cache
+		const cache = options?.cache !== false ? this._cache : undefined;
+		let cacheKey: string | undefined;
+		if (cache) {
+			cacheKey = await this.generateCacheKey(renderKey);
+			const cached = await cache.get<Buffer>(cacheKey);
+			if (cached) {
+				// Ensure we return a Buffer, not Uint8Array
+				return Buffer.from(cached);
+			}
+		}
+
+		if (!this._logo) {
+			result = await this._napi.generateQrCodePdfAsync(
+				this.codeSvgOptions(),
+				options?.width,
+				unit,
+			);
+		} else if (Buffer.isBuffer(this._logo)) {
+			result = await this._napi.generateQrPdfWithBufferAsync(
+				{ ...this.logoOptions(), logoBuffer: this._logo },
+				options?.width,
+				unit,
+			);
+		} else {
+			await this.checkLogoFile();
+			result = await this._napi.generateQrPdfAsync(
+				{ ...this.logoOptions(), logoPath: this._logo as string },
+				options?.width,
+				unit,
+			);
+		}
+
+		if (cache && cacheKey) {
+			await cache.set(cacheKey, result);
+		}
+
+		return result;
+	}
+
+	/**
+	 * Generate a vector PDF of the QR code and save it to a file.
+	 * Creates directories if they don't exist.
+	 * @param filePath - The file path where to save the PDF
+	 * @param options - Generation options
+	 * @param options.cache - Whether to use caching (default: true)
+	 * @param options.width - Physical page width in `unit` (default: the SVG size at 0.75pt per pixel)
+	 * @param options.unit - "mm" (default), "cm", "in", "pt" or "px"
+	 * @returns {Promise<void>} Resolves when file is written
+	 */
+	public async toPdfFile(
+		filePath: string,
+		options?: PdfOptions,
+	): Promise<void> {
+		const pdfBuffer = await this.toPdf(options);
+
+		// Create directory if it doesn't exist
+		const dir = path.dirname(filePath);
+		await fs.promises.mkdir(dir, { recursive: true });
+
+		await fs.promises.writeFile(filePath, pdfBuffer);
+	}
+
 	/**
 	 * Generate SVG QR code and save it to a file.
 	 * Creates directories if they don't exist.
@@ -673,6 +1439,155 @@ export class QrBit extends Hookified {
 		await fs.promises.writeFile(filePath, svgString, "utf8");
 	}
 
+	/**
+	 * Generate a Structured Append sequence: the text is split across up to 16
+	 * linked QR codes that a compatible scanner joins back together. Every
+	 * symbol shares the same version, so use `maxVersion` to cap their size.
+	 * Text that fits in one QR code returns a single plain symbol. The logo is
+	 * not drawn on the parts.
+	 * @returns {Promise<string[]>} One SVG string per symbol, in sequence order
+	 */
+	public async toSvgParts(): Promise<string[]> {
+		return this._napi.generateQrCodeSvgParts(this.structuredAppendOptions());
+	}
+
+	/**
+	 * Generate a Structured Append sequence as PNG images. See `toSvgParts`.
+	 * @returns {Promise<Buffer[]>} One PNG buffer per symbol, in sequence order
+	 */
+	public async toPngParts(): Promise<Buffer[]> {
+		const parts = await this.toSvgParts();
+		return Promise.all(parts.map((svg) => QrBit.convertSvgToPngAsync(svg)));
+	}
+
+	/**
+	 * Generate a Structured Append sequence laid out on a single SVG sheet,
+	 * in sequence order from left to right and top to bottom. Each symbol is
+	 * `size` pixels wide. See `toSvgParts`.
+	 * @param options - Sheet options
+	 * @param options.columns - Symbols per row (default: a near-square grid)
+	 * @returns {Promise<string>} The SVG string
+	 */
+	public async toSvgSheet(options?: SheetOptions): Promise<string> {
+		return this._napi.generateQrCodeSvgSheet(
+			this.structuredAppendOptions(),
+			options?.columns,
+		);
+	}
+
+	/**
+	 * Generate a Structured Append sequence laid out on a single PNG sheet.
+	 * See `toSvgSheet`.
+	 * @param options - Sheet options
+	 * @param options.columns - Symbols per row (default: a near-square grid)
+	 * @returns {Promise<Buffer>} The PNG buffer
+	 */
+	public async toPngSheet(options?: SheetOptions): Promise<Buffer> {
+		const svg = await this.toSvgSheet(options);
+		return QrBit.convertSvgToPngAsync(svg);
+	}
+
+	/**
+	 * Generate a Structured Append sequence as an animated GIF or APNG that
+	 * cycles through the symbols, for screens that show one at a time.
+	 * See `toSvgParts`.
+	 * @param options - Animation options
+	 * @param options.format - "gif" (default) or "apng"
+	 * @param options.delay - Milliseconds each symbol is shown (default: 1000)
+	 * @param options.loop - How many times the animation plays; 0 loops forever (default: 0)
+	 * @returns {Promise<Buffer>} The animation buffer
+	 */
+	public async toAnimatedParts(options?: AnimationOptions): Promise<Buffer> {
+		const parts = await this.toSvgParts();
+		return QrBit.encodeAnimation(parts.map((svg) => ({ svg })), options);
+	}
+
+	/**
+	 * The error correction level the encoder uses for the current text and
+	 * options: `errorCorrection`, or the higher level `boostEcl` raised it to.
+	 * @returns {"L" | "M" | "Q" | "H"} The level actually used
+	 */
+	public getErrorCorrectionLevel(): "L" | "M" | "Q" | "H" {
+		return this._napi.getErrorCorrectionLevel({
+			text: this._text,
+			errorCorrection: this._errorCorrection,
+			version: this._version,
+			minVersion: this._minVersion,
+			maxVersion: this._maxVersion,
+			kanji: this._kanji,
+			eci: this._eci,
+			data: this._data,
+			symbology: this._symbology,
+			gs1: this._gs1,
+			boostEcl: this._boostEcl,
+			segments: this._segments,
+		}) as "L" | "M" | "Q" | "H";
+	}
+
+	/**
+	 * Score all eight mask patterns for the current text and options. Lower
+	 * penalties scan more reliably; pick one and set `maskPattern` to force it,
+	 * for example when another pattern looks better under a logo.
+	 * @returns {MaskPenalties} The penalty of each pattern and the automatic choice
+	 */
+	public getMaskPenalties(): MaskPenalties {
+		return this._napi.getMaskPenalties({
+			text: this._text,
+			errorCorrection: this._errorCorrection,
+			version: this._version,
+			minVersion: this._minVersion,
+			maxVersion: this._maxVersion,
+			kanji: this._kanji,
+			eci: this._eci,
+			data: this._data,
+			symbology: this._symbology,
+			gs1: this._gs1,
+			boostEcl: this._boostEcl,
+			segments: this._segments,
+		});
+	}
+
+	/**
+	 * Plan the QR code for the current text and options without rendering
+	 * it: the version, mask, segments with their bit cost, the bits used and
+	 * left, and the capacity of the version at every error correction level.
+	 * @returns {Inspection} The encoding plan
+	 */
+	public inspect(): Inspection {
+		return this._napi.inspect(
+			this._text,
+			this.encodeOptions(),
+		) as Inspection;
+	}
+
+	/**
+	 * Encode the current text and options and return the module grid without
+	 * rendering it, for drawing the modules yourself. No quiet zone is included.
+	 * @returns {QrMatrix} The modules as a packed buffer and as rows, the role and block of each module, and the version, level and mask
+	 */
+	public toMatrix(): QrMatrix {
+		return this._napi.generateQrMatrix(
+			this._text,
+			this.encodeOptions(),
+		) as QrMatrix;
+	}
+
+	/**
+	 * The module grid as JSON: the fields of `toMatrix()` without the packed buffer.
+	 * @returns {string} The JSON string
+	 */
+	public toMatrixJson(): string {
+		return this._napi.generateQrMatrixJson(this._text, this.encodeOptions());
+	}
+
+	/**
+	 * The module grid as text: one line of `0` (light) and `1` (dark) per row.
+	 * @returns {string} The rows
+	 */
+	public toMatrixText(): string {
+		return this._napi.generateQrMatrixText(this._text, this.encodeOptions());
+	}
+
 	/**
 	 * Convert SVG content to PNG buffer using the native Rust implementation.
 	 * @param svgContent - The SVG content as a string
@@ -707,11 +1622,14 @@ export class QrBit extends Hookified {
 
 	/**
 	 * Convert SVG content to WebP buffer using the native Rust implementation.
-	 * Note: WebP encoding uses lossless compression - quality parameter is reserved for future lossy support.
+	 * Lossless unless a quality is given, in which case the colors are encoded
+	 * lossy and the alpha channel stays lossless.
 	 * @param svgContent - The SVG content as a string
 	 * @param width - Optional width for the WebP output
 	 * @param height - Optional height for the WebP output
-	 * @param quality - Reserved for future lossy WebP support (currently ignored)
+	 * @param quality - Optional lossy WebP quality 0-100
+	 * @param lossless - Encode losslessly even when a quality is given
+	 * @param effort - Compression effort 0 (fastest) to 6 (smallest, default: 4)
 	 * @returns {Buffer} The WebP buffer
 	 */
 	public static convertSvgToWebp(
@@ -719,8 +1637,195 @@ export class QrBit extends Hookified {
 		width?: number,
 		height?: number,
 		quality?: number,
+		lossless?: boolean,
+		effort?: number,
+	): Buffer {
+		return nativeConvertSvgToWebp(
+			svgContent,
+			width,
+			height,
+			quality,
+			lossless,
+			effort,
+		);
+	}
+
+	/**
+	 * Convert SVG content to AVIF buffer using the native Rust implementation,
+	 * keeping the alpha channel of the transparent render.
+	 * @param svgContent - The SVG content as a string
+	 * @param width - Optional width for the AVIF output
+	 * @param height - Optional height for the AVIF output
+	 * @param quality - Optional AVIF quality 1-100 for color and alpha (default: 80)
+	 * @param speed - Encoder speed 1 (slowest, smallest) to 10 (fastest, default: 4)
+	 * @returns {Buffer} The AVIF buffer
+	 */
+	public static convertSvgToAvif(
+		svgContent: string,
+		width?: number,
+		height?: number,
+		quality?: number,
+		speed?: number,
 	): Buffer {
-		return nativeConvertSvgToWebp(svgContent, width, height, quality);
+		return nativeConvertSvgToAvif(svgContent, width, height, quality, speed);
+	}
+
+	/**
+	 * Convert SVG content to PNG buffer on the libuv threadpool, leaving the
+	 * event loop free while resvg rasterizes.
+	 * @param svgContent - The SVG content as a string
+	 * @param width - Optional width for the PNG output
+	 * @param height - Optional height for the PNG output
+	 * @returns {Promise<Buffer>} The PNG buffer
+	 */
+	public static async convertSvgToPngAsync(
+		svgContent: string,
+		width?: number,
+		height?: number,
+	): Promise<Buffer> {
+		return nativeConvertSvgToPngAsync(svgContent, width, height);
+	}
+
+	/**
+	 * Convert SVG content to JPEG buffer on the libuv threadpool.
+	 * @param svgContent - The SVG content as a string
+	 * @param width - Optional width for the JPEG output
+	 * @param height - Optional height for the JPEG output
+	 * @param quality - Optional JPEG quality 1-100 (default: 90)
+	 * @returns {Promise<Buffer>} The JPEG buffer
+	 */
+	public static async convertSvgToJpegAsync(
+		svgContent: string,
+		width?: number,
+		height?: number,
+		quality?: number,
+	): Promise<Buffer> {
+		return nativeConvertSvgToJpegAsync(svgContent, width, height, quality);
+	}
+
+	/**
+	 * Convert SVG content to WebP buffer on the libuv threadpool.
+	 * @param svgContent - The SVG content as a string
+	 * @param width - Optional width for the WebP output
+	 * @param height - Optional height for the WebP output
+	 * @param quality - Optional lossy WebP quality 0-100
+	 * @param lossless - Encode losslessly even when a quality is given
+	 * @param effort - Compression effort 0 (fastest) to 6 (smallest, default: 4)
+	 * @returns {Promise<Buffer>} The WebP buffer
+	 */
+	public static async convertSvgToWebpAsync(
+		svgContent: string,
+		width?: number,
+		height?: number,
+		quality?: number,
+		lossless?: boolean,
+		effort?: number,
+	): Promise<Buffer> {
+		return nativeConvertSvgToWebpAsync(
+			svgContent,
+			width,
+			height,
+			quality,
+			lossless,
+			effort,
+		);
+	}
+
+	/**
+	 * Convert SVG content to AVIF buffer on the libuv threadpool.
+	 * @param svgContent - The SVG content as a string
+	 * @param width - Optional width for the AVIF output
+	 * @param height - Optional height for the AVIF output
+	 * @param quality - Optional AVIF quality 1-100 for color and alpha (default: 80)
+	 * @param speed - Encoder speed 1 (slowest, smallest) to 10 (fastest, default: 4)
+	 * @returns {Promise<Buffer>} The AVIF buffer
+	 */
+	public static async convertSvgToAvifAsync(
+		svgContent: string,
+		width?: number,
+		height?: number,
+		quality?: number,
+		speed?: number,
+	): Promise<Buffer> {
+		return nativeConvertSvgToAvifAsync(
+			svgContent,
+			width,
+			height,
+			quality,
+			speed,
+		);
+	}
+
+	/**
+	 * Render SVG frames and encode them as an animated GIF or APNG, for
+	 * digital signage: cycling Structured Append parts, or a reveal that ends
+	 * on the final code. Every frame is rendered at the size of the first and
+	 * replaces the previous one. Runs on the libuv threadpool.
+	 * @param frames - The SVG of each frame, with an optional delay in milliseconds
+	 * @param options - Animation options
+	 * @param options.format - "gif" (default) or "apng"
+	 * @param options.width - Frame width (default: the first frame at 2x)
+	 * @param options.height - Frame height (default: the first frame at 2x)
+	 * @param options.delay - Milliseconds for frames without their own delay (default: 1000)
+	 * @param options.loop - How many times the animation plays; 0 loops forever (default: 0)
+	 * @returns {Promise<Buffer>} The animation buffer
+	 */
+	public static async encodeAnimation(
+		frames: AnimationFrame[],
+		options: AnimationOptions = {},
+	): Promise<Buffer> {
+		const delay = options.delay ?? 1000;
+		return nativeEncodeAnimationAsync(
+			frames.map((frame) => ({ svg: frame.svg, delay: frame.delay ?? delay })),
+			options.format ?? "gif",
+			options.width,
+			options.height,
+			options.loop,
+		);
+	}
+
+	/**
+	 * Generate many QR codes with one shared style in a single native call.
+	 * The items are encoded and rendered in parallel on a Rust thread pool and
+	 * the results come back in the same order. An item that fails gets `error`
+	 * and `code` instead of `output`, so one bad item doesn't fail the batch;
+	 * only an invalid style, logo or format rejects. Each code matches what
+	 * `toSvg()`, `toPng()`, `toJpg()` or `toWebp()` produce for the same options.
+	 * @param items - The payload of each code
+	 * @param sharedStyle - Style applied to every code, with the constructor's defaults
+	 * @param format - Output format (default: "svg")
+	 * @returns {Promise<BatchResult[]>} One result per item
+	 */
+	public static async generateBatch(
+		items: BatchItem[],
+		sharedStyle: BatchStyle = {},
+		format: BatchFormat = "svg",
+	): Promise<BatchResult[]> {
+		const { logo, logoBackgroundColor, ...style } = sharedStyle;
+		const backgroundColor = style.backgroundColor ?? "#FFFFFF";
+		const results = await nativeGenerateBatch(
+			items.map((item) => ({
+				...item,
+				data: item.data ? Buffer.from(item.data) : undefined,
+			})),
+			{
+				...style,
+				size: style.size ?? 200,
+				backgroundColor,
+				foregroundColor: style.foregroundColor ?? "#000000",
+				logoPath: typeof logo === "string" ? logo : undefined,
+				logoBuffer: Buffer.isBuffer(logo) ? logo : undefined,
+				logoBackgroundColor:
+					logoBackgroundColor === false
+						? undefined
+						: (logoBackgroundColor ?? backgroundColor),
+				errorCorrection:
+					style.errorCorrection ??
+					(style.symbology === "microqr" ? "M" : "H"),
+			},
+			format,
+		);
+		return results as BatchResult[];
 	}
 
 	/**
@@ -732,7 +1837,7 @@ export class QrBit extends Hookified {
 		input: Buffer | Uint8Array | string,
 	): Promise<string | null> {
 		const buffer = await QrBit.resolveInput(input);
-		return nativeDecode(buffer);
+		return nativeDecodeAsync(buffer);
 	}
 
 	/**
@@ -744,7 +1849,7 @@ export class QrBit extends Hookified {
 		input: Buffer | Uint8Array | string,
 	): Promise<DecodeResult> {
 		const buffer = await QrBit.resolveInput(input);
-		return nativeDecodeDetailed(buffer) as DecodeResult;
+		return (await nativeDecodeDetailedAsync(buffer)) as DecodeResult;
 	}
 
 	/**
@@ -760,7 +1865,7 @@ export class QrBit extends Hookified {
 		options?: ValidateOptions,
 	): Promise<DecodeResult> {
 		const buffer = await QrBit.resolveInput(input);
-		const result = nativeValidateQr(buffer) as DecodeResult;
+		const result = (await nativeValidateQrAsync(buffer)) as DecodeResult;
 
 		if (result.valid && result.data != null && options?.content) {
 			try {
@@ -793,7 +1898,7 @@ export class QrBit extends Hookified {
 			);
 		}
 
-		if (result.data !== this._text) {
+		if (result.data !== this.payloadText()) {
 			throw new Error("Generated QR code content does not match input text");
 		}
 
@@ -809,7 +1914,7 @@ export class QrBit extends Hookified {
 	 */
 	public async safeGenerateSvg(options?: toOptions): Promise<string> {
 		const svg = await this.toSvg(options);
-		const png = QrBit.convertSvgToPng(svg);
+		const png = await QrBit.convertSvgToPngAsync(svg);
 		const result = await QrBit.decodeDetailed(png);
 		if (!result.valid) {
 			throw new Error(
@@ -817,13 +1922,69 @@ export class QrBit extends Hookified {
 			);
 		}
 
-		if (result.data !== this._text) {
+		if (result.data !== this.payloadText()) {
 			throw new Error("Generated QR code content does not match input text");
 		}
 
 		return svg;
 	}
 
+	/**
+	 * Native encoding options for the functions that take the text separately.
+	 * @returns The options for `inspect` and the `generateQrMatrix` functions
+	 */
+	private encodeOptions() {
+		return {
+			errorCorrection: this._errorCorrection,
+			version: this._version,
+			minVersion: this._minVersion,
+			maxVersion: this._maxVersion,
+			maskPattern: this._maskPattern,
+			kanji: this._kanji,
+			eci: this._eci,
+			data: this._data,
+			symbology: this._symbology,
+			gs1: this._gs1,
+			boostEcl: this._boostEcl,
+			segments: this._segments,
+		};
+	}
+
+	/**
+	 * Native options shared by the Structured Append generators.
+	 * @returns The options for `generateQrCodeSvgParts` and `generateQrCodeSvgSheet`
+	 */
+	private structuredAppendOptions() {
+		return {
+			text: this._text,
+			width: this._size,
+			margin: this._margin,
+			errorCorrection: this._errorCorrection,
+			darkColor: this._foregroundColor,
+			lightColor: this._backgroundColor,
+			version: this._version,
+			minVersion: this._minVersion,
+			maxVersion: this._maxVersion,
+			maskPattern: this._maskPattern,
+			kanji: this._kanji,
+			eci: this._eci,
+			data: this._data,
+			symbology: this._symbology,
+			gs1: this._gs1,
+			boostEcl: this._boostEcl,
+			segments: this._segments,
+		};
+	}
+
+	/**
+	 * The text a scanner should read back: the raw data decoded as UTF-8 when
+	 * set, otherwise the text.
+	 * @returns {string} The expected decoded text
+	 */
+	private payloadText(): string {
+		return this._data ? this._data.toString("utf8") : this._text;
+	}
+
 	/**
 	 * Resolve input to a Buffer for decode/validate operations.
 	 * @param input - Buffer, Uint8Array, or file path string
@@ -894,6 +2055,17 @@ export class QrBit extends Hookified {
 			backgroundColor: this._backgroundColor,
 			foregroundColor: this._foregroundColor,
 			errorCorrection: this._errorCorrection,
+			version: this._version,
+			minVersion: this._minVersion,
+			maxVersion: this._maxVersion,
+			maskPattern: this._maskPattern,
+			kanji: this._kanji,
+			eci: this._eci,
+			data: this._data,
+			symbology: this._symbology,
+			gs1: this._gs1,
+			boostEcl: this._boostEcl,
+			segments: this._segments,
 			renderKey,
 		};
 
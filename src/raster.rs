@@ -0,0 +1,327 @@
+//! Paints a `BitMatrix` straight into a raster image, skipping the SVG string,
+//! `usvg` parsing and `resvg` rendering. Every pixel takes the color of the
+//! module under its center, so module edges are pixel-exact with no
+//! antialiasing. A symbol without a logo in shades of gray is painted into a
+//! one-channel `GrayImage`; anything else goes into a `tiny_skia::Pixmap`,
+//! with the logo composited on top.
+
+use std::io::Cursor;
+
+use image::{ExtendedColorType, GrayImage, ImageEncoder};
+use tiny_skia::{ColorU8, FilterQuality, Paint, Pixmap, PixmapPaint, Rect, Transform};
+
+use crate::error::QrError;
+use crate::nodeqr::BitMatrix;
+
+/// An image format the symbol can be rasterized to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RasterFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+impl RasterFormat {
+    pub fn parse(format: &str) -> Result<Self, QrError> {
+        match format.to_lowercase().as_str() {
+            "png" => Ok(RasterFormat::Png),
+            "jpeg" | "jpg" => Ok(RasterFormat::Jpeg),
+            "webp" => Ok(RasterFormat::Webp),
+            "avif" => Ok(RasterFormat::Avif),
+            other => Err(QrError::InvalidOption(format!(
+                "Unsupported image format '{}': expected png, jpeg, webp or avif",
+                other
+            ))),
+        }
+    }
+
+    /// The color under the symbol: transparent where the format keeps alpha,
+    /// white for JPEG.
+    fn base(self) -> [u8; 4] {
+        match self {
+            RasterFormat::Png | RasterFormat::Webp | RasterFormat::Avif => [0, 0, 0, 0],
+            RasterFormat::Jpeg => [255, 255, 255, 255],
+        }
+    }
+}
+
+/// How to encode the painted image. `quality` is the JPEG quality, 1-100
+/// (default: 90), or the lossy WebP quality, 0-100. WebP is lossy when a
+/// `quality` is given and lossless otherwise, unless `lossless` says which;
+/// `effort` is the WebP compression method, 0 (fastest) to 6 (smallest,
+/// default: 4). For AVIF, `quality` is 1-100 (default: 80) for both color
+/// and alpha, and `speed` runs from 1 (slowest, smallest) to 10 (default: 4).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Encoding {
+    pub quality: Option<u8>,
+    pub lossless: Option<bool>,
+    pub effort: Option<u8>,
+    pub speed: Option<u8>,
+}
+
+/// A decoded logo, kept premultiplied so it can be drawn into many symbols.
+pub struct LogoImage(Pixmap);
+
+impl LogoImage {
+    /// Decode raster logo bytes, or `None` when `image` can't read them, in
+    /// which case the caller falls back to rendering the SVG with resvg.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let rgba = image::load_from_memory(bytes).ok()?.to_rgba8();
+        let mut pixmap = Pixmap::new(rgba.width(), rgba.height())?;
+        for (pixel, rgba) in pixmap.pixels_mut().iter_mut().zip(rgba.pixels()) {
+            let [r, g, b, a] = rgba.0;
+            *pixel = ColorU8::from_rgba(r, g, b, a).premultiply();
+        }
+        Some(LogoImage(pixmap))
+    }
+}
+
+/// Where a logo goes, in output pixels: a square box of `size` at `x`, `y`,
+/// which the image fits into keeping its aspect ratio, and an optional
+/// knockout patch behind it.
+pub struct LogoPlacement<'a> {
+    pub image: &'a LogoImage,
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub patch: Option<Patch>,
+}
+
+pub struct Patch {
+    pub color: [u8; 4],
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+}
+
+/// A symbol laid out in output pixels: a `width` x `height` raster filled
+/// with `light`, whose module grid starts at `x`, `y`.
+pub struct Scene<'a> {
+    pub matrix: &'a BitMatrix,
+    pub width: u32,
+    pub height: u32,
+    pub x: f64,
+    pub y: f64,
+    pub module_width: f64,
+    pub module_height: f64,
+    /// Straight (not premultiplied) RGBA colors.
+    pub dark: [u8; 4],
+    pub light: [u8; 4],
+    pub logo: Option<LogoPlacement<'a>>,
+}
+
+/// Paint the scene and encode it as `format`.
+pub fn render(scene: &Scene, format: RasterFormat, encoding: Encoding) -> Result<Vec<u8>, QrError> {
+    let light = over(scene.light, format.base());
+    let dark = over(scene.dark, light);
+    let gray = |[r, g, b, a]: [u8; 4]| a == 255 && r == g && g == b;
+
+    if scene.logo.is_none() && gray(light) && gray(dark) {
+        let mut image = GrayImage::new(scene.width, scene.height);
+        paint(scene, image.as_mut(), [dark[0]], [light[0]]);
+        return encode_gray(&image, format, encoding);
+    }
+
+    let mut pixmap = Pixmap::new(scene.width, scene.height)
+        .ok_or_else(|| QrError::InvalidOption("Failed to create pixmap".to_string()))?;
+    let premultiply = |[r, g, b, a]: [u8; 4]| {
+        let color = ColorU8::from_rgba(r, g, b, a).premultiply();
+        [color.red(), color.green(), color.blue(), color.alpha()]
+    };
+    paint(scene, pixmap.data_mut(), premultiply(dark), premultiply(light));
+    if let Some(logo) = &scene.logo {
+        draw_logo(&mut pixmap, logo);
+    }
+    encode_pixmap(&pixmap, format, encoding)
+}
+
+/// Fill `pixels`, rows of `N`-byte pixels, with the module under each pixel
+/// center. Pixel rows over the same module row are copied from the first.
+fn paint<const N: usize>(scene: &Scene, pixels: &mut [u8], dark: [u8; N], light: [u8; N]) {
+    let matrix = scene.matrix;
+    let cols = module_indices(scene.width, scene.x, scene.module_width, matrix.width);
+    let rows = module_indices(scene.height, scene.y, scene.module_height, matrix.height);
+    let stride = scene.width as usize * N;
+
+    let mut previous: Option<(usize, Option<usize>)> = None;
+    for (y, &row) in rows.iter().enumerate() {
+        let start = y * stride;
+        if let Some((source, _)) = previous.filter(|&(_, previous_row)| previous_row == row) {
+            pixels.copy_within(source..source + stride, start);
+            continue;
+        }
+        for (pixel, &col) in pixels[start..start + stride].chunks_exact_mut(N).zip(&cols) {
+            let is_dark = matches!((row, col), (Some(row), Some(col)) if matrix.get(row, col) != 0);
+            pixel.copy_from_slice(if is_dark { &dark } else { &light });
+        }
+        previous = Some((start, row));
+    }
+}
+
+/// The module index under the center of each of `pixels` pixels, for a grid
+/// of `count` modules of `module` pixels starting at `origin`.
+fn module_indices(pixels: u32, origin: f64, module: f64, count: usize) -> Vec<Option<usize>> {
+    (0..pixels)
+        .map(|pixel| {
+            let index = ((pixel as f64 + 0.5 - origin) / module).floor();
+            (index >= 0.0 && index < count as f64).then_some(index as usize)
+        })
+        .collect()
+}
+
+fn draw_logo(pixmap: &mut Pixmap, logo: &LogoPlacement) {
+    if let Some(patch) = &logo.patch {
+        if let Some(rect) = Rect::from_xywh(patch.x, patch.y, patch.size, patch.size) {
+            let [r, g, b, a] = patch.color;
+            let mut paint = Paint::default();
+            paint.set_color_rgba8(r, g, b, a);
+            paint.anti_alias = false;
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+    }
+
+    // Fit the image into the logo box and center it, like an SVG `<image>`
+    // with the default `preserveAspectRatio="xMidYMid meet"`.
+    let image = &logo.image.0;
+    let scale = (logo.size / image.width() as f32).min(logo.size / image.height() as f32);
+    let x = logo.x + (logo.size - image.width() as f32 * scale) / 2.0;
+    let y = logo.y + (logo.size - image.height() as f32 * scale) / 2.0;
+    let paint = PixmapPaint {
+        quality: FilterQuality::Bicubic,
+        ..Default::default()
+    };
+    let transform = Transform::from_row(scale, 0.0, 0.0, scale, x, y);
+    pixmap.draw_pixmap(0, 0, image.as_ref(), &paint, transform, None);
+}
+
+/// Composite the straight RGBA color `top` over `bottom`.
+fn over(top: [u8; 4], bottom: [u8; 4]) -> [u8; 4] {
+    let top_alpha = top[3] as f32 / 255.0;
+    let bottom_alpha = bottom[3] as f32 / 255.0 * (1.0 - top_alpha);
+    let alpha = top_alpha + bottom_alpha;
+    if alpha == 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |i: usize| {
+        ((top[i] as f32 * top_alpha + bottom[i] as f32 * bottom_alpha) / alpha).round() as u8
+    };
+    [channel(0), channel(1), channel(2), (alpha * 255.0).round() as u8]
+}
+
+/// Encode a rendered pixmap. JPEG drops the alpha channel; PNG, WebP and
+/// AVIF keep it.
+pub fn encode_pixmap(pixmap: &Pixmap, format: RasterFormat, encoding: Encoding) -> Result<Vec<u8>, QrError> {
+    let (width, height) = (pixmap.width(), pixmap.height());
+    match format {
+        RasterFormat::Png => pixmap
+            .encode_png()
+            .map_err(|e| QrError::Encode(format!("Failed to encode PNG: {}", e))),
+        RasterFormat::Jpeg => {
+            // Convert pixmap to RGB image (drop the alpha channel)
+            let rgb_image = image::RgbImage::from_raw(
+                width,
+                height,
+                pixmap.data()
+                    .chunks_exact(4)
+                    .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
+                    .collect::<Vec<u8>>()
+            ).ok_or_else(|| QrError::Encode("Failed to create RGB image".to_string()))?;
+            encode_jpeg(rgb_image.as_raw(), width, height, ExtendedColorType::Rgb8, encoding.quality)
+        }
+        RasterFormat::Webp => {
+            let rgba = demultiplied(pixmap);
+            encode_webp(webp::Encoder::from_rgba(&rgba, width, height), encoding)
+        }
+        RasterFormat::Avif => encode_avif(&demultiplied(pixmap), width, height, ExtendedColorType::Rgba8, encoding),
+    }
+}
+
+fn encode_gray(image: &GrayImage, format: RasterFormat, encoding: Encoding) -> Result<Vec<u8>, QrError> {
+    let (width, height) = image.dimensions();
+    match format {
+        RasterFormat::Png => {
+            let mut png_buffer = Vec::new();
+            image::codecs::png::PngEncoder::new(&mut png_buffer)
+                .write_image(image.as_raw(), width, height, ExtendedColorType::L8)
+                .map_err(|e| QrError::Encode(format!("Failed to encode PNG: {}", e)))?;
+            Ok(png_buffer)
+        }
+        RasterFormat::Jpeg => encode_jpeg(image.as_raw(), width, height, ExtendedColorType::L8, encoding.quality),
+        RasterFormat::Webp => {
+            // libwebp has no grayscale input
+            let rgb = image.as_raw().iter().flat_map(|&luma| [luma; 3]).collect::<Vec<u8>>();
+            encode_webp(webp::Encoder::from_rgb(&rgb, width, height), encoding)
+        }
+        RasterFormat::Avif => encode_avif(image.as_raw(), width, height, ExtendedColorType::L8, encoding),
+    }
+}
+
+/// The pixmap as straight RGBA, for encoders that keep the alpha channel.
+pub fn demultiplied(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+fn encode_jpeg(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    color_type: ExtendedColorType,
+    quality: Option<u8>,
+) -> Result<Vec<u8>, QrError> {
+    // Encode as JPEG with specified quality (default: 90)
+    let jpeg_quality = quality.unwrap_or(90).clamp(1, 100);
+    let mut jpeg_buffer = Vec::new();
+    let mut cursor = Cursor::new(&mut jpeg_buffer);
+
+    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, jpeg_quality);
+    encoder
+        .encode(data, width, height, color_type)
+        .map_err(|e| QrError::Encode(format!("Failed to encode JPEG: {}", e)))?;
+
+    Ok(jpeg_buffer)
+}
+
+fn encode_webp(encoder: webp::Encoder, encoding: Encoding) -> Result<Vec<u8>, QrError> {
+    let mut config = webp::WebPConfig::new()
+        .map_err(|_| QrError::Encode("Failed to initialize WebP encoder".to_string()))?;
+    config.lossless = encoding.lossless.unwrap_or(encoding.quality.is_none()) as i32;
+    if config.lossless == 0 {
+        // Lossy: VP8 color at the chosen quality (default: 80), with the
+        // alpha channel kept losslessly beside it
+        config.quality = encoding.quality.unwrap_or(80).min(100) as f32;
+        config.alpha_quality = 100;
+    }
+    config.method = encoding.effort.unwrap_or(4).min(6) as i32;
+
+    let webp_buffer = encoder
+        .encode_advanced(&config)
+        .map_err(|e| QrError::Encode(format!("Failed to encode WebP: {:?}", e)))?;
+
+    Ok(webp_buffer.to_vec())
+}
+
+fn encode_avif(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    color_type: ExtendedColorType,
+    encoding: Encoding,
+) -> Result<Vec<u8>, QrError> {
+    let speed = encoding.speed.unwrap_or(4).clamp(1, 10);
+    let quality = encoding.quality.unwrap_or(80).clamp(1, 100);
+    let mut avif_buffer = Vec::new();
+
+    image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut avif_buffer, speed, quality)
+        .write_image(data, width, height, color_type)
+        .map_err(|e| QrError::Encode(format!("Failed to encode AVIF: {}", e)))?;
+
+    Ok(avif_buffer)
+}
@@ -0,0 +1,428 @@
+//! Rectangular Micro QR Code (ISO/IEC 23941, R7x43 to R17x139) encoder.
+//!
+//! rMQR is not part of `node-qrcode`. It reuses the segment encoding, bit
+//! buffer and Reed-Solomon blocks of `nodeqr.rs` and returns a `BitMatrix`
+//! whose width and height differ. A symbol has one finder pattern on the left,
+//! a smaller sub-finder in the bottom right corner, timing patterns along all
+//! four edges and down the alignment columns, two copies of the format
+//! information, a single fixed mask and levels M and H only. It needs a
+//! 2-module quiet zone.
+//!
+//! The 32 sizes are numbered 1 to 32 in the standard's order (R7x43, R7x59,
+//! ..., R17x139). Without a fixed version the smallest symbol by area that
+//! holds the data is chosen. As in `microqr.rs` the data is stored as a single
+//! segment in the most compact mode.
+
+use std::ops::RangeInclusive;
+
+use crate::microqr::get_segment;
+use crate::error::QrError;
+use crate::nodeqr::{
+    check_input_present, codeword_roles, get_bch_digit, interleave_codewords, BitBuffer, BitMatrix, CreateOptions,
+    EcLevel, Mode, ModuleRole, QrData, Segment, Symbol,
+};
+
+/// Quiet zone an rMQR symbol needs on every side, in modules.
+pub const QUIET_ZONE: i64 = 2;
+
+const VERSION_COUNT: usize = 32;
+
+/// Height and width of every version.
+#[rustfmt::skip]
+const SIZES: [(usize, usize); VERSION_COUNT] = [
+    (7, 43), (7, 59), (7, 77), (7, 99), (7, 139),
+    (9, 43), (9, 59), (9, 77), (9, 99), (9, 139),
+    (11, 27), (11, 43), (11, 59), (11, 77), (11, 99), (11, 139),
+    (13, 27), (13, 43), (13, 59), (13, 77), (13, 99), (13, 139),
+    (15, 43), (15, 59), (15, 77), (15, 99), (15, 139),
+    (17, 43), (17, 59), (17, 77), (17, 99), (17, 139),
+];
+
+/// Total codewords of every version.
+#[rustfmt::skip]
+const TOTAL_CODEWORDS: [usize; VERSION_COUNT] = [
+    13, 21, 32, 44, 68,
+    21, 33, 49, 66, 99,
+    15, 31, 47, 67, 89, 132,
+    21, 41, 60, 85, 113, 166,
+    51, 74, 103, 136, 199,
+    61, 88, 122, 160, 232,
+];
+
+/// Error correction blocks and codewords per block at levels M and H.
+#[rustfmt::skip]
+const EC_BLOCKS: [[(usize, usize); 2]; VERSION_COUNT] = [
+    [(1, 7), (1, 10)], [(1, 9), (1, 14)], [(1, 12), (1, 22)], [(1, 16), (2, 15)], [(2, 12), (2, 23)],
+    [(1, 9), (1, 14)], [(1, 12), (1, 22)], [(2, 9), (2, 16)], [(2, 12), (2, 22)], [(3, 12), (3, 22)],
+    [(1, 8), (1, 10)], [(1, 12), (1, 20)], [(1, 16), (2, 16)], [(2, 12), (2, 22)], [(2, 16), (2, 30)],
+    [(3, 16), (3, 30)],
+    [(1, 9), (1, 14)], [(1, 14), (1, 28)], [(2, 11), (2, 20)], [(2, 16), (2, 28)], [(3, 14), (3, 24)],
+    [(4, 14), (4, 28)],
+    [(2, 10), (2, 16)], [(2, 14), (2, 24)], [(2, 18), (3, 23)], [(4, 12), (4, 22)], [(4, 18), (5, 26)],
+    [(2, 12), (2, 20)], [(2, 16), (2, 30)], [(2, 22), (3, 28)], [(4, 14), (4, 26)], [(4, 20), (6, 26)],
+];
+
+/// Character count indicator lengths for Numeric, Alphanumeric, Byte and
+/// Kanji mode.
+#[rustfmt::skip]
+const CC_BITS: [[usize; 4]; VERSION_COUNT] = [
+    [4, 3, 3, 2], [5, 5, 4, 3], [6, 5, 5, 4], [7, 6, 5, 5], [7, 6, 6, 5],
+    [5, 5, 4, 3], [6, 5, 5, 4], [7, 6, 5, 5], [7, 6, 6, 5], [8, 7, 6, 6],
+    [4, 4, 3, 2], [6, 5, 5, 4], [7, 6, 5, 5], [7, 6, 6, 5], [8, 7, 6, 6], [8, 7, 7, 6],
+    [5, 5, 4, 3], [6, 6, 5, 5], [7, 6, 6, 5], [7, 7, 6, 5], [8, 7, 7, 6], [8, 8, 7, 7],
+    [7, 6, 6, 5], [7, 7, 6, 5], [8, 7, 7, 6], [8, 7, 7, 6], [9, 8, 7, 7],
+    [7, 6, 6, 5], [8, 7, 6, 6], [8, 7, 7, 6], [8, 8, 7, 6], [9, 8, 8, 7],
+];
+
+/// Alignment pattern centre columns for each symbol width.
+fn get_alignment_columns(width: usize) -> &'static [usize] {
+    match width {
+        43 => &[21],
+        59 => &[19, 39],
+        77 => &[25, 51],
+        99 => &[23, 49, 75],
+        139 => &[27, 55, 83, 111],
+        _ => &[],
+    }
+}
+
+const MODE_INDICATOR_BITS: usize = 3;
+const TERMINATOR_BITS: usize = 3;
+
+/// Version name as printed in the standard, e.g. `R7x43`.
+fn get_version_name(version: usize) -> String {
+    let (height, width) = SIZES[version - 1];
+    format!("R{}x{}", height, width)
+}
+
+/// `(blocks, ec codewords per block)` of `version` at `ecl` (M or H).
+fn get_ec_blocks(version: usize, ecl: EcLevel) -> (usize, usize) {
+    EC_BLOCKS[version - 1][if ecl == EcLevel::H { 1 } else { 0 }]
+}
+
+fn get_data_bits(version: usize, ecl: EcLevel) -> usize {
+    let (blocks, ec_count) = get_ec_blocks(version, ecl);
+    (TOTAL_CODEWORDS[version - 1] - blocks * ec_count) * 8
+}
+
+fn mode_index(mode: Mode) -> usize {
+    match mode {
+        Mode::Numeric => 0,
+        Mode::Alphanumeric => 1,
+        Mode::Byte => 2,
+        Mode::Kanji => 3,
+        Mode::Fnc1First => unreachable!("rMQR Codes are not encoded with FNC1"),
+    }
+}
+
+/// Bits `segment` takes in `version`, or `None` when its length does not fit
+/// the character count indicator.
+fn get_segment_bits(segment: &Segment, version: usize) -> Option<usize> {
+    let cc_bits = CC_BITS[version - 1][mode_index(segment.mode)];
+    if segment.get_length() >= 1 << cc_bits {
+        return None;
+    }
+    Some(MODE_INDICATOR_BITS + cc_bits + segment.get_bits_length())
+}
+
+/// Resolve the version constraints, counting R7x43 to R17x139 as 1 to 32.
+fn version_range(options: &CreateOptions) -> Result<RangeInclusive<usize>, QrError> {
+    for v in [options.version, options.min_version, options.max_version].into_iter().flatten() {
+        if !(1..=VERSION_COUNT).contains(&v) {
+            return Err(QrError::InvalidOption(format!(
+                "Invalid rMQR Code version: {}. Expected 1 to 32 (R7x43 to R17x139)",
+                v
+            )));
+        }
+    }
+
+    if let Some(v) = options.version {
+        return Ok(v..=v);
+    }
+
+    let min = options.min_version.unwrap_or(1);
+    let max = options.max_version.unwrap_or(VERSION_COUNT);
+    if min > max {
+        return Err(QrError::InvalidOption(format!(
+            "Invalid rMQR Code version range: minimum {} is greater than maximum {}",
+            min, max
+        )));
+    }
+    Ok(min..=max)
+}
+
+/// `QrError::DataTooLong` for `segment`, counting its bits against the
+/// version in `versions` with the most data bits at `ecl`.
+fn data_too_long(segment: &Segment, ecl: EcLevel, versions: RangeInclusive<usize>, message: String) -> QrError {
+    let version = versions.max_by_key(|&v| get_data_bits(v, ecl)).unwrap_or(VERSION_COUNT);
+    QrError::DataTooLong {
+        message,
+        needed: MODE_INDICATOR_BITS
+            + CC_BITS[version - 1][mode_index(segment.mode)]
+            + segment.get_bits_length(),
+        available: get_data_bits(version, ecl),
+    }
+}
+
+/// Smallest version by area in `versions` that holds `segment` at `ecl`,
+/// preferring the shorter symbol on ties.
+fn get_best_version(segment: &Segment, ecl: EcLevel, versions: RangeInclusive<usize>) -> Option<usize> {
+    versions
+        .filter(|&v| get_segment_bits(segment, v).is_some_and(|bits| bits <= get_data_bits(v, ecl)))
+        .min_by_key(|&v| {
+            let (height, width) = SIZES[v - 1];
+            (height * width, height)
+        })
+}
+
+// ---------------------------------------------------------------------------
+// Data and error correction codewords
+// ---------------------------------------------------------------------------
+
+/// Encode `segment`, pad it to the data capacity and interleave the error
+/// correction blocks.
+fn create_data(version: usize, ecl: EcLevel, segment: &Segment) -> Vec<u8> {
+    let capacity = get_data_bits(version, ecl);
+    let cc_bits = CC_BITS[version - 1][mode_index(segment.mode)];
+
+    let mut buffer = BitBuffer::new();
+    buffer.put(mode_index(segment.mode) as u32 + 1, MODE_INDICATOR_BITS);
+    buffer.put(segment.get_length() as u32, cc_bits);
+    segment.write(&mut buffer);
+
+    let terminator = TERMINATOR_BITS.min(capacity - buffer.get_length_in_bits());
+    buffer.put(0, terminator);
+
+    while !buffer.get_length_in_bits().is_multiple_of(8) {
+        buffer.put_bit(false);
+    }
+    let mut i = 0;
+    while buffer.get_length_in_bits() < capacity {
+        buffer.put(if i % 2 != 0 { 0x11 } else { 0xEC }, 8);
+        i += 1;
+    }
+
+    let (blocks, ec_count) = get_ec_blocks(version, ecl);
+    interleave_codewords(&buffer, TOTAL_CODEWORDS[version - 1], blocks * ec_count, blocks)
+}
+
+// ---------------------------------------------------------------------------
+// Matrix assembly
+// ---------------------------------------------------------------------------
+
+/// Timing patterns, alignment patterns, finder and sub-finder patterns,
+/// corner patterns and the reserved format information areas.
+fn setup_function_patterns(matrix: &mut BitMatrix) {
+    let (width, height) = (matrix.width, matrix.height);
+
+    // Timing patterns along the edges and down every alignment column.
+    for col in 0..width {
+        matrix.set(0, col, col % 2 == 0, ModuleRole::Timing);
+        matrix.set(height - 1, col, col % 2 == 0, ModuleRole::Timing);
+    }
+    for row in 0..height {
+        matrix.set(row, 0, row % 2 == 0, ModuleRole::Timing);
+        matrix.set(row, width - 1, row % 2 == 0, ModuleRole::Timing);
+    }
+    for &center in get_alignment_columns(width) {
+        for row in 3..height - 3 {
+            matrix.set(row, center, row % 2 == 0, ModuleRole::Timing);
+        }
+        // 3x3 alignment patterns on the top and bottom edges.
+        for top in [0, height - 3] {
+            for r in 0..3 {
+                for c in 0..3 {
+                    matrix.set(top + r, center - 1 + c, r != 1 || c != 1, ModuleRole::Alignment);
+                }
+            }
+        }
+    }
+
+    // Finder pattern with its separator, clipped to the symbol height.
+    for r in 0..8.min(height) {
+        for c in 0..8 {
+            let dark = ((0..=6).contains(&r) && (c == 0 || c == 6))
+                || ((0..=6).contains(&c) && (r == 0 || r == 6))
+                || ((2..=4).contains(&r) && (2..=4).contains(&c));
+            matrix.set(r, c, dark, ModuleRole::Finder);
+        }
+    }
+
+    // Sub-finder pattern in the bottom right corner.
+    for r in 0..5 {
+        for c in 0..5 {
+            let dark = r == 0 || r == 4 || c == 0 || c == 4 || (r == 2 && c == 2);
+            matrix.set(height - 5 + r, width - 5 + c, dark, ModuleRole::Finder);
+        }
+    }
+
+    // Corner patterns in the top right and bottom left corners.
+    for c in width - 3..width {
+        matrix.set(0, c, true, ModuleRole::Finder);
+    }
+    matrix.set(1, width - 1, true, ModuleRole::Finder);
+    matrix.set(1, width - 2, false, ModuleRole::Finder);
+    for c in 0..3 {
+        matrix.set(height - 1, c, true, ModuleRole::Finder);
+    }
+    matrix.set(height - 2, 0, true, ModuleRole::Finder);
+    if height > 9 {
+        matrix.set(height - 2, 1, false, ModuleRole::Finder);
+    }
+
+    // Format information next to the finder and the sub-finder.
+    for r in 1..=5 {
+        for c in 8..=10 {
+            matrix.set(r, c, false, ModuleRole::Format);
+        }
+        for c in width - 8..=width - 6 {
+            matrix.set(height - 7 + r, c, false, ModuleRole::Format);
+        }
+    }
+    for i in 0..3 {
+        matrix.set(1 + i, 11, false, ModuleRole::Format);
+        matrix.set(height - 6, width - 5 + i, false, ModuleRole::Format);
+    }
+}
+
+/// Place the codewords in two-module columns, zig-zagging from the bottom
+/// right corner and skipping function modules. Leftover remainder modules
+/// stay light.
+fn setup_data(matrix: &mut BitMatrix, data: &[u8], roles: &[ModuleRole]) {
+    let (width, height) = (matrix.width, matrix.height);
+    let total_bits = data.len() * 8;
+    let mut bit_index = 0usize;
+    let mut upward = true;
+
+    let mut col = width - 2;
+    while col > 0 {
+        for i in 0..height {
+            let row = if upward { height - 1 - i } else { i };
+            for c in [col, col - 1] {
+                if matrix.is_reserved(row, c) {
+                    continue;
+                }
+                let dark = bit_index < total_bits && (data[bit_index / 8] >> (7 - bit_index % 8)) & 1 == 1;
+                let role = roles.get(bit_index / 8).copied().unwrap_or(ModuleRole::Remainder);
+                matrix.set(row, c, dark, role);
+                bit_index += 1;
+            }
+        }
+        upward = !upward;
+        col = col.saturating_sub(2);
+    }
+}
+
+/// Apply the single rMQR mask, `(row / 2 + col / 3) % 2 == 0`.
+fn apply_mask(matrix: &mut BitMatrix) {
+    matrix.apply_mask(|row, col| (row / 2 + col / 3).is_multiple_of(2));
+}
+
+/// 18-bit format information: the level bit and the version index followed
+/// by a (18, 6) BCH code, before the per-copy mask.
+fn format_get_encoded_bits(version: usize, ecl: EcLevel) -> u32 {
+    let g18: u32 = (1 << 12) | (1 << 11) | (1 << 10) | (1 << 9) | (1 << 8) | (1 << 5) | (1 << 2) | 1;
+    let g18_bch = get_bch_digit(g18);
+
+    let data = (if ecl == EcLevel::H { 1 << 5 } else { 0 }) | (version as u32 - 1);
+    let mut d = data << 12;
+
+    while get_bch_digit(d) - g18_bch >= 0 {
+        d ^= g18 << (get_bch_digit(d) - g18_bch);
+    }
+
+    (data << 12) | d
+}
+
+/// Write both copies of the format information. Bits 0-14 fill a 3x5 block
+/// column by column and bits 15-17 the three extra modules.
+fn setup_format_info(matrix: &mut BitMatrix, version: usize, ecl: EcLevel) {
+    let (width, height) = (matrix.width, matrix.height);
+    let bits = format_get_encoded_bits(version, ecl);
+    let left = bits ^ 0x1FAB2;
+    let right = bits ^ 0x20A7B;
+
+    for i in 0..15usize {
+        matrix.set(1 + i % 5, 8 + i / 5, (left >> i) & 1 == 1, ModuleRole::Format);
+        matrix.set(height - 6 + i % 5, width - 8 + i / 5, (right >> i) & 1 == 1, ModuleRole::Format);
+    }
+    for i in 0..3usize {
+        matrix.set(1 + i, 11, (left >> (15 + i)) & 1 == 1, ModuleRole::Format);
+        matrix.set(height - 6, width - 5 + i, (right >> (15 + i)) & 1 == 1, ModuleRole::Format);
+    }
+}
+
+/// rMQR counterpart of `nodeqr::create_symbol`. `options.version`,
+/// `min_version` and `max_version` count R7x43 to R17x139 as 1 to 32.
+pub fn create(data: QrData, ecl: EcLevel, options: &CreateOptions) -> Result<Symbol, QrError> {
+    if options.mask_pattern.is_some() {
+        return Err(QrError::InvalidOption(
+            "rMQR Codes use a single fixed mask pattern".to_string(),
+        ));
+    }
+
+    check_input_present(&data)?;
+
+    if options.eci.is_some() {
+        return Err(QrError::InvalidOption(
+            "rMQR Codes do not support ECI".to_string(),
+        ));
+    }
+
+    if options.gs1 {
+        return Err(QrError::InvalidOption(
+            "rMQR Codes do not support GS1 data".to_string(),
+        ));
+    }
+
+    if options.boost_ecl {
+        return Err(QrError::InvalidOption(
+            "rMQR Codes do not support boosting the error correction level".to_string(),
+        ));
+    }
+
+    if !matches!(ecl, EcLevel::M | EcLevel::H) {
+        return Err(QrError::InvalidOption(
+            "rMQR Codes support error correction levels M and H only".to_string(),
+        ));
+    }
+
+    let versions = version_range(options)?;
+    let segment = get_segment(data, options.kanji)?;
+
+    let version = match get_best_version(&segment, ecl, versions.clone()) {
+        Some(v) => v,
+        None => {
+            let message = match get_best_version(&segment, ecl, 1..=VERSION_COUNT) {
+                Some(required) => format!(
+                    "The allowed rMQR Code versions ({} to {}) cannot contain this amount of data. \
+                     Smallest version able to store current data is: {} ({})",
+                    versions.start(),
+                    versions.end(),
+                    required,
+                    get_version_name(required)
+                ),
+                None => "The amount of data is too big to be stored in an rMQR Code".to_string(),
+            };
+            return Err(data_too_long(&segment, ecl, versions, message));
+        }
+    };
+
+    let codewords = create_data(version, ecl, &segment);
+
+    let (height, width) = SIZES[version - 1];
+    let mut modules = BitMatrix::with_dimensions(width, height);
+    setup_function_patterns(&mut modules);
+    let (blocks, ec_count) = get_ec_blocks(version, ecl);
+    let roles = codeword_roles(TOTAL_CODEWORDS[version - 1], blocks * ec_count, blocks);
+    setup_data(&mut modules, &codewords, &roles);
+    apply_mask(&mut modules);
+    setup_format_info(&mut modules, version, ecl);
+
+    Ok(Symbol {
+        modules,
+        version,
+        ecl,
+        mask_pattern: None,
+    })
+}
@@ -0,0 +1,290 @@
+//! Promise-returning variants of the generators, rasterizers and decoders.
+//! Each runs its synchronous counterpart on the libuv threadpool through an
+//! `AsyncTask`, so large rasters and photo decoding leave the event loop free.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::error::{settle, Result};
+use crate::{AnimationFrame, BatchResult, DecodeResult, QrCodeSvgOptions, QrOptions, QrOptionsWithBuffer};
+
+/// Work moved to the threadpool, run once by `Task::compute`.
+pub(crate) type Job<T> = Box<dyn FnOnce() -> Result<T> + Send>;
+
+/// Run the job, keeping its error for `settle` so the rejection keeps its
+/// `code`: `Task::compute` can only fail with a `napi::Status`.
+fn run<T>(job: &mut Option<Job<T>>) -> napi::Result<Result<T>> {
+    let job = job
+        .take()
+        .ok_or_else(|| napi::Error::from_reason("Task already ran"))?;
+    Ok(job())
+}
+
+pub struct SvgTask(Option<Job<String>>);
+
+#[napi]
+impl Task for SvgTask {
+    type Output = Result<String>;
+    type JsValue = String;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        run(&mut self.0)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        settle(&env, output)
+    }
+}
+
+pub struct ImageTask(Option<Job<Buffer>>);
+
+#[napi]
+impl Task for ImageTask {
+    type Output = Result<Buffer>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        run(&mut self.0)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        settle(&env, output)
+    }
+}
+
+pub struct DecodeTask(Option<Job<Option<String>>>);
+
+#[napi]
+impl Task for DecodeTask {
+    type Output = Result<Option<String>>;
+    type JsValue = Option<String>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        run(&mut self.0)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        settle(&env, output)
+    }
+}
+
+pub struct DecodeDetailedTask(Option<Job<DecodeResult>>);
+
+#[napi]
+impl Task for DecodeDetailedTask {
+    type Output = Result<DecodeResult>;
+    type JsValue = DecodeResult;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        run(&mut self.0)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        settle(&env, output)
+    }
+}
+
+/// Runs a whole `generate_batch` on one threadpool thread, which fans the
+/// items out to the rayon pool.
+pub struct BatchTask(pub(crate) Option<Job<Vec<BatchResult>>>);
+
+#[napi]
+impl Task for BatchTask {
+    type Output = Result<Vec<BatchResult>>;
+    type JsValue = Vec<BatchResult>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        run(&mut self.0)
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        settle(&env, output)
+    }
+}
+
+/// `generate_qr_svg` on the threadpool.
+#[napi]
+pub fn generate_qr_svg_async(options: QrOptions) -> AsyncTask<SvgTask> {
+    AsyncTask::new(SvgTask(Some(Box::new(move || crate::qr_svg(options)))))
+}
+
+/// `generate_qr_svg_with_buffer` on the threadpool.
+#[napi]
+pub fn generate_qr_svg_with_buffer_async(options: QrOptionsWithBuffer) -> AsyncTask<SvgTask> {
+    AsyncTask::new(SvgTask(Some(Box::new(move || {
+        crate::qr_svg_with_buffer(options)
+    }))))
+}
+
+/// `generate_qr_code_svg` on the threadpool.
+#[napi]
+pub fn generate_qr_code_svg_async(options: QrCodeSvgOptions) -> AsyncTask<SvgTask> {
+    AsyncTask::new(SvgTask(Some(Box::new(move || crate::qr_code_svg(options)))))
+}
+
+/// `generate_qr_image` on the threadpool.
+#[napi]
+pub fn generate_qr_image_async(
+    options: QrOptions,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::qr_image(options, format, quality, lossless, effort, speed)
+    }))))
+}
+
+/// `generate_qr_image_with_buffer` on the threadpool.
+#[napi]
+pub fn generate_qr_image_with_buffer_async(
+    options: QrOptionsWithBuffer,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::qr_image_with_buffer(options, format, quality, lossless, effort, speed)
+    }))))
+}
+
+/// `generate_qr_code_image` on the threadpool.
+#[napi]
+pub fn generate_qr_code_image_async(
+    options: QrCodeSvgOptions,
+    format: String,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+    speed: Option<u8>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::qr_code_image(options, format, quality, lossless, effort, speed)
+    }))))
+}
+
+/// `generate_qr_pdf` on the threadpool.
+#[napi]
+pub fn generate_qr_pdf_async(
+    options: QrOptions,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::qr_pdf(options, width, unit)
+    }))))
+}
+
+/// `generate_qr_pdf_with_buffer` on the threadpool.
+#[napi]
+pub fn generate_qr_pdf_with_buffer_async(
+    options: QrOptionsWithBuffer,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::qr_pdf_with_buffer(options, width, unit)
+    }))))
+}
+
+/// `generate_qr_code_pdf` on the threadpool.
+#[napi]
+pub fn generate_qr_code_pdf_async(
+    options: QrCodeSvgOptions,
+    width: Option<f64>,
+    unit: Option<String>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::qr_code_pdf(options, width, unit)
+    }))))
+}
+
+/// `convert_svg_to_png` on the threadpool.
+#[napi]
+pub fn convert_svg_to_png_async(
+    svg_content: String,
+    width: Option<u32>,
+    height: Option<u32>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::convert_svg_to_png(svg_content, width, height)
+    }))))
+}
+
+/// `convert_svg_to_jpeg` on the threadpool.
+#[napi]
+pub fn convert_svg_to_jpeg_async(
+    svg_content: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    quality: Option<u8>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::convert_svg_to_jpeg(svg_content, width, height, quality)
+    }))))
+}
+
+/// `convert_svg_to_webp` on the threadpool.
+#[napi]
+pub fn convert_svg_to_webp_async(
+    svg_content: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    quality: Option<u8>,
+    lossless: Option<bool>,
+    effort: Option<u8>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::convert_svg_to_webp(svg_content, width, height, quality, lossless, effort)
+    }))))
+}
+
+/// `convert_svg_to_avif` on the threadpool.
+#[napi]
+pub fn convert_svg_to_avif_async(
+    svg_content: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    quality: Option<u8>,
+    speed: Option<u8>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::convert_svg_to_avif(svg_content, width, height, quality, speed)
+    }))))
+}
+
+/// `encode_animation` on the threadpool.
+#[napi]
+pub fn encode_animation_async(
+    frames: Vec<AnimationFrame>,
+    format: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    loop_count: Option<u32>,
+) -> AsyncTask<ImageTask> {
+    AsyncTask::new(ImageTask(Some(Box::new(move || {
+        crate::encode_animation(frames, format, width, height, loop_count)
+    }))))
+}
+
+/// `decode` on the threadpool.
+#[napi]
+pub fn decode_async(input: Buffer) -> AsyncTask<DecodeTask> {
+    AsyncTask::new(DecodeTask(Some(Box::new(move || crate::decode(input)))))
+}
+
+/// `decode_detailed` on the threadpool.
+#[napi]
+pub fn decode_detailed_async(input: Buffer) -> AsyncTask<DecodeDetailedTask> {
+    AsyncTask::new(DecodeDetailedTask(Some(Box::new(move || crate::decode_detailed(input)))))
+}
+
+/// `validate_qr` on the threadpool.
+#[napi]
+pub fn validate_qr_async(input: Buffer) -> AsyncTask<DecodeDetailedTask> {
+    AsyncTask::new(DecodeDetailedTask(Some(Box::new(move || crate::validate_qr(input)))))
+}
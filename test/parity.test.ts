@@ -91,6 +91,38 @@ describe("node-qrcode SVG parity", () => {
 		}
 	});
 
+	it("matches node-qrcode when the version is pinned", async () => {
+		for (const text of inputs.slice(0, 8)) {
+			for (const version of [10, 27]) {
+				const qr = new QrBit({ text, version, cache: false });
+				const mine = await qr.toSvg();
+				const theirs = await QRCode.toString(text, {
+					type: "svg",
+					width: 200,
+					errorCorrectionLevel: "H",
+					version,
+				});
+				expect(mine, `text=${text} version=${version}`).toEqual(theirs);
+			}
+		}
+	});
+
+	it("matches node-qrcode when the mask pattern is forced", async () => {
+		for (const text of inputs.slice(0, 4)) {
+			for (let maskPattern = 0; maskPattern < 8; maskPattern++) {
+				const qr = new QrBit({ text, maskPattern, cache: false });
+				const mine = await qr.toSvg();
+				const theirs = await QRCode.toString(text, {
+					type: "svg",
+					width: 200,
+					errorCorrectionLevel: "H",
+					maskPattern: maskPattern as QRCode.QRCodeMaskPattern,
+				});
+				expect(mine, `text=${text} mask=${maskPattern}`).toEqual(theirs);
+			}
+		}
+	});
+
 	it("accepts full error-correction names identically to initials", async () => {
 		const text = "https://qrbit.dev";
 		const pairs: Array<[ECLevel, "L" | "M" | "Q" | "H"]> = [
@@ -2,10 +2,23 @@ import fs from "node:fs";
 import { faker } from "@faker-js/faker";
 import { Cacheable } from "cacheable";
 import { afterEach, beforeEach, describe, expect, it, vi } from "vitest";
-import { QrBit } from "../src/qrbit";
+import {
+	type BatchFormat,
+	ModuleRole,
+	QrBit,
+	QrBitErrorCode,
+	type QrSegment,
+} from "../src/qrbit";
 
 const testLogoPath = "test/fixtures/test_logo_large.png";
 const testLogoPathSmall = "test/fixtures/test_logo_small.png";
+// A module grid saved as one line of 0 and 1 per row, like `toMatrixText()`.
+const readMatrixFixture = (name: string): number[][] =>
+	fs
+		.readFileSync(`test/fixtures/${name}`, "utf8")
+		.trim()
+		.split("\n")
+		.map((row) => [...row].map(Number));
 
 describe("QrBit Class", () => {
 	it("should create a QrBit instance with default options", () => {
@@ -325,6 +338,785 @@ describe("Error Correction", () => {
 	});
 });
 
+describe("Version Constraints", () => {
+	it("should get and set version properties", () => {
+		const qr = new QrBit({ text: "Hello World", version: 5 });
+		expect(qr.version).toBe(5);
+		expect(qr.minVersion).toBeUndefined();
+		expect(qr.maxVersion).toBeUndefined();
+
+		qr.version = undefined;
+		qr.minVersion = 3;
+		qr.maxVersion = 10;
+		expect(qr.version).toBeUndefined();
+		expect(qr.minVersion).toBe(3);
+		expect(qr.maxVersion).toBe(10);
+	});
+
+	it("should pin the version on both rendering paths", async () => {
+		const text = "hello";
+		for (const logo of [undefined, testLogoPathSmall]) {
+			const qr = new QrBit({ text, version: 7, logo, cache: false });
+			const png = await qr.toPng();
+			const result = await QrBit.decodeDetailed(png);
+			expect(result.data).toBe(text);
+			expect(result.version).toBe(7);
+		}
+	});
+
+	it("should honor the minimum version", async () => {
+		const qr = new QrBit({ text: "hello", minVersion: 4, cache: false });
+		const result = await QrBit.decodeDetailed(await qr.toPng());
+		expect(result.version).toBe(4);
+	});
+
+	it("should throw when the data does not fit the allowed versions", async () => {
+		const text = "A".repeat(200);
+		const pinned = new QrBit({ text, version: 2, cache: false });
+		await expect(pinned.toSvg()).rejects.toThrow(/Minimum version required/);
+
+		const bounded = new QrBit({ text, maxVersion: 3, cache: false });
+		await expect(bounded.toSvg()).rejects.toThrow(/Minimum version required/);
+	});
+
+	it("should throw on an invalid version", async () => {
+		const qr = new QrBit({ text: "hello", version: 41, cache: false });
+		await expect(qr.toSvg()).rejects.toThrow(/Invalid QR Code version/);
+	});
+});
+
+describe("Mask Pattern", () => {
+	it("should get and set maskPattern property", () => {
+		const qr = new QrBit({ text: "Hello World", maskPattern: 2 });
+		expect(qr.maskPattern).toBe(2);
+		qr.maskPattern = undefined;
+		expect(qr.maskPattern).toBeUndefined();
+	});
+
+	it("should report a penalty for every mask pattern", () => {
+		const qr = new QrBit({ text: "https://example.com", version: 3 });
+		const result = qr.getMaskPenalties();
+		expect(result.version).toBe(3);
+		expect(result.penalties).toHaveLength(8);
+		expect(result.penalties[result.bestMaskPattern]).toBe(
+			Math.min(...result.penalties),
+		);
+	});
+
+	it("should match the automatic choice when forcing the best mask", async () => {
+		const text = "https://example.com";
+		const auto = new QrBit({ text, cache: false });
+		const { bestMaskPattern } = auto.getMaskPenalties();
+		const forced = new QrBit({ text, maskPattern: bestMaskPattern, cache: false });
+		expect(await forced.toSvg()).toEqual(await auto.toSvg());
+	});
+
+	it("should produce scannable codes for every forced mask", async () => {
+		const text = "mask test";
+		const svgs = new Set<string>();
+		for (let maskPattern = 0; maskPattern < 8; maskPattern++) {
+			const qr = new QrBit({ text, maskPattern, cache: false });
+			svgs.add(await qr.toSvg());
+			expect(await QrBit.decode(await qr.toPng())).toBe(text);
+		}
+		expect(svgs.size).toBe(8);
+	});
+
+	it("should throw on an invalid mask pattern", async () => {
+		const qr = new QrBit({ text: "hello", maskPattern: 8, cache: false });
+		await expect(qr.toSvg()).rejects.toThrow(/Invalid mask pattern/);
+	});
+});
+
+describe("Kanji Mode", () => {
+	const text = "日本語のテキスト、こんにちは世界";
+
+	it("should get and set kanji property", () => {
+		const qr = new QrBit({ text });
+		expect(qr.kanji).toBe(false);
+		qr.kanji = true;
+		expect(qr.kanji).toBe(true);
+	});
+
+	it("should produce a smaller, scannable code for Japanese text", async () => {
+		const byteMode = new QrBit({ text, errorCorrection: "M", cache: false });
+		const kanjiMode = new QrBit({
+			text,
+			errorCorrection: "M",
+			kanji: true,
+			cache: false,
+		});
+
+		const byteResult = await QrBit.decodeDetailed(await byteMode.toPng());
+		const kanjiResult = await QrBit.decodeDetailed(await kanjiMode.toPng());
+
+		expect(kanjiResult.data).toBe(text);
+		expect(kanjiResult.version).toBeLessThan(byteResult.version as number);
+	});
+
+	it("should mix Kanji with numeric and alphanumeric segments", async () => {
+		const mixed = "ORDER 12345 東京タワー";
+		const qr = new QrBit({ text: mixed, kanji: true, cache: false });
+		expect(await QrBit.decode(await qr.toPng())).toBe(mixed);
+	});
+
+	it("should decode UTF-8 Byte segments next to Kanji segments", async () => {
+		const mixed = "東京タワー café über";
+		const qr = new QrBit({ text: mixed, kanji: true, cache: false });
+		expect(await QrBit.decode(await qr.toPng())).toBe(mixed);
+
+		const manual = new QrBit({
+			segments: [
+				{ mode: "byte", data: "Grüße " },
+				{ mode: "kanji", data: "漢字" },
+				{ mode: "byte", data: " naïve" },
+			],
+			version: 12,
+			cache: false,
+		});
+		expect(await QrBit.decode(await manual.toPng())).toBe("Grüße 漢字 naïve");
+	});
+
+	it("should not change output for text without Kanji characters", async () => {
+		const latin = "https://example.com";
+		const plain = new QrBit({ text: latin, cache: false });
+		const kanji = new QrBit({ text: latin, kanji: true, cache: false });
+		expect(await kanji.toSvg()).toEqual(await plain.toSvg());
+	});
+});
+
+describe("ECI", () => {
+	it("should get and set eci property", () => {
+		const qr = new QrBit({ text: "hello" });
+		expect(qr.eci).toBeUndefined();
+		qr.eci = 26;
+		expect(qr.eci).toBe(26);
+	});
+
+	it("should generate scannable codes with a UTF-8 ECI header", async () => {
+		for (const text of ["héllo wörld café", "日本語のテキスト", "12345"]) {
+			const qr = new QrBit({ text, eci: 26, cache: false });
+			expect(await QrBit.decode(await qr.toPng())).toBe(text);
+		}
+	});
+
+	it("should change the output only when an ECI is set", async () => {
+		const text = "héllo wörld café";
+		const plain = new QrBit({ text, cache: false });
+		const eci = new QrBit({ text, eci: 26, cache: false });
+		expect(await eci.toSvg()).not.toEqual(await plain.toSvg());
+	});
+
+	it("should throw on an invalid ECI assignment number", async () => {
+		const qr = new QrBit({ text: "hello", eci: 1_000_000, cache: false });
+		await expect(qr.toSvg()).rejects.toThrow(/Invalid ECI/);
+	});
+});
+
+describe("Binary Data", () => {
+	it("should get and set data property", () => {
+		const qr = new QrBit({ data: new Uint8Array([1, 2, 3]) });
+		expect(Buffer.isBuffer(qr.data)).toBe(true);
+		expect(qr.text).toBe("");
+		qr.data = undefined;
+		expect(qr.data).toBeUndefined();
+	});
+
+	it("should encode raw bytes on both rendering paths", async () => {
+		const data = Buffer.from("binary payload ✓", "utf8");
+		for (const logo of [undefined, testLogoPathSmall]) {
+			const qr = new QrBit({ data, logo, cache: false });
+			expect(await QrBit.decode(await qr.toPng())).toBe("binary payload ✓");
+		}
+	});
+
+	it("should encode bytes that are not valid UTF-8", async () => {
+		const data = Buffer.from([0x00, 0xff, 0xfe, 0x80, 0x7f, 0x10, 0xc3]);
+		const qr = new QrBit({ data, cache: false });
+		const result = await QrBit.decodeDetailed(await qr.toPng());
+		expect(result.valid).toBe(true);
+		expect(result.version).toBe(1);
+	});
+
+	it("should prefer data over text", async () => {
+		const qr = new QrBit({
+			text: "ignored",
+			data: Buffer.from("used"),
+			cache: false,
+		});
+		expect(await QrBit.decode(await qr.toPng())).toBe("used");
+	});
+
+	it("should throw when data is empty", async () => {
+		const qr = new QrBit({ data: Buffer.alloc(0), cache: false });
+		await expect(qr.toSvg()).rejects.toThrow(/No input data/);
+	});
+});
+
+describe("Structured Append", () => {
+	const longText = "Structured Append splits one payload. ".repeat(20);
+
+	it("should return a single plain symbol when the text fits", async () => {
+		const qr = new QrBit({ text: "Hello World", cache: false });
+		const parts = await qr.toSvgParts();
+		expect(parts).toHaveLength(1);
+		expect(parts[0]).toBe(await qr.toSvg());
+	});
+
+	it("should split long text across symbols of the same size", async () => {
+		const qr = new QrBit({ text: longText, maxVersion: 5, cache: false });
+		const parts = await qr.toSvgParts();
+		expect(parts.length).toBeGreaterThan(1);
+		expect(parts.length).toBeLessThanOrEqual(16);
+		const viewBoxes = parts.map((svg) => svg.match(/viewBox="[^"]*"/)?.[0]);
+		expect(new Set(viewBoxes).size).toBe(1);
+	});
+
+	it("should return one PNG per symbol", async () => {
+		const qr = new QrBit({ text: longText, maxVersion: 5, cache: false });
+		const svgs = await qr.toSvgParts();
+		const pngs = await qr.toPngParts();
+		expect(pngs).toHaveLength(svgs.length);
+		for (const png of pngs) {
+			expect(png.subarray(1, 4).toString()).toBe("PNG");
+		}
+	});
+
+	it("should lay the symbols out on a sheet", async () => {
+		const qr = new QrBit({
+			text: longText,
+			maxVersion: 5,
+			size: 100,
+			cache: false,
+		});
+		const parts = await qr.toSvgParts();
+		const sheet = await qr.toSvgSheet({ columns: parts.length });
+		expect(sheet).toContain(`width="${100 * parts.length}" height="100"`);
+		expect(sheet.match(/transform="translate/g)).toHaveLength(parts.length);
+		const png = await qr.toPngSheet();
+		expect(png.subarray(1, 4).toString()).toBe("PNG");
+	});
+
+	it("should cycle the symbols in an animated GIF or APNG", async () => {
+		const qr = new QrBit({
+			text: longText,
+			maxVersion: 5,
+			size: 100,
+			cache: false,
+		});
+		const parts = await qr.toSvgParts();
+
+		const gif = await qr.toAnimatedParts({ delay: 500 });
+		expect(gif.subarray(0, 6).toString()).toBe("GIF89a");
+		expect(gif.includes("NETSCAPE2.0")).toBe(true);
+
+		// acTL holds the frame count and the number of plays
+		const apng = await qr.toAnimatedParts({ format: "apng", loop: 2 });
+		const acTL = apng.indexOf("acTL");
+		expect(apng.subarray(1, 4).toString()).toBe("PNG");
+		expect(apng.readUInt32BE(acTL + 4)).toBe(parts.length);
+		expect(apng.readUInt32BE(acTL + 8)).toBe(2);
+
+		await expect(QrBit.encodeAnimation([])).rejects.toMatchObject({
+			code: QrBitErrorCode.invalidOption,
+		});
+	});
+
+	it("should not show the previous GIF frame through transparent pixels", async () => {
+		const svgs = await Promise.all(
+			["first frame", "the second frame"].map((text) =>
+				new QrBit({ text, backgroundColor: "#00000000", cache: false }).toSvg(),
+			),
+		);
+		const gif = await QrBit.encodeAnimation(
+			svgs.map((svg) => ({ svg })),
+			{ width: 120, height: 120 },
+		);
+		// Walk the GIF blocks and collect each Graphic Control Extension's
+		// disposal method: 2 restores the background (transparent) before the
+		// next frame is drawn, so the first frame can't show through
+		const disposals: number[] = [];
+		const skipSubBlocks = (pos: number) => {
+			while (gif[pos] !== 0) pos += gif[pos] + 1;
+			return pos + 1;
+		};
+		const colorTable = (flags: number) =>
+			flags & 0x80 ? 3 * 2 ** ((flags & 7) + 1) : 0;
+		let pos = 13 + colorTable(gif[10]);
+		while (gif[pos] !== 0x3b) {
+			if (gif[pos] === 0x21) {
+				if (gif[pos + 1] === 0xf9) disposals.push((gif[pos + 3] >> 2) & 7);
+				pos = skipSubBlocks(pos + 2);
+			} else {
+				expect(gif[pos]).toBe(0x2c);
+				pos = skipSubBlocks(pos + 11 + colorTable(gif[pos + 9]));
+			}
+		}
+		expect(disposals).toEqual([2, 2]);
+	});
+
+	it("should throw when 16 symbols cannot hold the data", async () => {
+		const qr = new QrBit({ text: "x".repeat(2000), maxVersion: 1 });
+		await expect(qr.toSvgParts()).rejects.toThrow(
+			/16 Structured Append QR Codes/,
+		);
+	});
+});
+
+describe("Micro QR", () => {
+	const viewBoxSize = (svg: string) =>
+		Number(svg.match(/viewBox="0 0 (\d+)/)?.[1]);
+
+	it("should get and set symbology property", () => {
+		const qr = new QrBit({ text: "1" });
+		expect(qr.symbology).toBe("qr");
+		qr.symbology = "microqr";
+		expect(qr.symbology).toBe("microqr");
+	});
+
+	it("should generate the smallest Micro QR symbol with a 2-module quiet zone", async () => {
+		const qr = new QrBit({
+			text: "12345",
+			symbology: "microqr",
+			errorCorrection: "L",
+			cache: false,
+		});
+		// M1 is 11x11 modules
+		expect(viewBoxSize(await qr.toSvg())).toBe(11 + 2 * 2);
+	});
+
+	it("should grow to M4 for longer text", async () => {
+		const qr = new QrBit({
+			text: "https://a.io/x",
+			symbology: "microqr",
+			errorCorrection: "L",
+			cache: false,
+		});
+		expect(viewBoxSize(await qr.toSvg())).toBe(17 + 2 * 2);
+	});
+
+	it("should render on the logo and raster paths", async () => {
+		const qr = new QrBit({
+			text: "A-1042",
+			symbology: "microqr",
+			logo: testLogoPathSmall,
+			cache: false,
+		});
+		expect(await qr.toSvg()).toContain("<svg");
+		const png = await qr.toPng();
+		expect(png.subarray(1, 4).toString()).toBe("PNG");
+	});
+
+	it("should throw when the data does not fit in M4", async () => {
+		const qr = new QrBit({ text: "x".repeat(40), symbology: "microqr" });
+		await expect(qr.toSvg()).rejects.toThrow(/Micro QR Code/);
+	});
+
+	it("should reject error correction level H", async () => {
+		const qr = new QrBit({
+			text: "1",
+			symbology: "microqr",
+			errorCorrection: "H",
+		});
+		await expect(qr.toSvg()).rejects.toThrow(/L, M and Q only/);
+	});
+
+	// The ISO/IEC 18004 worked example: "01234567" as M2-L encodes the data
+	// codewords 40 18 AC C3 00 with error correction 86 0D 22 AE 30, mask 1.
+	it("should match the golden M2-L matrix of the standard's example", () => {
+		const matrix = new QrBit({
+			text: "01234567",
+			symbology: "microqr",
+			version: 2,
+			errorCorrection: "L",
+		}).toMatrix();
+		expect(matrix.maskPattern).toBe(1);
+		expect(matrix.rows).toEqual(readMatrixFixture("microqr_m2_l.txt"));
+	});
+});
+
+describe("rMQR", () => {
+	const viewBox = (svg: string) =>
+		svg
+			.match(/viewBox="0 0 (\d+) (\d+)"/)
+			?.slice(1)
+			.map(Number);
+
+	it("should generate a rectangular symbol with a 2-module quiet zone", async () => {
+		const qr = new QrBit({
+			text: "LOT-2024-0042",
+			symbology: "rmqr",
+			errorCorrection: "M",
+			cache: false,
+		});
+		// R13x27 is 27 modules wide and 13 high
+		expect(viewBox(await qr.toSvg())).toEqual([27 + 2 * 2, 13 + 2 * 2]);
+	});
+
+	it("should honor a fixed version", async () => {
+		const qr = new QrBit({
+			text: "1",
+			symbology: "rmqr",
+			version: 1,
+			cache: false,
+		});
+		// Version 1 is R7x43
+		expect(viewBox(await qr.toSvg())).toEqual([43 + 2 * 2, 7 + 2 * 2]);
+	});
+
+	it("should keep the aspect ratio on the logo and raster paths", async () => {
+		const qr = new QrBit({
+			text: "LOT-2024-0042",
+			symbology: "rmqr",
+			errorCorrection: "M",
+			logo: testLogoPathSmall,
+			size: 270,
+			margin: 0,
+			cache: false,
+		});
+		const svg = await qr.toSvg();
+		expect(svg).toContain('width="270"');
+		expect(svg).toContain('height="130"');
+		const png = await qr.toPng();
+		expect(png.readUInt32BE(16)).toBeGreaterThan(png.readUInt32BE(20));
+	});
+
+	it("should reject error correction level L", async () => {
+		const qr = new QrBit({
+			text: "1",
+			symbology: "rmqr",
+			errorCorrection: "L",
+		});
+		await expect(qr.toSvg()).rejects.toThrow(/M and H only/);
+	});
+
+	// The bundled decoder can't read rMQR. These grids were read back by a
+	// separate reader written from ISO/IEC 23941, which checked the format
+	// information in both corners, the Reed-Solomon blocks and the text.
+	it("should match the golden R7x43-M matrix", () => {
+		const qr = new QrBit({
+			text: "123456",
+			symbology: "rmqr",
+			version: 1,
+			errorCorrection: "M",
+		});
+		expect(qr.toMatrix().rows).toEqual(
+			readMatrixFixture("rmqr_r7x43_m.txt"),
+		);
+	});
+
+	it("should match the golden multi-block R17x139-H matrix", () => {
+		const qr = new QrBit({
+			text: "RMQR R17X139-H GOLDEN",
+			symbology: "rmqr",
+			version: 32,
+			errorCorrection: "H",
+		});
+		expect(qr.toMatrix().rows).toEqual(
+			readMatrixFixture("rmqr_r17x139_h.txt"),
+		);
+	});
+});
+
+describe("GS1", () => {
+	const gs1 = "(01)09506000134352(17)251231(10)ABC123";
+
+	it("should get and set gs1 property", () => {
+		const qr = new QrBit({ gs1 });
+		expect(qr.gs1).toBe(gs1);
+		qr.gs1 = undefined;
+		expect(qr.gs1).toBeUndefined();
+	});
+
+	it("should generate a GS1 QR Code from an element string", async () => {
+		const qr = new QrBit({ gs1, cache: false });
+		const svg = await qr.toSvg();
+		expect(svg).toContain("<svg");
+		const plain = await new QrBit({
+			text: "01095060001343521725123110ABC123",
+			cache: false,
+		}).toSvg();
+		expect(svg).not.toBe(plain);
+	});
+
+	it("should reject a wrong GTIN check digit", async () => {
+		const qr = new QrBit({ gs1: "(01)09506000134353" });
+		await expect(qr.toSvg()).rejects.toThrow(/check digit: expected 2/);
+	});
+
+	it("should reject an invalid expiry date", async () => {
+		const qr = new QrBit({ gs1: "(17)251301" });
+		await expect(qr.toSvg()).rejects.toThrow(/month 13 is out of range/);
+	});
+
+	it("should reject unsupported Application Identifiers", async () => {
+		const qr = new QrBit({ gs1: "(99)ABC" });
+		await expect(qr.toSvg()).rejects.toThrow(/Unsupported GS1/);
+	});
+});
+
+describe("boostEcl", () => {
+	it("should get and set boostEcl property", () => {
+		const qr = new QrBit({ text: "1" });
+		expect(qr.boostEcl).toBe(false);
+		qr.boostEcl = true;
+		expect(qr.boostEcl).toBe(true);
+	});
+
+	it("should report the requested level when not boosting", () => {
+		const qr = new QrBit({ text: "Hello World", errorCorrection: "L" });
+		expect(qr.getErrorCorrectionLevel()).toBe("L");
+	});
+
+	it("should raise the level while the version stays the same", () => {
+		const qr = new QrBit({
+			text: "Hello World",
+			errorCorrection: "L",
+			boostEcl: true,
+		});
+		expect(qr.getErrorCorrectionLevel()).toBe("Q");
+		expect(qr.getMaskPenalties().version).toBe(1);
+	});
+
+	it("should encode with the boosted level", async () => {
+		const boosted = new QrBit({
+			text: "hi",
+			errorCorrection: "L",
+			boostEcl: true,
+			cache: false,
+		});
+		const high = new QrBit({ text: "hi", errorCorrection: "H", cache: false });
+		expect(await boosted.toSvg()).toBe(await high.toSvg());
+	});
+});
+
+describe("Manual segments", () => {
+	const segments: QrSegment[] = [
+		{ mode: "alphanumeric", data: "INVOICE-" },
+		{ mode: "numeric", data: "000123456789" },
+	];
+
+	it("should get and set segments property", () => {
+		const qr = new QrBit({ segments });
+		expect(qr.segments).toEqual(segments);
+		qr.segments = undefined;
+		expect(qr.segments).toBeUndefined();
+	});
+
+	it("should encode the segments in order", async () => {
+		const qr = new QrBit({ segments, cache: false });
+		expect(await QrBit.decode(await qr.toPng())).toBe("INVOICE-000123456789");
+	});
+
+	it("should reject characters the mode cannot encode", async () => {
+		const qr = new QrBit({ segments: [{ mode: "numeric", data: "12a" }] });
+		await expect(qr.toSvg()).rejects.toThrow(/Suggested mode is: Byte/);
+	});
+
+	it("should allow a single segment in a Micro QR Code", async () => {
+		const qr = new QrBit({
+			segments: [{ mode: "numeric", data: "12345" }],
+			symbology: "microqr",
+			cache: false,
+		});
+		expect(await qr.toSvg()).toContain("<svg");
+		qr.segments = segments;
+		await expect(qr.toSvg()).rejects.toThrow(/single segment/);
+	});
+});
+
+describe("inspect", () => {
+	it("should report the version, segments and bits used", () => {
+		const qr = new QrBit({
+			text: "HELLO WORLD 12345678901234",
+			errorCorrection: "M",
+		});
+		const plan = qr.inspect();
+		expect(plan.version).toBe(2);
+		expect(plan.errorCorrection).toBe("M");
+		expect(plan.segments).toEqual([
+			{ mode: "Alphanumeric", data: "HELLO WORLD ", length: 12, bits: 79 },
+			{ mode: "Numeric", data: "12345678901234", length: 14, bits: 61 },
+		]);
+		expect(plan.totalBits).toBe(140);
+		expect(plan.remaining.bits).toBe(84);
+		expect(plan.maskPattern).toBe(qr.getMaskPenalties().bestMaskPattern);
+	});
+
+	it("should report the capacity at every error correction level", () => {
+		const plan = new QrBit({ text: "1" }).inspect();
+		expect(plan.capacities.map((c) => c.errorCorrection)).toEqual([
+			"L",
+			"M",
+			"Q",
+			"H",
+		]);
+		expect(plan.capacities[0].capacity.numeric).toBe(41);
+		expect(plan.capacities[3].capacity.byte).toBe(7);
+	});
+
+	it("should count what still fits in the version", () => {
+		const qr = new QrBit({ text: "ABC", errorCorrection: "L" });
+		const { remaining } = qr.inspect();
+		qr.segments = [
+			{ mode: "alphanumeric", data: "ABC" },
+			{ mode: "numeric", data: "1".repeat(remaining.numeric) },
+		];
+		expect(qr.inspect().version).toBe(1);
+		qr.segments = [
+			{ mode: "alphanumeric", data: "ABC" },
+			{ mode: "numeric", data: "1".repeat(remaining.numeric + 1) },
+		];
+		expect(qr.inspect().version).toBe(2);
+	});
+});
+
+describe("Matrix", () => {
+	it("should return the module grid with the encoder's choices", () => {
+		const qr = new QrBit({ text: "hello", errorCorrection: "Q" });
+		const matrix = qr.toMatrix();
+		expect(matrix.size).toBe(21);
+		expect(matrix.height).toBe(21);
+		expect(matrix.version).toBe(1);
+		expect(matrix.errorCorrection).toBe("Q");
+		expect(matrix.maskPattern).toBe(qr.getMaskPenalties().bestMaskPattern);
+		expect(matrix.rows).toHaveLength(21);
+		expect(matrix.rows[0].slice(0, 7)).toEqual([1, 1, 1, 1, 1, 1, 1]);
+	});
+
+	it("should pack 8 modules per byte, most significant bit first", () => {
+		const { size, packed, rows } = new QrBit({ text: "hello" }).toMatrix();
+		const rowBytes = Math.ceil(size / 8);
+		expect(packed.length).toBe(rowBytes * size);
+		for (let y = 0; y < size; y++) {
+			for (let x = 0; x < size; x++) {
+				const bit = (packed[y * rowBytes + (x >> 3)] >> (7 - (x & 7))) & 1;
+				expect(bit).toBe(rows[y][x]);
+			}
+		}
+	});
+
+	it("should export the grid as JSON and as text", () => {
+		const qr = new QrBit({ text: "hello" });
+		const { rows } = qr.toMatrix();
+		expect(JSON.parse(qr.toMatrixJson()).rows).toEqual(rows);
+		expect(qr.toMatrixText()).toBe(
+			rows.map((row) => `${row.join("")}\n`).join(""),
+		);
+	});
+
+	it("should omit the mask pattern for rMQR", () => {
+		const qr = new QrBit({
+			text: "AB12",
+			symbology: "rmqr",
+			errorCorrection: "M",
+		});
+		const matrix = qr.toMatrix();
+		expect(matrix.height).toBeLessThan(matrix.size);
+		expect(matrix.maskPattern).toBeUndefined();
+	});
+
+	it("should classify every module by role and block", () => {
+		const { size, roles, blocks } = new QrBit({
+			text: "x".repeat(300),
+			errorCorrection: "H",
+		}).toMatrix();
+		const count = (role: ModuleRole) => roles.filter((r) => r === role).length;
+		expect(roles.length).toBe(size * size);
+		expect(roles[0]).toBe(ModuleRole.finder);
+		expect(roles[6 * size + 10]).toBe(ModuleRole.timing);
+		expect(count(ModuleRole.format)).toBe(31);
+		expect(count(ModuleRole.version)).toBe(36);
+		// Version 18-H: 1156 codewords, 588 of them EC, in 21 blocks.
+		expect(count(ModuleRole.data)).toBe(568 * 8);
+		expect(count(ModuleRole.errorCorrection)).toBe(588 * 8);
+		expect(count(ModuleRole.remainder)).toBe(3);
+		expect(Math.max(...blocks)).toBe(20);
+	});
+});
+
+describe("Error codes", () => {
+	it("should report data that does not fit with the bits needed and available", async () => {
+		const qr = new QrBit({ text: "abc".repeat(100), version: 3 });
+		await expect(qr.toSvg()).rejects.toMatchObject({
+			code: QrBitErrorCode.dataTooLong,
+			needed: 2412,
+			available: 352,
+		});
+		expect(() => qr.toMatrix()).toThrow(
+			expect.objectContaining({
+				code: QrBitErrorCode.dataTooLong,
+				needed: 2412,
+				available: 352,
+			}),
+		);
+	});
+
+	it("should tag invalid options, colors and segments", async () => {
+		await expect(
+			new QrBit({ text: "hello", maskPattern: 9 }).toSvg(),
+		).rejects.toMatchObject({ code: QrBitErrorCode.invalidOption });
+		await expect(
+			new QrBit({ text: "hello", foregroundColor: "#12" }).toSvg(),
+		).rejects.toMatchObject({ code: QrBitErrorCode.invalidColor });
+		await expect(
+			new QrBit({ segments: [{ mode: "numeric", data: "ab" }] }).toSvg(),
+		).rejects.toMatchObject({ code: QrBitErrorCode.encode });
+	});
+
+	it("should tag unreadable logos, SVGs and images", async () => {
+		const qr = new QrBit({ text: "hello", logo: Buffer.from("not an image") });
+		await expect(qr.toSvg()).rejects.toMatchObject({
+			code: QrBitErrorCode.logoDecode,
+		});
+		expect(() => QrBit.convertSvgToPng("<svg")).toThrow(
+			expect.objectContaining({ code: QrBitErrorCode.svgParse }),
+		);
+		await expect(QrBit.decode(Buffer.from("nope"))).rejects.toMatchObject({
+			code: QrBitErrorCode.decode,
+		});
+	});
+});
+
+describe("Batch generation", () => {
+	it("should render each item like toSvg and toPng with the shared style", async () => {
+		const style = { size: 240, foregroundColor: "#1F2937" };
+		const texts = ["ticket-1", "ticket-2", "ticket-3"];
+
+		const svgs = await QrBit.generateBatch(
+			texts.map((text) => ({ text })),
+			style,
+		);
+		expect(svgs.map((result) => result.output)).toEqual(
+			await Promise.all(
+				texts.map((text) => new QrBit({ text, ...style, cache: false }).toSvg()),
+			),
+		);
+
+		const pngs = await QrBit.generateBatch([{ text: "ticket-4" }], style, "png");
+		expect(await QrBit.decode(pngs[0].output as Buffer)).toBe("ticket-4");
+	});
+
+	it("should report per-item errors without failing the batch", async () => {
+		const results = await QrBit.generateBatch(
+			[{ text: "ok" }, { text: "abc".repeat(100) }, {}],
+			{ maxVersion: 3 },
+		);
+		expect(typeof results[0].output).toBe("string");
+		expect(results[1]).toMatchObject({
+			code: QrBitErrorCode.dataTooLong,
+			needed: 2412,
+			available: 352,
+		});
+		expect(results[1].output).toBeUndefined();
+		expect(results[2]).toMatchObject({ code: QrBitErrorCode.invalidOption });
+
+		await expect(
+			QrBit.generateBatch([{ text: "ok" }], {}, "gif" as BatchFormat),
+		).rejects.toMatchObject({ code: QrBitErrorCode.invalidOption });
+	});
+});
+
 describe("Edge Cases", () => {
 	it("should handle very long text", async () => {
 		const longText = "A".repeat(1000);
@@ -817,6 +1609,128 @@ describe("SVG to PNG Conversion", () => {
 			QrBit.convertSvgToPng(invalidSvg);
 		}).toThrow();
 	});
+
+	it("should convert on the threadpool to the same bytes as the sync converters", async () => {
+		const svg = await new QrBit({ text: "async", size: 300 }).toSvg();
+
+		expect(await QrBit.convertSvgToPngAsync(svg)).toEqual(
+			QrBit.convertSvgToPng(svg),
+		);
+		expect(await QrBit.convertSvgToJpegAsync(svg, 150, 150, 80)).toEqual(
+			QrBit.convertSvgToJpeg(svg, 150, 150, 80),
+		);
+		expect(await QrBit.convertSvgToWebpAsync(svg)).toEqual(
+			QrBit.convertSvgToWebp(svg),
+		);
+	});
+
+	it("should reject async conversions with the error code", async () => {
+		await expect(QrBit.convertSvgToPngAsync("<svg")).rejects.toMatchObject({
+			code: QrBitErrorCode.svgParse,
+		});
+	});
+
+	it("should encode AVIF with the alpha channel", async () => {
+		const svg =
+			'<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40"><circle cx="20" cy="20" r="15" fill="red"/></svg>';
+
+		const avif = QrBit.convertSvgToAvif(svg, undefined, undefined, 70, 10);
+		expect(avif.toString("ascii", 4, 12)).toBe("ftypavif");
+		expect(avif.includes("urn:mpeg:mpegB:cicp:systems:auxiliary:alpha")).toBe(
+			true,
+		);
+		expect(
+			await QrBit.convertSvgToAvifAsync(svg, undefined, undefined, 70, 10),
+		).toEqual(avif);
+
+		const qr = new QrBit({ text: "avif", size: 100, margin: 2 });
+		const fromQr = await qr.toAvif({ speed: 10 });
+		expect(fromQr.toString("ascii", 4, 12)).toBe("ftypavif");
+	});
+});
+
+describe("Direct raster rendering", () => {
+	// PNG IHDR: width and height at bytes 16 and 20, color type at byte 25.
+	const pngHeader = (png: Buffer) => ({
+		width: png.readUInt32BE(16),
+		height: png.readUInt32BE(20),
+		colorType: png[25],
+	});
+
+	it("should paint PNGs the size of the converted SVG, in grayscale for gray colors", async () => {
+		for (const options of [
+			{ text: "direct raster", size: 333 },
+			{ text: "12345", size: 300, symbology: "rmqr" as const },
+			{ text: "logo", size: 300, margin: 15, logo: testLogoPath },
+		]) {
+			const qr = new QrBit({ ...options, cache: false });
+			const png = await qr.toPng();
+			const converted = QrBit.convertSvgToPng(await qr.toSvg());
+
+			expect(pngHeader(png).width).toBe(pngHeader(converted).width);
+			expect(pngHeader(png).height).toBe(pngHeader(converted).height);
+		}
+
+		const gray = await new QrBit({ text: "gray" }).toPng();
+		expect(pngHeader(gray).colorType).toBe(0);
+		expect(await QrBit.decode(gray)).toBe("gray");
+		const color = await new QrBit({ text: "color", foregroundColor: "#1D4ED8" }).toPng();
+		expect(pngHeader(color).colorType).toBe(6);
+		expect(await QrBit.decode(color)).toBe("color");
+	});
+
+	it("should paint logos directly and keep the result scannable", async () => {
+		const logo = fs.readFileSync(testLogoPath);
+		for (const qr of [
+			new QrBit({ text: "buffer logo", logo }),
+			new QrBit({ text: "file logo", logo: testLogoPath }),
+		]) {
+			expect(await QrBit.decode(await qr.toPng())).toBe(qr.text);
+			expect(await QrBit.decode(await qr.toJpg())).toBe(qr.text);
+			expect(await QrBit.decode(await qr.toWebp())).toBe(qr.text);
+		}
+	});
+});
+
+describe("PDF output", () => {
+	it("should write a vector page sized from the physical width", async () => {
+		const qr = new QrBit({ text: "hello pdf", size: 240, cache: false });
+		const pdf = await qr.toPdf();
+		expect(pdf.subarray(0, 5).toString()).toBe("%PDF-");
+		expect(pdf.toString("latin1")).toContain("/MediaBox [0 0 180 180]");
+		expect(pdf.toString("latin1")).not.toContain("/Subtype /Image");
+
+		const label = await qr.toPdf({ width: 50, unit: "mm" });
+		expect(label.toString("latin1")).toMatch(/\/MediaBox \[0 0 141\.7/);
+	});
+
+	it("should embed the logo as an image from a path or a buffer", async () => {
+		const fromPath = await new QrBit({
+			text: "pdf logo",
+			logo: testLogoPath,
+		}).toPdf();
+		const fromBuffer = await new QrBit({
+			text: "pdf logo",
+			logo: fs.readFileSync(testLogoPath),
+		}).toPdf();
+		expect(fromPath.toString("latin1")).toContain("/Subtype /Image");
+		expect(fromBuffer.equals(fromPath)).toBe(true);
+	});
+
+	it("should reject unsupported units", async () => {
+		const qr = new QrBit({ text: "pdf unit" });
+		await expect(
+			qr.toPdf({ width: 2, unit: "ft" as "mm" }),
+		).rejects.toMatchObject({ code: QrBitErrorCode.invalidOption });
+	});
+
+	it("should reject a logo file it can't read", async () => {
+		const qr = new QrBit({ text: "pdf logo", logo: "/non/existent/logo.png" });
+		qr.on("error", () => {});
+		await expect(qr.toPdf({ cache: false })).rejects.toMatchObject({
+			code: QrBitErrorCode.invalidOption,
+		});
+	});
 });
 
 describe("QrBit Decode", () => {
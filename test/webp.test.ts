@@ -23,6 +23,10 @@ const isValidWebP = (buffer: Buffer): boolean => {
 	);
 };
 
+// The first chunk after the RIFF header: "VP8L" for lossless, "VP8 " for
+// lossy, "VP8X" for the extended format that carries a lossy alpha image.
+const webpChunk = (buffer: Buffer): string => buffer.toString("ascii", 12, 16);
+
 describe("WebP Generation", () => {
 	it("should generate WebP output", async () => {
 		const text = faker.internet.url();
@@ -44,7 +48,15 @@ describe("WebP Generation", () => {
 		expect(webp.length).toBeGreaterThan(0);
 	});
 
-	it("should generate WebP with quality parameter (reserved for future use)", async () => {
+	it("should generate lossless WebP by default", async () => {
+		const text = faker.internet.url();
+		const qr = new QrBit({ text });
+		const webp = await qr.toWebp();
+
+		expect(webpChunk(webp)).toBe("VP8L");
+	});
+
+	it("should generate lossy WebP with quality parameter", async () => {
 		const text = faker.internet.url();
 		const qr = new QrBit({ text });
 		const webp = await qr.toWebp({ quality: 95 });
@@ -53,15 +65,17 @@ describe("WebP Generation", () => {
 		expect(webp.length).toBeGreaterThan(0);
 		// Check WebP signature
 		expect(isValidWebP(webp)).toBe(true);
+		expect(webpChunk(webp)).toBe("VP8 ");
 	});
 
-	it("should generate WebP with low quality parameter (reserved for future use)", async () => {
+	it("should generate lossy WebP with low quality parameter", async () => {
 		const text = faker.internet.url();
 		const qr = new QrBit({ text });
 		const webp = await qr.toWebp({ quality: 60 });
 
 		expect(webp).toBeInstanceOf(Buffer);
-		expect(webp.length).toBeGreaterThan(0);
+		expect(webpChunk(webp)).toBe("VP8 ");
+		expect(await QrBit.decode(webp)).toBe(text);
 	});
 
 	it("should generate WebP with logo path", async () => {
@@ -247,7 +261,7 @@ describe("WebP Caching", () => {
 
 		expect(webp1).toEqual(webp2);
 
-		const cacheKey = await qr.generateCacheKey("napi-webp-90");
+		const cacheKey = await qr.generateCacheKey("napi-webp-lossless-4");
 		expect(qr.cache).toBeDefined();
 		expect(qr.cache).toBeInstanceOf(Cacheable);
 		const has = await qr.cache?.has(cacheKey);
@@ -263,29 +277,32 @@ describe("WebP Caching", () => {
 
 		expect(webp1).toEqual(webp2);
 
-		const cacheKey = await qr.generateCacheKey("napi-webp-90");
+		const cacheKey = await qr.generateCacheKey("napi-webp-lossless-4");
 		expect(qr.cache).toBeDefined();
 		expect(qr.cache).toBeInstanceOf(Cacheable);
 		const has = await qr.cache?.has(cacheKey);
 		expect(has).toBe(false);
 	});
 
-	it("should cache different quality keys separately", async () => {
+	it("should cache different quality and effort keys separately", async () => {
 		const text = faker.internet.url();
 		const qr = new QrBit({ text });
 
 		await qr.toWebp({ quality: 90 });
 		await qr.toWebp({ quality: 60 });
-
-		// Both should be cached with different keys
-		const cacheKey90 = await qr.generateCacheKey("napi-webp-90");
-		const cacheKey60 = await qr.generateCacheKey("napi-webp-60");
-
-		const has90 = await qr.cache?.has(cacheKey90);
-		const has60 = await qr.cache?.has(cacheKey60);
-
-		expect(has90).toBe(true);
-		expect(has60).toBe(true);
+		await qr.toWebp({ quality: 60, effort: 6 });
+		await qr.toWebp({ quality: 60, lossless: true });
+
+		// Each should be cached with a different key
+		const cacheKey90 = await qr.generateCacheKey("napi-webp-90-4");
+		const cacheKey60 = await qr.generateCacheKey("napi-webp-60-4");
+		const cacheKeyEffort = await qr.generateCacheKey("napi-webp-60-6");
+		const cacheKeyLossless = await qr.generateCacheKey("napi-webp-lossless-4");
+
+		expect(await qr.cache?.has(cacheKey90)).toBe(true);
+		expect(await qr.cache?.has(cacheKey60)).toBe(true);
+		expect(await qr.cache?.has(cacheKeyEffort)).toBe(true);
+		expect(await qr.cache?.has(cacheKeyLossless)).toBe(true);
 	});
 
 	it("should use cache instance when provided", async () => {
@@ -295,7 +312,7 @@ describe("WebP Caching", () => {
 
 		await qr.toWebp();
 
-		const cacheKey = await qr.generateCacheKey("napi-webp-90");
+		const cacheKey = await qr.generateCacheKey("napi-webp-lossless-4");
 		const has = await cache.has(cacheKey);
 		expect(has).toBe(true);
 	});
@@ -336,16 +353,21 @@ describe("Static WebP Conversion", () => {
 		expect(isValidWebP(webpBuffer)).toBe(true);
 	});
 
-	it("should convert SVG to WebP with quality parameter (reserved)", () => {
+	it("should convert SVG to lossy WebP with quality parameter", () => {
 		const svgContent =
 			'<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"><rect width="100" height="100" fill="green"/></svg>';
 
 		const webpHigh = QrBit.convertSvgToWebp(svgContent, 100, 100, 95);
 		const webpLow = QrBit.convertSvgToWebp(svgContent, 100, 100, 60);
+		const webpLossless = QrBit.convertSvgToWebp(svgContent, 100, 100);
 
-		// Both should be valid WebP (lossless encoding means same output)
+		// Each quality is encoded lossy, so the outputs differ
 		expect(isValidWebP(webpHigh)).toBe(true);
 		expect(isValidWebP(webpLow)).toBe(true);
+		expect(webpChunk(webpHigh)).toBe("VP8 ");
+		expect(webpChunk(webpLow)).toBe("VP8 ");
+		expect(webpHigh.equals(webpLow)).toBe(false);
+		expect(webpChunk(webpLossless)).toBe("VP8L");
 	});
 
 	it("should handle invalid SVG content gracefully", () => {
@@ -404,18 +426,49 @@ describe("WebP with Various QR Options", () => {
 	});
 });
 
-describe("WebP Lossless Encoding", () => {
-	it("should produce consistent output regardless of quality parameter", async () => {
+describe("WebP Lossy Encoding", () => {
+	it("should produce different output for different quality settings", async () => {
 		const text = faker.internet.url();
-		const qr = new QrBit({ text });
+		const qr = new QrBit({ text, cache: false });
 
-		// Since WebP uses lossless encoding, quality parameter is reserved for future use
-		// Currently, output should be the same regardless of quality setting
 		const webp90 = await qr.toWebp({ quality: 90 });
 		const webp60 = await qr.toWebp({ quality: 60 });
 
-		// Both should be valid WebP
 		expect(isValidWebP(webp90)).toBe(true);
 		expect(isValidWebP(webp60)).toBe(true);
+		expect(webp90.equals(webp60)).toBe(false);
+	});
+
+	it("should stay lossless when lossless is set with a quality", async () => {
+		const text = faker.internet.url();
+		const qr = new QrBit({ text, cache: false });
+
+		const forced = await qr.toWebp({ quality: 60, lossless: true });
+		expect(webpChunk(forced)).toBe("VP8L");
+		expect(forced.equals(await qr.toWebp())).toBe(true);
+	});
+
+	it("should encode lossy WebP with a quality and keep the alpha channel", async () => {
+		const svg =
+			'<svg xmlns="http://www.w3.org/2000/svg" width="40" height="40"><circle cx="20" cy="20" r="15" fill="red"/></svg>';
+
+		const lossless = QrBit.convertSvgToWebp(svg);
+		expect(webpChunk(lossless)).toBe("VP8L");
+
+		// Extended format: a lossless ALPH chunk next to the lossy VP8 image
+		const lossy = QrBit.convertSvgToWebp(svg, undefined, undefined, 75);
+		expect(webpChunk(lossy)).toBe("VP8X");
+		expect(lossy.includes("ALPH")).toBe(true);
+		expect(lossy.includes("VP8 ")).toBe(true);
+
+		const forced = QrBit.convertSvgToWebp(
+			svg,
+			undefined,
+			undefined,
+			75,
+			true,
+			6,
+		);
+		expect(webpChunk(forced)).toBe("VP8L");
 	});
 });